@@ -11,6 +11,7 @@ const PanePortal: React.FC<{ paneId: string; children: React.ReactNode }> = ({ p
 };
 
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { Server, Session, ConnectionStatus, Pane, Tab, SplitNode, AppSessionState, TabState, PaneState, AppSettings } from './types';
 import { simpleCn, generateId, isTauri } from './utils';
 import { TerminalSession } from './components/Terminal/TerminalSession';
@@ -18,9 +19,14 @@ import { SplitPane } from './components/Layout/SplitPane';
 
 import { NewConnectionModal } from './components/NewConnectionModal';
 import { SettingsModal } from './components/SettingsModal';
+import { FanoutModal } from './components/FanoutModal';
 import { SplashScreen } from './components/SplashScreen';
 import { RightSidebar } from './components/RightSidebar';
 import { AIModal } from './components/AIModal';
+import { HostKeyPrompt } from './components/Prompts/HostKeyPrompt';
+import { PassphrasePrompt } from './components/Prompts/PassphrasePrompt';
+import { KeyboardInteractivePrompt } from './components/Prompts/KeyboardInteractivePrompt';
+import { AgentConfirmPrompt } from './components/Prompts/AgentConfirmPrompt';
 import { UnlockScreen } from './components/Security/UnlockScreen';
 import { SetupMasterPassword } from './components/Security/SetupMasterPassword';
 import { Button } from './components/ui/Button';
@@ -95,6 +101,16 @@ function AppContent() {
     checkSecurity();
   }, []); // Run only once on mount
 
+  // The backend locks itself after its own idle timeout or on lock_app, and
+  // has already forgotten the vault and agent keys by then
+  useEffect(() => {
+    if (!isTauri()) return;
+    const unlisten = listen('app_locked', () => setIsLocked(true));
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
 
   // Use split panes hook
   const {
@@ -194,6 +210,7 @@ function AppContent() {
   const [isNewConnectionModalOpen, setIsNewConnectionModalOpen] = useState(false);
   const [editingServer, setEditingServer] = useState<Server | null>(null);
   const [isSettingsModalOpen, setIsSettingsModalOpen] = useState(false);
+  const [isFanoutModalOpen, setIsFanoutModalOpen] = useState(false);
   const [showSplash, setShowSplash] = useState(true);
 
 
@@ -267,12 +284,15 @@ function AppContent() {
           host: newServer.host,
           port: parseInt(newServer.port?.toString() || '22'),
           username: newServer.username,
-          password: newServer.password || null,
+          password: newServer.password ?? null, // '' removes the saved password
           id: typeof newServer.id === 'number' ? newServer.id : null, // Use existing ID for updates
-          private_key_path: null,
+          private_key_path: newServer.private_key_path || null,
+          key_id: newServer.key_id ?? null,
           group: newServer.group,
           tags: newServer.tags,
           color: newServer.color,
+          forward_agent: newServer.forward_agent ?? false,
+          record_sessions: newServer.record_sessions ?? false,
           created_at: Date.now(),
           updated_at: Date.now(),
         }
@@ -444,6 +464,7 @@ function AppContent() {
           onNewLocal={handleNewLocalTerminal}
           onNewConnection={() => setIsNewConnectionModalOpen(true)}
           onOpenSettings={() => setIsSettingsModalOpen(true)}
+          onRunCommand={() => setIsFanoutModalOpen(true)}
           servers={servers}
           onConnect={handleConnect}
           onEditServer={handleEditServer}
@@ -577,8 +598,24 @@ function AppContent() {
         <SettingsModal
           isOpen={isSettingsModalOpen}
           onClose={() => setIsSettingsModalOpen(false)}
+          onServersChanged={loadServers}
+          sessions={Object.values(sessions)
+            .filter(session => session.serverId !== 'local')
+            .map(session => {
+              const server = servers.find(s => s.id === session.serverId);
+              return { id: session.id, label: server ? `${server.name} (${server.host})` : session.id };
+            })}
+        />
+        <FanoutModal
+          isOpen={isFanoutModalOpen}
+          onClose={() => setIsFanoutModalOpen(false)}
+          servers={servers}
         />
         <AIModal />
+        <HostKeyPrompt />
+        <PassphrasePrompt />
+        <KeyboardInteractivePrompt />
+        <AgentConfirmPrompt />
       </div>
     </div>
   );
@@ -0,0 +1,354 @@
+import React, { useState, useEffect, useCallback, useMemo, useRef } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { Play, Square, Trash2, Plus, CheckCircle2, XCircle, Loader2, Clock } from 'lucide-react';
+import { Modal } from './Modal';
+import { Button } from './ui/Button';
+import { Input } from './ui/Input';
+import { Select } from './ui/Select';
+import { useApp } from '../contexts/AppContext';
+import { Server, ServerSelector, FanoutRun, FanoutHostResult, FanoutRunDetail } from '../types';
+import { simpleCn } from '../utils';
+
+interface FanoutModalProps {
+  isOpen: boolean;
+  onClose: () => void;
+  servers: Server[];
+}
+
+// Progress of a run started in this window, filled from backend events
+interface LiveRun {
+  hosts: { server_id: number; name: string; host: string }[];
+  states: Record<number, string>;
+  output: Record<number, string>;
+  results: Record<number, FanoutHostResult>;
+}
+
+interface HostRow {
+  server_id: number;
+  name: string;
+  host: string;
+  state: string;
+  result?: FanoutHostResult;
+  output: string;
+}
+
+const emptyLive = (): LiveRun => ({ hosts: [], states: {}, output: {}, results: {} });
+
+const hostStatus = (result: FanoutHostResult) => {
+  if (result.cancelled) return 'cancelled';
+  if (result.timed_out) return 'timed_out';
+  if (result.error && result.exit_status === null && !result.exit_signal) return 'error';
+  return result.exit_status === 0 ? 'ok' : 'failed';
+};
+
+export const FanoutModal: React.FC<FanoutModalProps> = ({ isOpen, onClose, servers }) => {
+  const { t } = useApp();
+  const [runs, setRuns] = useState<FanoutRun[]>([]);
+  const [detail, setDetail] = useState<FanoutRunDetail | null>(null);
+  const [live, setLive] = useState<Record<number, LiveRun>>({});
+  const [expanded, setExpanded] = useState<number | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  const [command, setCommand] = useState('');
+  const [group, setGroup] = useState('');
+  const [tags, setTags] = useState('');
+  const [parallelism, setParallelism] = useState(10);
+  const [timeout, setTimeoutSecs] = useState(0);
+  const [targets, setTargets] = useState<Server[]>([]);
+  const decoders = useRef<Record<string, TextDecoder>>({});
+
+  const selector = useMemo<ServerSelector>(() => ({
+    group: group || undefined,
+    tags: tags.split(',').map(tag => tag.trim()).filter(Boolean),
+  }), [group, tags]);
+  const selectorEmpty = !selector.group && selector.tags!.length === 0;
+
+  const groups = useMemo(
+    () => Array.from(new Set(servers.map(s => s.group || ''))).filter(Boolean).sort(),
+    [servers]
+  );
+
+  const refresh = useCallback(() => {
+    invoke<FanoutRun[]>('list_fanout_runs').then(setRuns).catch(console.error);
+  }, []);
+
+  const updateLive = (runId: number, update: (run: LiveRun) => LiveRun) => {
+    setLive(prev => ({ ...prev, [runId]: update(prev[runId] || emptyLive()) }));
+  };
+
+  useEffect(() => {
+    if (isOpen) refresh();
+  }, [isOpen, refresh]);
+
+  // Events are buffered per run, also while closed: the first ones can
+  // arrive before start_fanout has returned the run id
+  useEffect(() => {
+    const unlisteners = [
+      listen<{ run_id: number; server_id: number; state: string }>('fanout_host', ({ payload }) => {
+        updateLive(payload.run_id, run => ({ ...run, states: { ...run.states, [payload.server_id]: payload.state } }));
+      }),
+      listen<{ run_id: number; server_id: number; stream: string; data: number[] }>('fanout_output', ({ payload }) => {
+        const key = `${payload.run_id}:${payload.server_id}`;
+        const decoder = decoders.current[key] || (decoders.current[key] = new TextDecoder());
+        const text = decoder.decode(new Uint8Array(payload.data), { stream: true });
+        updateLive(payload.run_id, run => ({
+          ...run,
+          output: { ...run.output, [payload.server_id]: (run.output[payload.server_id] || '') + text },
+        }));
+      }),
+      listen<{ run_id: number; result: FanoutHostResult }>('fanout_result', ({ payload }) => {
+        updateLive(payload.run_id, run => ({
+          ...run,
+          results: { ...run.results, [payload.result.server_id]: payload.result },
+        }));
+      }),
+      listen<FanoutRun>('fanout_done', ({ payload }) => {
+        setDetail(prev => (prev?.run.id === payload.id ? { ...prev, run: payload } : prev));
+        refresh();
+      }),
+    ];
+    return () => {
+      unlisteners.forEach(unlisten => unlisten.then(fn => fn()));
+    };
+  }, [refresh]);
+
+  useEffect(() => {
+    if (!isOpen || selectorEmpty) {
+      setTargets([]);
+      return;
+    }
+    invoke<Server[]>('preview_fanout_targets', { selector }).then(setTargets).catch(() => setTargets([]));
+  }, [isOpen, selector, selectorEmpty]);
+
+  const start = async () => {
+    setError(null);
+    try {
+      const run = await invoke<FanoutRun>('start_fanout', {
+        command,
+        selector,
+        parallelism,
+        timeoutSecs: timeout > 0 ? timeout : null,
+      });
+      const hosts = targets.map(s => ({ server_id: Number(s.id), name: s.name, host: s.host || '' }));
+      updateLive(run.id, prev => ({ ...prev, hosts }));
+      setDetail({ run, results: [] });
+      setExpanded(null);
+      refresh();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const open = async (run: FanoutRun) => {
+    setExpanded(null);
+    try {
+      setDetail(await invoke<FanoutRunDetail>('get_fanout_run', { runId: run.id }));
+    } catch (e) {
+      console.error('Failed to load run:', e);
+    }
+  };
+
+  const remove = async (run: FanoutRun) => {
+    try {
+      await invoke('delete_fanout_run', { runId: run.id });
+      if (detail?.run.id === run.id) setDetail(null);
+      refresh();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const running = !!detail && detail.run.finished_at === null;
+  const liveRun = detail ? live[detail.run.id] : undefined;
+
+  const rows = useMemo<HostRow[]>(() => {
+    if (!detail) return [];
+    const stored = detail.results.map(result => ({
+      server_id: result.server_id,
+      name: result.server_name,
+      host: result.host,
+      state: hostStatus(result),
+      result,
+      output: result.output,
+    }));
+    if (!liveRun) return stored;
+    return liveRun.hosts.map(host => {
+      const result = liveRun.results[host.server_id] || detail.results.find(r => r.server_id === host.server_id);
+      return {
+        ...host,
+        state: result ? hostStatus(result) : liveRun.states[host.server_id] || 'waiting',
+        result,
+        output: liveRun.output[host.server_id] ?? result?.output ?? '',
+      };
+    });
+  }, [detail, liveRun]);
+
+  const summary = useMemo(() => ({
+    ok: rows.filter(row => row.state === 'ok').length,
+    failed: rows.filter(row => row.result && row.state !== 'ok').length,
+  }), [rows]);
+
+  const statusIcon = (state: string) => {
+    if (state === 'ok') return <CheckCircle2 size={14} className="text-green-500" />;
+    if (state === 'waiting') return <Clock size={14} className="text-slate-400" />;
+    if (state === 'connecting' || state === 'running') return <Loader2 size={14} className="animate-spin text-nebula-500" />;
+    return <XCircle size={14} className="text-red-500" />;
+  };
+
+  return (
+    <Modal isOpen={isOpen} onClose={onClose} title={t('fanout.title')} maxWidth="5xl">
+      <div className="flex gap-4 h-[70vh]">
+        <div className="w-64 flex-shrink-0 flex flex-col gap-2 min-h-0">
+          <Button size="sm" onClick={() => setDetail(null)} className="gap-2">
+            <Plus size={14} /> {t('fanout.new_run')}
+          </Button>
+          <div className="text-[11px] font-bold text-slate-400 uppercase tracking-widest px-1 pt-2">{t('fanout.history')}</div>
+          <div className="flex-1 overflow-y-auto space-y-1">
+            {runs.length === 0 && <div className="text-sm text-slate-400 p-2">{t('fanout.no_runs')}</div>}
+            {runs.map(run => (
+              <div
+                key={run.id}
+                onClick={() => open(run)}
+                className={simpleCn(
+                  "group flex items-center gap-2 px-2 py-1.5 rounded-md cursor-pointer text-sm",
+                  detail?.run.id === run.id
+                    ? "bg-nebula-500/20 text-nebula-600 dark:text-nebula-300"
+                    : "text-slate-600 dark:text-slate-300 hover:bg-slate-100 dark:hover:bg-dark-surface"
+                )}
+              >
+                <div className="min-w-0 flex-1">
+                  <div className="truncate font-mono text-xs">{run.command}</div>
+                  <div className="text-xs text-slate-400">
+                    {new Date(run.started_at * 1000).toLocaleString()} · {run.succeeded}/{run.total}
+                    {run.failed > 0 && <span className="text-red-500"> · {run.failed} {t('fanout.failed')}</span>}
+                  </div>
+                </div>
+                <button
+                  onClick={(e) => { e.stopPropagation(); remove(run); }}
+                  className="opacity-0 group-hover:opacity-100 text-slate-400 hover:text-red-500"
+                >
+                  <Trash2 size={14} />
+                </button>
+              </div>
+            ))}
+          </div>
+        </div>
+
+        <div className="flex-1 min-w-0 flex flex-col gap-3 min-h-0">
+          {error && <div className="text-xs text-red-500 bg-red-500/10 rounded-md px-3 py-2">{error}</div>}
+
+          {!detail ? (
+            <>
+              <textarea
+                value={command}
+                onChange={(e) => setCommand(e.target.value)}
+                placeholder={t('fanout.command_placeholder')}
+                rows={3}
+                className="w-full rounded-lg border px-3 py-2 font-mono text-sm bg-white border-slate-300 text-slate-900 dark:border-dark-border dark:bg-dark-bg dark:text-slate-200 focus:outline-none focus:ring-2 focus:ring-nebula-500/20"
+              />
+              <div className="grid grid-cols-4 gap-3">
+                <Select
+                  label={t('fanout.group')}
+                  value={group}
+                  onChange={(e) => setGroup(e.target.value)}
+                  options={[{ value: '', label: t('fanout.any_group') }, ...groups.map(g => ({ value: g, label: g }))]}
+                />
+                <Input label={t('fanout.tags')} value={tags} onChange={(e) => setTags(e.target.value)} placeholder="web, eu" />
+                <Input
+                  label={t('fanout.parallelism')}
+                  type="number"
+                  min={1}
+                  max={64}
+                  value={parallelism}
+                  onChange={(e) => setParallelism(Math.max(1, Number(e.target.value) || 1))}
+                />
+                <Input
+                  label={t('fanout.timeout')}
+                  type="number"
+                  min={0}
+                  value={timeout}
+                  onChange={(e) => setTimeoutSecs(Math.max(0, Number(e.target.value) || 0))}
+                />
+              </div>
+              <div className="text-xs text-slate-500">
+                {t('fanout.targets')}: {targets.length}
+                {targets.length > 0 && <span className="text-slate-400"> — {targets.map(s => s.name).join(', ')}</span>}
+              </div>
+              <div>
+                <Button onClick={start} disabled={!command.trim() || targets.length === 0} className="gap-2">
+                  <Play size={14} /> {t('fanout.run')}
+                </Button>
+              </div>
+            </>
+          ) : (
+            <>
+              <div className="flex items-center gap-3">
+                <code className="flex-1 truncate text-sm bg-slate-100 dark:bg-dark-surface rounded px-2 py-1">{detail.run.command}</code>
+                <span className="text-xs text-slate-500 tabular-nums">
+                  <span className="text-green-500">{summary.ok} {t('fanout.succeeded')}</span>
+                  {' · '}
+                  <span className={summary.failed ? "text-red-500" : ""}>{summary.failed} {t('fanout.failed')}</span>
+                  {' · '}
+                  {detail.run.total}
+                </span>
+                {running && (
+                  <Button size="sm" variant="danger" onClick={() => invoke('cancel_fanout', { runId: detail.run.id }).catch(console.error)} className="gap-2">
+                    <Square size={12} /> {t('fanout.stop')}
+                  </Button>
+                )}
+              </div>
+              <div className="flex-1 overflow-y-auto border border-slate-200 dark:border-dark-border rounded-md">
+                <table className="w-full text-sm">
+                  <thead className="sticky top-0 bg-slate-50 dark:bg-dark-surface text-xs text-slate-500">
+                    <tr>
+                      <th className="text-left px-3 py-2 font-medium">{t('fanout.host')}</th>
+                      <th className="text-left px-3 py-2 font-medium">{t('fanout.status')}</th>
+                      <th className="text-left px-3 py-2 font-medium">{t('fanout.exit')}</th>
+                      <th className="text-right px-3 py-2 font-medium">{t('fanout.duration')}</th>
+                    </tr>
+                  </thead>
+                  <tbody>
+                    {rows.map(row => (
+                      <React.Fragment key={row.server_id}>
+                        <tr
+                          onClick={() => setExpanded(expanded === row.server_id ? null : row.server_id)}
+                          className="border-t border-slate-100 dark:border-dark-border cursor-pointer hover:bg-slate-50 dark:hover:bg-dark-surface"
+                        >
+                          <td className="px-3 py-1.5">
+                            <div className="text-slate-700 dark:text-slate-200">{row.name}</div>
+                            <div className="text-xs text-slate-400">{row.host}</div>
+                          </td>
+                          <td className="px-3 py-1.5">
+                            <span className="flex items-center gap-1.5 text-xs">
+                              {statusIcon(row.state)} {t(`fanout.state_${row.state}` as any)}
+                            </span>
+                          </td>
+                          <td className="px-3 py-1.5 font-mono text-xs">
+                            {row.result?.exit_status ?? (row.result?.exit_signal ? `SIG${row.result.exit_signal}` : '—')}
+                          </td>
+                          <td className="px-3 py-1.5 text-right text-xs text-slate-400 tabular-nums">
+                            {row.result ? `${(row.result.duration_ms / 1000).toFixed(1)}s` : ''}
+                          </td>
+                        </tr>
+                        {expanded === row.server_id && (
+                          <tr>
+                            <td colSpan={4} className="px-3 pb-2">
+                              {row.result?.error && <div className="text-xs text-red-500 py-1">{row.result.error}</div>}
+                              <pre className="max-h-64 overflow-auto bg-black text-slate-200 text-xs rounded-md p-2 whitespace-pre-wrap">{row.output}</pre>
+                              {row.result?.truncated && <div className="text-xs text-slate-400 pt-1">{t('fanout.truncated')}</div>}
+                            </td>
+                          </tr>
+                        )}
+                      </React.Fragment>
+                    ))}
+                  </tbody>
+                </table>
+              </div>
+            </>
+          )}
+        </div>
+      </div>
+    </Modal>
+  );
+};
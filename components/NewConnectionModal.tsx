@@ -6,11 +6,12 @@ import { Input } from './ui/Input';
 import { Button } from './ui/Button';
 import { ComboBox } from './ui/ComboBox';
 import { Select } from './ui/Select';
-import { Server } from '../types';
+import { CertificateInfo, Server, SshKey } from '../types';
 import { generateId, simpleCn } from '../utils';
 import { useApp } from '../contexts/AppContext';
-import { Check, Eye, EyeOff, Network } from 'lucide-react';
+import { AlertTriangle, BadgeCheck, Check, Copy, Eye, EyeOff, Network, Trash2, Undo2 } from 'lucide-react';
 import { PortForwardingPanel } from './PortForwardingPanel';
+import { MasterPasswordPrompt } from './Prompts/MasterPasswordPrompt';
 import { PortForwardingRule } from '../types';
 
 interface Props {
@@ -34,15 +35,44 @@ export const NewConnectionModal: React.FC<Props> = ({ isOpen, onClose, onSave, e
     tags: '',
     color: 'nebula',
     forwarding_rules: [] as PortForwardingRule[],
-    jump_host_id: undefined as number | undefined
+    jump_host_id: undefined as number | undefined,
+    key_id: undefined as number | undefined,
+    forward_agent: false,
+    record_sessions: false
   });
   const [activeTab, setActiveTab] = useState<'general' | 'tunnels'>('general');
   const [isTestingConnection, setIsTestingConnection] = useState(false);
   const [testResult, setTestResult] = useState<{ success: boolean; message: string } | null>(null);
   const [showPassword, setShowPassword] = useState(false);
+  const [certificate, setCertificate] = useState<CertificateInfo | null>(null);
+  const [keys, setKeys] = useState<SshKey[]>([]);
+  // Saved password of the server being edited: removed on save when set
+  const [clearPassword, setClearPassword] = useState(false);
+  const [pendingSecret, setPendingSecret] = useState<(() => Promise<void>) | null>(null);
+  const [secretNotice, setSecretNotice] = useState('');
+
+  // Keys from the key store a server can log in with
+  useEffect(() => {
+    if (!isOpen) return;
+    invoke<SshKey[]>('get_keys')
+      .then(setKeys)
+      .catch(e => console.error('Failed to load keys:', e));
+  }, [isOpen]);
+
+  // Certificate of the key a saved server logs in with
+  useEffect(() => {
+    setCertificate(null);
+    const serverId = editingServer?.id as unknown;
+    if (!isOpen || typeof serverId !== 'number') return;
+    invoke<CertificateInfo | null>('get_server_certificate', { serverId })
+      .then(setCertificate)
+      .catch(e => console.error('Failed to load certificate:', e));
+  }, [isOpen, editingServer]);
 
   // Initialize form data when editing
   useEffect(() => {
+    setClearPassword(false);
+    setSecretNotice('');
     if (isOpen && editingServer) {
       setFormData({
         name: editingServer.name,
@@ -54,11 +84,14 @@ export const NewConnectionModal: React.FC<Props> = ({ isOpen, onClose, onSave, e
         tags: editingServer.tags?.join(', ') || '',
         color: editingServer.color || 'nebula',
         forwarding_rules: editingServer.forwarding_rules || [],
-        jump_host_id: editingServer.jump_host_id
+        jump_host_id: editingServer.jump_host_id,
+        key_id: editingServer.key_id ?? undefined,
+        forward_agent: editingServer.forward_agent ?? false,
+        record_sessions: editingServer.record_sessions ?? false
       });
     } else if (isOpen && !editingServer) {
       // Reset form for new connection
-      setFormData({ name: '', group: '', host: '', port: '22', username: 'root', password: '', tags: '', color: 'nebula', forwarding_rules: [], jump_host_id: undefined });
+      setFormData({ name: '', group: '', host: '', port: '22', username: 'root', password: '', tags: '', color: 'nebula', forwarding_rules: [], jump_host_id: undefined, key_id: undefined, forward_agent: false, record_sessions: false });
       setActiveTab('general');
     }
   }, [isOpen, editingServer]);
@@ -72,19 +105,60 @@ export const NewConnectionModal: React.FC<Props> = ({ isOpen, onClose, onSave, e
       host: formData.host,
       port: parseInt(formData.port || '22'),
       username: formData.username,
-      password: formData.password,
+      // Blank keeps the saved password, '' removes it
+      password: formData.password || (clearPassword ? '' : undefined),
       tags: formData.tags.split(',').map(t => t.trim()).filter(Boolean),
       color: formData.color,
       lastConnected: editingServer?.lastConnected || 0,
       protocol: editingServer?.protocol || 'ssh',
       forwarding_rules: formData.forwarding_rules,
-      jump_host_id: formData.jump_host_id
+      jump_host_id: formData.jump_host_id,
+      private_key_path: editingServer?.private_key_path,
+      key_id: formData.key_id,
+      forward_agent: formData.forward_agent,
+      record_sessions: formData.record_sessions
     });
     onClose();
-    setFormData({ name: '', group: '', host: '', port: '22', username: 'root', password: '', tags: '', color: 'nebula', forwarding_rules: [], jump_host_id: undefined });
+    setFormData({ name: '', group: '', host: '', port: '22', username: 'root', password: '', tags: '', color: 'nebula', forwarding_rules: [], jump_host_id: undefined, key_id: undefined, forward_agent: false, record_sessions: false });
     setTestResult(null);
   };
 
+  const savedServerId = typeof editingServer?.id === 'number' ? editingServer.id : null;
+  const hasSavedPassword = !!editingServer?.has_password && !clearPassword;
+
+  // Secrets need a recent master password confirmation; ask for one and retry
+  const withVault = async (action: () => Promise<void>) => {
+    setSecretNotice('');
+    try {
+      await action();
+    } catch (e) {
+      if (String(e).includes('Confirm the master password')) {
+        setPendingSecret(() => action);
+      } else {
+        setSecretNotice(String(e));
+      }
+    }
+  };
+
+  const revealPassword = () => withVault(async () => {
+    const value = await invoke<string | null>('reveal_credential', { serverId: savedServerId, field: 'password' });
+    setFormData(prev => ({ ...prev, password: value || '' }));
+    setShowPassword(true);
+  });
+
+  const copyPassword = () => withVault(async () => {
+    const { value, clear_after_secs } = await invoke<{ value: string; clear_after_secs: number }>(
+      'copy_credential', { serverId: savedServerId, field: 'password' }
+    );
+    await navigator.clipboard.writeText(value);
+    setSecretNotice(t('modal.password_copied').replace('{secs}', String(clear_after_secs)));
+    setTimeout(async () => {
+      // Leave the clipboard alone if something else was copied since
+      const current = await navigator.clipboard.readText().catch(() => value);
+      if (current === value) navigator.clipboard.writeText('').catch(() => {});
+    }, clear_after_secs * 1000);
+  });
+
   const handleTestConnection = async () => {
     if (!formData.host || !formData.username) {
       setTestResult({ success: false, message: 'Host and username are required' });
@@ -100,6 +174,8 @@ export const NewConnectionModal: React.FC<Props> = ({ isOpen, onClose, onSave, e
         port: parseInt(formData.port || '22'),
         username: formData.username,
         password: formData.password || null,
+        // Fall back to the saved password unless it is being removed
+        serverId: typeof editingServer?.id === 'number' && !clearPassword ? editingServer.id : null,
       });
       setTestResult({ success: true, message });
     } catch (error) {
@@ -208,6 +284,16 @@ export const NewConnectionModal: React.FC<Props> = ({ isOpen, onClose, onSave, e
             ]}
           />
 
+          <Select
+            label={t('modal.key')}
+            value={formData.key_id ?? ''}
+            onChange={e => setFormData({ ...formData, key_id: e.target.value ? parseInt(e.target.value) : undefined })}
+            options={[
+              { value: '', label: editingServer?.private_key_path || t('modal.key_none') },
+              ...keys.map(k => ({ value: k.id!, label: `${k.name} (${k.key_type})` }))
+            ]}
+          />
+
           <div className="relative">
             <Input
               label={t('modal.password')}
@@ -226,6 +312,82 @@ export const NewConnectionModal: React.FC<Props> = ({ isOpen, onClose, onSave, e
             </button>
           </div>
 
+          {savedServerId !== null && (editingServer?.has_password || clearPassword) && (
+            <div className="flex items-center gap-3 ml-1 -mt-2 text-xs text-slate-500 dark:text-slate-400">
+              {hasSavedPassword ? (
+                <>
+                  <span>{t('modal.password_saved')}</span>
+                  <button type="button" onClick={revealPassword} className="flex items-center gap-1 text-nebula-600 dark:text-nebula-400 hover:underline">
+                    <Eye size={12} /> {t('modal.password_reveal')}
+                  </button>
+                  <button type="button" onClick={copyPassword} className="flex items-center gap-1 text-nebula-600 dark:text-nebula-400 hover:underline">
+                    <Copy size={12} /> {t('modal.password_copy')}
+                  </button>
+                  <button
+                    type="button"
+                    onClick={() => { setClearPassword(true); setFormData(prev => ({ ...prev, password: '' })); }}
+                    className="flex items-center gap-1 text-red-500 hover:underline"
+                  >
+                    <Trash2 size={12} /> {t('modal.password_remove')}
+                  </button>
+                </>
+              ) : (
+                <>
+                  <span className="text-amber-600 dark:text-amber-400">{t('modal.password_removed')}</span>
+                  <button type="button" onClick={() => setClearPassword(false)} className="flex items-center gap-1 text-nebula-600 dark:text-nebula-400 hover:underline">
+                    <Undo2 size={12} /> {t('modal.password_keep')}
+                  </button>
+                </>
+              )}
+              {secretNotice && <span className="ml-auto">{secretNotice}</span>}
+            </div>
+          )}
+
+          {certificate && (
+            <div className={simpleCn(
+              "rounded-lg border p-3 text-xs space-y-1",
+              certificate.expired || certificate.expires_soon
+                ? "border-amber-500/40 bg-amber-500/10 text-amber-700 dark:text-amber-300"
+                : "border-slate-200 dark:border-dark-border text-slate-600 dark:text-slate-300"
+            )}>
+              <div className="flex items-center gap-1.5 font-medium">
+                {certificate.expired || certificate.expires_soon ? <AlertTriangle size={14} /> : <BadgeCheck size={14} />}
+                {t('modal.certificate')}: {certificate.key_id}
+              </div>
+              <div>{t('modal.certificate_principals')}: {certificate.principals.length ? certificate.principals.join(', ') : t('modal.certificate_any')}</div>
+              <div>
+                {certificate.expired ? t('modal.certificate_expired') : t('modal.certificate_valid_until')}{' '}
+                {new Date(certificate.valid_before * 1000).toLocaleString()}
+              </div>
+            </div>
+          )}
+
+          <label className="flex items-start gap-2 ml-1 text-sm text-slate-600 dark:text-slate-300 cursor-pointer">
+            <input
+              type="checkbox"
+              className="mt-0.5"
+              checked={formData.forward_agent}
+              onChange={e => setFormData({ ...formData, forward_agent: e.target.checked })}
+            />
+            <span>
+              {t('modal.forward_agent')}
+              <span className="block text-xs text-amber-600 dark:text-amber-400">{t('modal.forward_agent_desc')}</span>
+            </span>
+          </label>
+
+          <label className="flex items-start gap-2 ml-1 text-sm text-slate-600 dark:text-slate-300 cursor-pointer">
+            <input
+              type="checkbox"
+              className="mt-0.5"
+              checked={formData.record_sessions}
+              onChange={e => setFormData({ ...formData, record_sessions: e.target.checked })}
+            />
+            <span>
+              {t('modal.record_sessions')}
+              <span className="block text-xs text-slate-400">{t('modal.record_sessions_desc')}</span>
+            </span>
+          </label>
+
           <Input
             label={t('modal.tags')}
             placeholder={t('modal.tags_ph')}
@@ -292,6 +454,15 @@ export const NewConnectionModal: React.FC<Props> = ({ isOpen, onClose, onSave, e
           <Button type="submit">{t('common.save')}</Button>
         </div>
       </form>
+      <MasterPasswordPrompt
+        isOpen={!!pendingSecret}
+        onConfirmed={() => {
+          const action = pendingSecret;
+          setPendingSecret(null);
+          if (action) withVault(action);
+        }}
+        onCancel={() => setPendingSecret(null)}
+      />
     </Modal>
   );
 };
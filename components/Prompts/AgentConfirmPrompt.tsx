@@ -0,0 +1,54 @@
+import React, { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { ShieldAlert } from 'lucide-react';
+import { Modal } from '../Modal';
+import { Button } from '../ui/Button';
+import { useApp } from '../../contexts/AppContext';
+import { AgentConfirmRequest } from '../../types';
+
+// Asks before an agent key added with confirmation signs anything
+export const AgentConfirmPrompt: React.FC = () => {
+  const { t } = useApp();
+  const [queue, setQueue] = useState<AgentConfirmRequest[]>([]);
+  const current = queue[0];
+
+  useEffect(() => {
+    const unlisten = listen<AgentConfirmRequest>('agent_confirm_prompt', (event) => {
+      setQueue(prev => [...prev, event.payload]);
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  const respond = (allow: boolean) => {
+    if (!current) return;
+    invoke('respond_agent_confirm', { requestId: current.request_id, allow })
+      .catch(e => console.error('Failed to answer agent confirmation:', e));
+    setQueue(prev => prev.slice(1));
+  };
+
+  return (
+    <Modal isOpen={!!current} onClose={() => respond(false)} title={t('prompts.agent_title')}>
+      {current && (
+        <div className="space-y-4">
+          <div className="flex gap-3">
+            <ShieldAlert size={32} className="text-amber-500 flex-shrink-0" />
+            <p className="text-sm text-slate-600 dark:text-slate-300">
+              <span className="font-semibold">{current.client}</span> {t('prompts.agent_desc')}{' '}
+              <span className="font-semibold">{current.key_name}</span>
+            </p>
+          </div>
+          <div className="rounded-lg bg-slate-100 dark:bg-dark-bg p-3 text-xs font-mono break-all text-slate-800 dark:text-slate-200">
+            {current.fingerprint}
+          </div>
+          <div className="flex justify-end gap-2">
+            <Button variant="ghost" onClick={() => respond(false)}>{t('prompts.deny')}</Button>
+            <Button onClick={() => respond(true)}>{t('prompts.allow')}</Button>
+          </div>
+        </div>
+      )}
+    </Modal>
+  );
+};
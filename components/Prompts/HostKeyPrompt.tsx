@@ -0,0 +1,57 @@
+import React, { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { ShieldQuestion } from 'lucide-react';
+import { Modal } from '../Modal';
+import { Button } from '../ui/Button';
+import { useApp } from '../../contexts/AppContext';
+import { HostKeyPromptRequest } from '../../types';
+
+// Asks whether to trust the key of a host not yet in known_hosts. The
+// connection waits on the backend until this is answered.
+export const HostKeyPrompt: React.FC = () => {
+  const { t } = useApp();
+  const [queue, setQueue] = useState<HostKeyPromptRequest[]>([]);
+  const current = queue[0];
+
+  useEffect(() => {
+    const unlisten = listen<HostKeyPromptRequest>('host_key_prompt', (event) => {
+      setQueue(prev => [...prev, event.payload]);
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  const respond = (accept: boolean) => {
+    if (!current) return;
+    invoke('respond_host_key_prompt', { requestId: current.request_id, accept })
+      .catch(e => console.error('Failed to answer host key prompt:', e));
+    setQueue(prev => prev.slice(1));
+  };
+
+  return (
+    <Modal isOpen={!!current} onClose={() => respond(false)} title={t('prompts.host_key_title')}>
+      {current && (
+        <div className="space-y-4">
+          <div className="flex gap-3">
+            <ShieldQuestion size={32} className="text-amber-500 flex-shrink-0" />
+            <p className="text-sm text-slate-600 dark:text-slate-300">
+              {t('prompts.host_key_desc')}{' '}
+              <span className="font-semibold">{current.port === 22 ? current.host : `[${current.host}]:${current.port}`}</span>
+            </p>
+          </div>
+          <div className="rounded-lg bg-slate-100 dark:bg-dark-bg p-3 text-xs space-y-1">
+            <div className="text-slate-500">{current.key_type}</div>
+            <div className="font-mono break-all text-slate-800 dark:text-slate-200">{current.fingerprint}</div>
+          </div>
+          <p className="text-xs text-slate-500">{t('prompts.host_key_hint')}</p>
+          <div className="flex justify-end gap-2">
+            <Button variant="ghost" onClick={() => respond(false)}>{t('prompts.reject')}</Button>
+            <Button onClick={() => respond(true)}>{t('prompts.trust')}</Button>
+          </div>
+        </div>
+      )}
+    </Modal>
+  );
+};
@@ -0,0 +1,69 @@
+import React, { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { Modal } from '../Modal';
+import { Button } from '../ui/Button';
+import { Input } from '../ui/Input';
+import { useApp } from '../../contexts/AppContext';
+import { KeyboardInteractiveRequest } from '../../types';
+
+// Shows keyboard-interactive challenges (one-time codes, 2FA) from the server
+export const KeyboardInteractivePrompt: React.FC = () => {
+  const { t } = useApp();
+  const [queue, setQueue] = useState<KeyboardInteractiveRequest[]>([]);
+  const [responses, setResponses] = useState<string[]>([]);
+  const current = queue[0];
+
+  useEffect(() => {
+    const unlisten = listen<KeyboardInteractiveRequest>('keyboard_interactive_prompt', (event) => {
+      setQueue(prev => [...prev, event.payload]);
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    setResponses(current ? current.prompts.map(() => '') : []);
+  }, [current]);
+
+  const respond = (answers: string[] | null) => {
+    if (!current) return;
+    invoke('respond_keyboard_interactive', { requestId: current.request_id, responses: answers })
+      .catch(e => console.error('Failed to answer keyboard-interactive prompt:', e));
+    setQueue(prev => prev.slice(1));
+  };
+
+  return (
+    <Modal isOpen={!!current} onClose={() => respond(null)} title={t('prompts.challenge_title')}>
+      {current && (
+        <form
+          className="space-y-4"
+          onSubmit={(e) => {
+            e.preventDefault();
+            respond(responses);
+          }}
+        >
+          <div className="text-xs text-slate-400">{current.username}</div>
+          {current.instructions && (
+            <p className="text-sm text-slate-600 dark:text-slate-300 whitespace-pre-wrap">{current.instructions}</p>
+          )}
+          {current.prompts.map((prompt, i) => (
+            <Input
+              key={i}
+              label={prompt.text}
+              type={prompt.echo ? 'text' : 'password'}
+              autoFocus={i === 0}
+              value={responses[i] ?? ''}
+              onChange={(e) => setResponses(prev => prev.map((r, j) => (j === i ? e.target.value : r)))}
+            />
+          ))}
+          <div className="flex justify-end gap-2">
+            <Button type="button" variant="ghost" onClick={() => respond(null)}>{t('prompts.cancel')}</Button>
+            <Button type="submit">{t('prompts.submit')}</Button>
+          </div>
+        </form>
+      )}
+    </Modal>
+  );
+};
@@ -0,0 +1,59 @@
+import React, { useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { ShieldCheck } from 'lucide-react';
+import { Modal } from '../Modal';
+import { Button } from '../ui/Button';
+import { Input } from '../ui/Input';
+import { useApp } from '../../contexts/AppContext';
+
+interface MasterPasswordPromptProps {
+  isOpen: boolean;
+  onConfirmed: () => void;
+  onCancel: () => void;
+}
+
+// Re-asks for the master password before stored secrets are revealed or copied
+export const MasterPasswordPrompt: React.FC<MasterPasswordPromptProps> = ({ isOpen, onConfirmed, onCancel }) => {
+  const { t } = useApp();
+  const [password, setPassword] = useState('');
+  const [error, setError] = useState('');
+
+  const close = (confirmed: boolean) => {
+    setPassword('');
+    setError('');
+    if (confirmed) onConfirmed(); else onCancel();
+  };
+
+  const submit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    try {
+      await invoke('confirm_master_password', { password });
+      close(true);
+    } catch {
+      setError(t('security.incorrect_password'));
+    }
+  };
+
+  return (
+    <Modal isOpen={isOpen} onClose={() => close(false)} title={t('prompts.vault_title')}>
+      <form className="space-y-4" onSubmit={submit}>
+        <div className="flex gap-3">
+          <ShieldCheck size={28} className="text-nebula-500 flex-shrink-0" />
+          <p className="text-sm text-slate-600 dark:text-slate-300">{t('prompts.vault_desc')}</p>
+        </div>
+        <Input
+          type="password"
+          autoFocus
+          placeholder={t('security.master_password')}
+          value={password}
+          onChange={(e) => setPassword(e.target.value)}
+        />
+        {error && <div className="text-xs text-red-500">{error}</div>}
+        <div className="flex justify-end gap-2">
+          <Button type="button" variant="ghost" onClick={() => close(false)}>{t('prompts.cancel')}</Button>
+          <Button type="submit">{t('prompts.submit')}</Button>
+        </div>
+      </form>
+    </Modal>
+  );
+};
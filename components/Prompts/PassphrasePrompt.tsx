@@ -0,0 +1,80 @@
+import React, { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { KeyRound } from 'lucide-react';
+import { Modal } from '../Modal';
+import { Button } from '../ui/Button';
+import { Input } from '../ui/Input';
+import { useApp } from '../../contexts/AppContext';
+import { PassphrasePromptRequest } from '../../types';
+
+// Asks for the passphrase of an encrypted private key during login
+export const PassphrasePrompt: React.FC = () => {
+  const { t } = useApp();
+  const [queue, setQueue] = useState<PassphrasePromptRequest[]>([]);
+  const [passphrase, setPassphrase] = useState('');
+  const [remember, setRemember] = useState(false);
+  const current = queue[0];
+
+  useEffect(() => {
+    const unlisten = listen<PassphrasePromptRequest>('passphrase_prompt', (event) => {
+      setQueue(prev => [...prev, event.payload]);
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  const respond = (answer: string | null) => {
+    if (!current) return;
+    invoke('respond_passphrase_prompt', {
+      requestId: current.request_id,
+      passphrase: answer,
+      remember: answer !== null && remember,
+    }).catch(e => console.error('Failed to answer passphrase prompt:', e));
+    setPassphrase('');
+    setRemember(false);
+    setQueue(prev => prev.slice(1));
+  };
+
+  return (
+    <Modal isOpen={!!current} onClose={() => respond(null)} title={t('prompts.passphrase_title')}>
+      {current && (
+        <form
+          className="space-y-4"
+          onSubmit={(e) => {
+            e.preventDefault();
+            respond(passphrase);
+          }}
+        >
+          <div className="flex gap-3">
+            <KeyRound size={28} className="text-nebula-500 flex-shrink-0" />
+            <div className="text-sm text-slate-600 dark:text-slate-300 min-w-0">
+              {t('prompts.passphrase_desc')}{' '}
+              <span className="font-mono break-all">{current.key}</span>
+              <div className="text-xs text-slate-400">{current.username}</div>
+            </div>
+          </div>
+          {current.retry && <div className="text-xs text-red-500">{t('prompts.passphrase_retry')}</div>}
+          <Input
+            type="password"
+            autoFocus
+            placeholder={t('prompts.passphrase')}
+            value={passphrase}
+            onChange={(e) => setPassphrase(e.target.value)}
+          />
+          {current.can_remember && (
+            <label className="flex items-center gap-2 text-sm text-slate-600 dark:text-slate-300 cursor-pointer">
+              <input type="checkbox" checked={remember} onChange={(e) => setRemember(e.target.checked)} />
+              {t('prompts.remember')}
+            </label>
+          )}
+          <div className="flex justify-end gap-2">
+            <Button type="button" variant="ghost" onClick={() => respond(null)}>{t('prompts.cancel')}</Button>
+            <Button type="submit">{t('prompts.submit')}</Button>
+          </div>
+        </form>
+      )}
+    </Modal>
+  );
+};
@@ -0,0 +1,207 @@
+import React, { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { Fingerprint, Trash2, Info, Clock, ShieldAlert } from 'lucide-react';
+import { useApp } from '../../contexts/AppContext';
+import { AgentStatus, AppSettings, SshKey } from '../../types';
+import { Button } from '../ui/Button';
+import { Input } from '../ui/Input';
+import { Select } from '../ui/Select';
+
+interface SettingsAgentProps {
+    settings: AppSettings;
+    setSettings: (settings: AppSettings) => void;
+}
+
+const LIFETIMES = [0, 15 * 60, 60 * 60, 8 * 60 * 60];
+
+const cardClass = "bg-slate-50 dark:bg-dark-surface rounded-xl border border-slate-200 dark:border-dark-border p-4";
+
+export const SettingsAgent: React.FC<SettingsAgentProps> = ({ settings, setSettings }) => {
+    const { t } = useApp();
+    const [status, setStatus] = useState<AgentStatus | null>(null);
+    const [keys, setKeys] = useState<SshKey[]>([]);
+    const [form, setForm] = useState({ keyId: '', passphrase: '', confirm: false, lifetime: '0' });
+    const [error, setError] = useState('');
+    const [busy, setBusy] = useState(false);
+
+    const loadStatus = async () => {
+        try {
+            setStatus(await invoke<AgentStatus>('get_agent_status'));
+        } catch (e) {
+            console.error('Failed to load agent status:', e);
+        }
+    };
+
+    useEffect(() => {
+        loadStatus();
+        invoke<SshKey[]>('get_keys')
+            .then(setKeys)
+            .catch(e => console.error('Failed to load keys:', e));
+        // Keys with a lifetime drop out on their own
+        const timer = setInterval(loadStatus, 30000);
+        return () => clearInterval(timer);
+    }, []);
+
+    const selected = keys.find(k => String(k.id) === form.keyId);
+
+    const handleAdd = async () => {
+        if (!selected) return;
+        setBusy(true);
+        setError('');
+        try {
+            await invoke('agent_add_key', {
+                keyId: selected.id,
+                passphrase: form.passphrase || null,
+                confirm: form.confirm,
+                lifetimeSecs: parseInt(form.lifetime) || null,
+            });
+            setForm({ keyId: '', passphrase: '', confirm: false, lifetime: '0' });
+            await loadStatus();
+        } catch (e) {
+            setError(String(e));
+        } finally {
+            setBusy(false);
+        }
+    };
+
+    const handleRemove = async (keyId?: number) => {
+        try {
+            if (keyId === undefined) {
+                await invoke('agent_remove_all');
+            } else {
+                await invoke('agent_remove_key', { keyId });
+            }
+            await loadStatus();
+        } catch (e) {
+            console.error('Failed to remove agent key:', e);
+        }
+    };
+
+    const lifetimeLabel = (secs: number) => {
+        if (secs === 0) return t('agent.lifetime_forever');
+        return secs < 3600 ? `${secs / 60} ${t('security.minutes')}` : `${secs / 3600} ${t('agent.hours')}`;
+    };
+
+    return (
+        <div className="space-y-6 animate-in fade-in slide-in-from-bottom-4 duration-500">
+            <div>
+                <h3 className="text-lg font-semibold text-slate-900 dark:text-slate-100 mb-1 flex items-center gap-2">
+                    <Fingerprint className="text-nebula-500" size={20} />
+                    {t('settings.agent')}
+                </h3>
+                <p className="text-slate-500 dark:text-slate-400 text-xs mb-6">
+                    {t('settings.agent_desc')}
+                </p>
+
+                <div className={`${cardClass} mb-4`}>
+                    <div className="flex items-start justify-between">
+                        <div>
+                            <h4 className="text-sm font-medium text-slate-900 dark:text-slate-100">{t('agent.local_terminals')}</h4>
+                            <p className="text-xs text-slate-500 dark:text-slate-400 mt-1">{t('agent.local_terminals_desc')}</p>
+                            <p className="text-xs font-mono text-slate-400 mt-1 break-all">
+                                {status?.socket_path ? `SSH_AUTH_SOCK=${status.socket_path}` : t('agent.no_socket')}
+                            </p>
+                        </div>
+                        <button
+                            onClick={() => setSettings({
+                                ...settings,
+                                agent_for_local_terminals: !settings.agent_for_local_terminals
+                            })}
+                            className={`relative inline-flex h-6 w-11 flex-shrink-0 items-center rounded-full transition-colors ${settings.agent_for_local_terminals
+                                ? 'bg-nebula-500'
+                                : 'bg-slate-300 dark:bg-slate-600'
+                                }`}
+                        >
+                            <span
+                                className={`inline-block h-4 w-4 transform rounded-full bg-white transition-transform ${settings.agent_for_local_terminals ? 'translate-x-6' : 'translate-x-1'
+                                    }`}
+                            />
+                        </button>
+                    </div>
+                </div>
+
+                <div className={`${cardClass} space-y-3 mb-4`}>
+                    <h4 className="text-sm font-medium text-slate-900 dark:text-slate-100">{t('agent.add_key')}</h4>
+                    <div className="grid grid-cols-2 gap-3">
+                        <Select
+                            label={t('agent.key')}
+                            value={form.keyId}
+                            onChange={(e) => setForm({ ...form, keyId: e.target.value, passphrase: '' })}
+                            options={[
+                                { value: '', label: keys.length ? t('agent.choose_key') : t('keys.empty') },
+                                ...keys.map(k => ({ value: String(k.id), label: `${k.name} (${k.key_type})` })),
+                            ]}
+                        />
+                        <Select
+                            label={t('agent.lifetime')}
+                            value={form.lifetime}
+                            onChange={(e) => setForm({ ...form, lifetime: e.target.value })}
+                            options={LIFETIMES.map(secs => ({ value: String(secs), label: lifetimeLabel(secs) }))}
+                        />
+                    </div>
+                    {selected?.encrypted && (
+                        <Input
+                            label={t('keys.passphrase')}
+                            type="password"
+                            value={form.passphrase}
+                            onChange={(e) => setForm({ ...form, passphrase: e.target.value })}
+                        />
+                    )}
+                    <label className="flex items-start gap-2 text-sm text-slate-600 dark:text-slate-300 cursor-pointer">
+                        <input
+                            type="checkbox"
+                            className="mt-0.5"
+                            checked={form.confirm}
+                            onChange={(e) => setForm({ ...form, confirm: e.target.checked })}
+                        />
+                        <span>
+                            {t('agent.confirm')}
+                            <span className="block text-xs text-slate-400">{t('agent.confirm_desc')}</span>
+                        </span>
+                    </label>
+                    {error && (
+                        <p className="text-xs text-red-500 flex items-center gap-1">
+                            <Info size={12} /> {error}
+                        </p>
+                    )}
+                    <div className="flex justify-end">
+                        <Button size="sm" onClick={handleAdd} disabled={busy || !selected}>{t('agent.add')}</Button>
+                    </div>
+                </div>
+
+                <div className={cardClass}>
+                    <div className="flex items-center justify-between mb-3">
+                        <h4 className="text-sm font-medium text-slate-900 dark:text-slate-100">{t('agent.loaded')}</h4>
+                        {!!status?.keys.length && (
+                            <Button size="sm" variant="ghost" onClick={() => handleRemove()}>{t('agent.remove_all')}</Button>
+                        )}
+                    </div>
+                    {!status?.keys.length && (
+                        <p className="text-xs text-slate-500 dark:text-slate-400">{t('agent.none_loaded')}</p>
+                    )}
+                    <div className="space-y-2">
+                        {status?.keys.map(key => (
+                            <div key={key.key_id} className="flex items-center justify-between gap-3">
+                                <div className="min-w-0">
+                                    <div className="text-sm text-slate-900 dark:text-slate-100 flex items-center gap-2">
+                                        {key.name}
+                                        {key.confirm && <ShieldAlert size={12} className="text-amber-500" />}
+                                        {key.expires_in_secs != null && (
+                                            <span className="text-xs text-slate-400 flex items-center gap-1">
+                                                <Clock size={12} /> {Math.ceil(key.expires_in_secs / 60)} {t('security.minutes')}
+                                            </span>
+                                        )}
+                                    </div>
+                                    <div className="text-xs font-mono text-slate-500 dark:text-slate-400 break-all">{key.fingerprint}</div>
+                                </div>
+                                <Button size="icon" variant="ghost" title={t('agent.remove')} onClick={() => handleRemove(key.key_id)}>
+                                    <Trash2 size={14} />
+                                </Button>
+                            </div>
+                        ))}
+                    </div>
+                </div>
+            </div>
+        </div>
+    );
+};
@@ -1,8 +1,9 @@
-import React from 'react';
-import { History } from 'lucide-react';
+import React, { useEffect, useState } from 'react';
+import { History, FileText } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
 import { simpleCn } from '../../utils';
 import { useApp } from '../../contexts/AppContext';
-import { AppSettings } from '../../types';
+import { AppSettings, SessionLogSettings } from '../../types';
 
 interface SettingsGeneralProps {
     settings: AppSettings;
@@ -11,6 +12,26 @@ interface SettingsGeneralProps {
 
 export const SettingsGeneral: React.FC<SettingsGeneralProps> = ({ settings, setSettings }) => {
     const { t, language, setLanguage } = useApp();
+    const [logSettings, setLogSettings] = useState<SessionLogSettings | null>(null);
+    const [logStatus, setLogStatus] = useState<string | null>(null);
+
+    useEffect(() => {
+        invoke<SessionLogSettings>('get_session_log_settings')
+            .then(setLogSettings)
+            .catch(e => console.error('Failed to load session log settings:', e));
+    }, []);
+
+    const saveLogSettings = async () => {
+        if (!logSettings) return;
+        try {
+            await invoke('set_session_log_settings', { logSettings });
+            setLogStatus(t('settings.session_log_saved'));
+        } catch (e) {
+            setLogStatus(String(e));
+        }
+    };
+
+    const inputClass = "px-3 py-1.5 bg-white dark:bg-[#0b0b0d] border border-slate-200 dark:border-dark-border rounded-md focus:outline-none focus:ring-2 focus:ring-nebula-500/50 text-slate-900 dark:text-slate-100 font-mono text-sm";
 
     return (
         <div className="space-y-6 animate-in fade-in slide-in-from-bottom-4 duration-500">
@@ -68,6 +89,91 @@ export const SettingsGeneral: React.FC<SettingsGeneralProps> = ({ settings, setS
                     </div>
                 </div>
             </div>
+
+            {logSettings && (
+                <div>
+                    <h3 className="text-lg font-semibold text-slate-900 dark:text-slate-100 mb-1 flex items-center gap-2">
+                        <FileText className="text-nebula-500" size={20} />
+                        {t('settings.session_log')}
+                    </h3>
+                    <p className="text-slate-500 dark:text-slate-400 text-xs mb-4">
+                        {t('settings.session_log_desc')}
+                    </p>
+
+                    <div className="bg-slate-50 dark:bg-dark-surface rounded-xl border border-slate-200 dark:border-dark-border p-4 space-y-3">
+                        {([
+                            ['enabled', 'settings.session_log_enabled'],
+                            ['timestamps', 'settings.session_log_timestamps'],
+                            ['strip_ansi', 'settings.session_log_strip_ansi'],
+                        ] as const).map(([key, label]) => (
+                            <label key={key} className="flex items-center gap-2 text-sm text-slate-700 dark:text-slate-300 cursor-pointer">
+                                <input
+                                    type="checkbox"
+                                    checked={logSettings[key]}
+                                    onChange={(e) => setLogSettings({ ...logSettings, [key]: e.target.checked })}
+                                />
+                                {t(label)}
+                            </label>
+                        ))}
+
+                        <div>
+                            <label className="block text-xs font-medium text-slate-700 dark:text-slate-300 mb-1">
+                                {t('settings.session_log_directory')}
+                            </label>
+                            <input
+                                type="text"
+                                value={logSettings.directory ?? ''}
+                                placeholder={t('settings.session_log_directory_default')}
+                                onChange={(e) => setLogSettings({ ...logSettings, directory: e.target.value || null })}
+                                className={simpleCn(inputClass, "w-full")}
+                            />
+                        </div>
+                        <div>
+                            <label className="block text-xs font-medium text-slate-700 dark:text-slate-300 mb-1">
+                                {t('settings.session_log_pattern')}
+                            </label>
+                            <input
+                                type="text"
+                                value={logSettings.directory_pattern}
+                                onChange={(e) => setLogSettings({ ...logSettings, directory_pattern: e.target.value })}
+                                className={simpleCn(inputClass, "w-full")}
+                            />
+                            <p className="text-xs text-slate-400 mt-1">{t('settings.session_log_pattern_desc')}</p>
+                        </div>
+                        <div className="flex gap-4">
+                            <label className="text-xs text-slate-700 dark:text-slate-300">
+                                {t('settings.session_log_max_size')}
+                                <input
+                                    type="number"
+                                    min={0}
+                                    value={logSettings.max_file_size_mb}
+                                    onChange={(e) => setLogSettings({ ...logSettings, max_file_size_mb: parseInt(e.target.value) || 0 })}
+                                    className={simpleCn(inputClass, "w-24 block mt-1")}
+                                />
+                            </label>
+                            <label className="text-xs text-slate-700 dark:text-slate-300">
+                                {t('settings.session_log_retention')}
+                                <input
+                                    type="number"
+                                    min={0}
+                                    value={logSettings.retention_days}
+                                    onChange={(e) => setLogSettings({ ...logSettings, retention_days: parseInt(e.target.value) || 0 })}
+                                    className={simpleCn(inputClass, "w-24 block mt-1")}
+                                />
+                            </label>
+                        </div>
+                        <div className="flex items-center gap-3">
+                            <button
+                                onClick={saveLogSettings}
+                                className="px-3 py-1.5 rounded-md text-xs font-medium bg-nebula-500 text-white hover:bg-nebula-600"
+                            >
+                                {t('common.save')}
+                            </button>
+                            {logStatus && <span className="text-xs text-slate-500">{logStatus}</span>}
+                        </div>
+                    </div>
+                </div>
+            )}
         </div>
     );
 };
@@ -0,0 +1,235 @@
+import React, { useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { open, save } from '@tauri-apps/plugin-dialog';
+import { ArrowLeftRight, Info, Check, AlertTriangle } from 'lucide-react';
+import { useApp } from '../../contexts/AppContext';
+import { ImportPreview, ImportSummary } from '../../types';
+import { Button } from '../ui/Button';
+import { Input } from '../ui/Input';
+import { Select } from '../ui/Select';
+import { MasterPasswordPrompt } from '../Prompts/MasterPasswordPrompt';
+import { simpleCn } from '../../utils';
+
+interface SettingsInventoryProps {
+    onServersChanged?: () => void;
+}
+
+const FORMATS = [
+    { value: 'ssh_config', label: 'OpenSSH config' },
+    { value: 'json', label: 'JSON' },
+    { value: 'csv', label: 'CSV' },
+];
+
+const EXTENSIONS: Record<string, string> = { ssh_config: 'config', json: 'json', csv: 'csv' };
+
+const cardClass = "bg-slate-50 dark:bg-dark-surface rounded-xl border border-slate-200 dark:border-dark-border p-4";
+
+export const SettingsInventory: React.FC<SettingsInventoryProps> = ({ onServersChanged }) => {
+    const { t } = useApp();
+    const [path, setPath] = useState('');
+    const [preview, setPreview] = useState<ImportPreview | null>(null);
+    const [selected, setSelected] = useState<Set<string>>(new Set());
+    const [format, setFormat] = useState('ssh_config');
+    const [includeSecrets, setIncludeSecrets] = useState(false);
+    const [confirming, setConfirming] = useState(false);
+    const [busy, setBusy] = useState(false);
+    const [error, setError] = useState('');
+    const [notice, setNotice] = useState('');
+
+    const run = async (action: () => Promise<string | void>) => {
+        setBusy(true);
+        setError('');
+        setNotice('');
+        try {
+            const message = await action();
+            if (message) setNotice(message);
+        } catch (e) {
+            setError(String(e));
+        } finally {
+            setBusy(false);
+        }
+    };
+
+    const handleBrowse = async () => {
+        const picked = await open({ multiple: false, directory: false });
+        if (typeof picked === 'string') setPath(picked);
+    };
+
+    const handlePreview = () => run(async () => {
+        const result = await invoke<ImportPreview>('preview_ssh_config_import', { path: path || null });
+        setPreview(result);
+        setSelected(new Set(result.entries.filter(e => e.action !== 'unchanged').map(e => e.alias)));
+    });
+
+    const toggle = (alias: string) => {
+        setSelected(prev => {
+            const next = new Set(prev);
+            if (next.has(alias)) next.delete(alias); else next.add(alias);
+            return next;
+        });
+    };
+
+    const handleImport = () => run(async () => {
+        const summary = await invoke<ImportSummary>('import_ssh_config', {
+            path: path || null,
+            aliases: Array.from(selected),
+        });
+        setPreview(null);
+        onServersChanged?.();
+        const counts = t('inventory.imported')
+            .replace('{created}', String(summary.created))
+            .replace('{updated}', String(summary.updated))
+            .replace('{unchanged}', String(summary.unchanged));
+        return [counts, ...summary.warnings].join(' · ');
+    });
+
+    const secretsAllowed = format !== 'ssh_config';
+
+    const exportServers = () => run(async () => {
+        const target = await save({ defaultPath: `nebula-servers.${EXTENSIONS[format]}` });
+        if (!target) return;
+        try {
+            const count = await invoke<number>('export_servers', {
+                path: target,
+                format,
+                includeSecrets: secretsAllowed && includeSecrets,
+            });
+            return `${t('inventory.exported').replace('{count}', String(count))} ${target}`;
+        } catch (e) {
+            // Writing secrets needs the master password again
+            if (String(e).includes('Confirm the master password')) {
+                setConfirming(true);
+                return;
+            }
+            throw e;
+        }
+    });
+
+    const actionClass = (action: string) => simpleCn(
+        "px-1.5 py-0.5 rounded text-[10px] font-bold uppercase",
+        action === 'create' ? "bg-green-100 text-green-700 dark:bg-green-900/30 dark:text-green-300" :
+            action === 'update' ? "bg-blue-100 text-blue-700 dark:bg-blue-900/30 dark:text-blue-300" :
+                "bg-slate-100 text-slate-500 dark:bg-dark-bg dark:text-slate-400"
+    );
+
+    return (
+        <div className="space-y-6 animate-in fade-in slide-in-from-bottom-4 duration-500">
+            <div>
+                <h3 className="text-lg font-semibold text-slate-900 dark:text-slate-100 mb-1 flex items-center gap-2">
+                    <ArrowLeftRight className="text-nebula-500" size={20} />
+                    {t('settings.inventory')}
+                </h3>
+                <p className="text-slate-500 dark:text-slate-400 text-xs mb-6">
+                    {t('settings.inventory_desc')}
+                </p>
+
+                <div className={`${cardClass} space-y-3 mb-4`}>
+                    <h4 className="text-sm font-medium text-slate-900 dark:text-slate-100">{t('inventory.import')}</h4>
+                    <div className="flex gap-2 items-end">
+                        <div className="flex-1">
+                            <Input
+                                label={t('inventory.config_path')}
+                                placeholder="~/.ssh/config"
+                                value={path}
+                                onChange={(e) => { setPath(e.target.value); setPreview(null); }}
+                            />
+                        </div>
+                        <Button size="sm" variant="ghost" onClick={handleBrowse}>{t('keys.browse')}</Button>
+                        <Button size="sm" onClick={handlePreview} disabled={busy}>{t('inventory.preview')}</Button>
+                    </div>
+
+                    {preview && (
+                        <div className="space-y-2">
+                            {preview.warnings.map((warning, i) => (
+                                <p key={i} className="text-xs text-amber-600 dark:text-amber-400 flex items-center gap-1">
+                                    <AlertTriangle size={12} /> {warning}
+                                </p>
+                            ))}
+                            {preview.entries.length === 0 && (
+                                <p className="text-xs text-slate-500 dark:text-slate-400">{t('inventory.no_hosts')}</p>
+                            )}
+                            {preview.entries.map(entry => (
+                                <label key={entry.alias} className="flex items-start gap-2 text-xs cursor-pointer">
+                                    <input
+                                        type="checkbox"
+                                        className="mt-0.5"
+                                        checked={selected.has(entry.alias)}
+                                        disabled={entry.action === 'unchanged'}
+                                        onChange={() => toggle(entry.alias)}
+                                    />
+                                    <div className="min-w-0 flex-1">
+                                        <div className="flex items-center gap-2">
+                                            <span className={actionClass(entry.action)}>{t(`inventory.${entry.action}`)}</span>
+                                            <span className="font-medium text-slate-900 dark:text-slate-100">{entry.alias}</span>
+                                            <span className="font-mono text-slate-500">
+                                                {entry.server.username}@{entry.server.host}:{entry.server.port}
+                                            </span>
+                                            {entry.jump_host && <span className="text-slate-400">via {entry.jump_host}</span>}
+                                        </div>
+                                        {entry.changes.map(change => (
+                                            <div key={change.field} className="font-mono text-slate-500 dark:text-slate-400 pl-1">
+                                                {change.field}: {change.old || '—'} → {change.new || '—'}
+                                            </div>
+                                        ))}
+                                    </div>
+                                </label>
+                            ))}
+                            <div className="flex justify-end gap-2">
+                                <Button size="sm" variant="ghost" onClick={() => setPreview(null)}>{t('common.cancel')}</Button>
+                                <Button size="sm" onClick={handleImport} disabled={busy || selected.size === 0}>
+                                    {t('inventory.import_selected')} ({selected.size})
+                                </Button>
+                            </div>
+                        </div>
+                    )}
+                </div>
+
+                <div className={`${cardClass} space-y-3 mb-4`}>
+                    <h4 className="text-sm font-medium text-slate-900 dark:text-slate-100">{t('inventory.export')}</h4>
+                    <Select
+                        label={t('inventory.format')}
+                        value={format}
+                        onChange={(e) => setFormat(e.target.value)}
+                        options={FORMATS}
+                    />
+                    <label className={simpleCn(
+                        "flex items-start gap-2 text-sm text-slate-600 dark:text-slate-300",
+                        secretsAllowed ? "cursor-pointer" : "opacity-50"
+                    )}>
+                        <input
+                            type="checkbox"
+                            className="mt-0.5"
+                            disabled={!secretsAllowed}
+                            checked={secretsAllowed && includeSecrets}
+                            onChange={(e) => setIncludeSecrets(e.target.checked)}
+                        />
+                        <span>
+                            {t('inventory.include_secrets')}
+                            <span className="block text-xs text-amber-600 dark:text-amber-400">{t('inventory.include_secrets_desc')}</span>
+                        </span>
+                    </label>
+                    <div className="flex justify-end">
+                        <Button size="sm" onClick={exportServers} disabled={busy}>{t('inventory.export')}</Button>
+                    </div>
+                </div>
+
+                {error && (
+                    <p className="text-xs text-red-500 flex items-center gap-1">
+                        <Info size={12} /> {error}
+                    </p>
+                )}
+                {notice && (
+                    <p className="text-xs text-green-500 flex items-center gap-1">
+                        <Check size={12} /> {notice}
+                    </p>
+                )}
+            </div>
+
+            <MasterPasswordPrompt
+                isOpen={confirming}
+                onConfirmed={() => { setConfirming(false); exportServers(); }}
+                onCancel={() => setConfirming(false)}
+            />
+        </div>
+    );
+};
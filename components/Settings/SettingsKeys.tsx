@@ -0,0 +1,309 @@
+import React, { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { save, open } from '@tauri-apps/plugin-dialog';
+import { KeyRound, Lock, Copy, Download, Upload, Trash2, Info, Check, Plus } from 'lucide-react';
+import { useApp } from '../../contexts/AppContext';
+import { SshKey } from '../../types';
+import { Button } from '../ui/Button';
+import { Input } from '../ui/Input';
+import { Select } from '../ui/Select';
+
+// An open SSH session a key can be deployed to
+export interface DeployTarget {
+    id: string;
+    label: string;
+}
+
+interface SettingsKeysProps {
+    sessions: DeployTarget[];
+}
+
+const KEY_TYPES = [
+    { value: 'ed25519', label: 'Ed25519' },
+    { value: 'ecdsa', label: 'ECDSA' },
+    { value: 'rsa', label: 'RSA' },
+];
+
+const BITS: Record<string, number[]> = {
+    ecdsa: [256, 384, 521],
+    rsa: [2048, 3072, 4096],
+};
+
+const cardClass = "bg-slate-50 dark:bg-dark-surface rounded-xl border border-slate-200 dark:border-dark-border p-4";
+
+export const SettingsKeys: React.FC<SettingsKeysProps> = ({ sessions }) => {
+    const { t } = useApp();
+    const [keys, setKeys] = useState<SshKey[]>([]);
+    const [mode, setMode] = useState<'none' | 'generate' | 'import'>('none');
+    const [form, setForm] = useState({ name: '', keyType: 'ed25519', bits: '', comment: '', key: '', passphrase: '' });
+    const [busy, setBusy] = useState(false);
+    const [error, setError] = useState('');
+    const [notice, setNotice] = useState('');
+
+    // Per-key forms
+    const [passphraseFor, setPassphraseFor] = useState<number | null>(null);
+    const [passphraseForm, setPassphraseForm] = useState({ old: '', next: '' });
+    const [deployFor, setDeployFor] = useState<number | null>(null);
+    const [deploySession, setDeploySession] = useState('');
+
+    const loadKeys = async () => {
+        try {
+            setKeys(await invoke<SshKey[]>('get_keys'));
+        } catch (e) {
+            console.error('Failed to load keys:', e);
+        }
+    };
+
+    useEffect(() => {
+        loadKeys();
+    }, []);
+
+    const run = async (action: () => Promise<string | void>) => {
+        setBusy(true);
+        setError('');
+        setNotice('');
+        try {
+            const message = await action();
+            if (message) setNotice(message);
+            await loadKeys();
+        } catch (e) {
+            setError(String(e));
+        } finally {
+            setBusy(false);
+        }
+    };
+
+    const handleCreate = () => run(async () => {
+        const name = form.name.trim();
+        if (!name) throw t('keys.name_required');
+        const passphrase = form.passphrase || null;
+        if (mode === 'generate') {
+            await invoke('generate_key', {
+                name,
+                keyType: form.keyType,
+                bits: form.bits ? parseInt(form.bits) : null,
+                comment: form.comment || null,
+                passphrase,
+            });
+        } else {
+            await invoke('import_key', { name, key: form.key, passphrase });
+        }
+        setMode('none');
+        setForm({ name: '', keyType: 'ed25519', bits: '', comment: '', key: '', passphrase: '' });
+    });
+
+    const handleBrowse = async () => {
+        const path = await open({ multiple: false, directory: false });
+        if (typeof path === 'string') setForm({ ...form, key: path });
+    };
+
+    const handleChangePassphrase = (key: SshKey) => run(async () => {
+        await invoke('change_key_passphrase', {
+            id: key.id,
+            oldPassphrase: passphraseForm.old || null,
+            newPassphrase: passphraseForm.next || null,
+        });
+        setPassphraseFor(null);
+        setPassphraseForm({ old: '', next: '' });
+        return t('keys.passphrase_changed');
+    });
+
+    const handleCopy = (key: SshKey) => run(async () => {
+        const line = await invoke<string>('export_public_key', { id: key.id, path: null });
+        await navigator.clipboard.writeText(line);
+        return t('keys.copied');
+    });
+
+    const handleExport = (key: SshKey) => run(async () => {
+        const path = await save({ defaultPath: `${key.name.replace(/\s+/g, '_')}.pub` });
+        if (!path) return;
+        await invoke('export_public_key', { id: key.id, path });
+        return `${t('keys.exported')} ${path}`;
+    });
+
+    const handleDeploy = (key: SshKey) => run(async () => {
+        const added = await invoke<boolean>('deploy_key', { sessionId: deploySession, keyId: key.id });
+        setDeployFor(null);
+        return added ? t('keys.deployed') : t('keys.already_deployed');
+    });
+
+    const handleDelete = (key: SshKey) => {
+        if (confirm(t('keys.delete_confirm'))) {
+            run(async () => {
+                await invoke('delete_key', { id: key.id });
+            });
+        }
+    };
+
+    return (
+        <div className="space-y-6 animate-in fade-in slide-in-from-bottom-4 duration-500">
+            <div>
+                <h3 className="text-lg font-semibold text-slate-900 dark:text-slate-100 mb-1 flex items-center gap-2">
+                    <KeyRound className="text-nebula-500" size={20} />
+                    {t('settings.keys')}
+                </h3>
+                <p className="text-slate-500 dark:text-slate-400 text-xs mb-6">
+                    {t('settings.keys_desc')}
+                </p>
+
+                <div className="flex gap-2 mb-4">
+                    <Button size="sm" variant={mode === 'generate' ? 'primary' : 'secondary'} onClick={() => setMode(mode === 'generate' ? 'none' : 'generate')}>
+                        <Plus size={14} className="mr-1" /> {t('keys.generate')}
+                    </Button>
+                    <Button size="sm" variant={mode === 'import' ? 'primary' : 'secondary'} onClick={() => setMode(mode === 'import' ? 'none' : 'import')}>
+                        <Upload size={14} className="mr-1" /> {t('keys.import')}
+                    </Button>
+                </div>
+
+                {mode !== 'none' && (
+                    <div className={`${cardClass} space-y-3 mb-4`}>
+                        <Input
+                            label={t('keys.name')}
+                            value={form.name}
+                            onChange={(e) => setForm({ ...form, name: e.target.value })}
+                        />
+                        {mode === 'generate' ? (
+                            <>
+                                <div className="grid grid-cols-2 gap-3">
+                                    <Select
+                                        label={t('keys.type')}
+                                        value={form.keyType}
+                                        onChange={(e) => setForm({ ...form, keyType: e.target.value, bits: '' })}
+                                        options={KEY_TYPES}
+                                    />
+                                    <Select
+                                        label={t('keys.bits')}
+                                        value={form.bits}
+                                        disabled={!BITS[form.keyType]}
+                                        onChange={(e) => setForm({ ...form, bits: e.target.value })}
+                                        options={[
+                                            { value: '', label: t('keys.default_bits') },
+                                            ...(BITS[form.keyType] || []).map(b => ({ value: String(b), label: String(b) })),
+                                        ]}
+                                    />
+                                </div>
+                                <Input
+                                    label={t('keys.comment')}
+                                    placeholder="user@host"
+                                    value={form.comment}
+                                    onChange={(e) => setForm({ ...form, comment: e.target.value })}
+                                />
+                            </>
+                        ) : (
+                            <div>
+                                <label className="block text-xs font-medium text-slate-500 dark:text-slate-400 mb-1 ml-1">{t('keys.source')}</label>
+                                <textarea
+                                    rows={4}
+                                    placeholder={t('keys.source_ph')}
+                                    value={form.key}
+                                    onChange={(e) => setForm({ ...form, key: e.target.value })}
+                                    className="w-full px-3 py-2 bg-white dark:bg-[#0b0b0d] border border-slate-200 dark:border-dark-border rounded-md focus:outline-none focus:ring-2 focus:ring-nebula-500/50 text-slate-900 dark:text-slate-100 text-xs font-mono"
+                                />
+                                <Button size="sm" variant="ghost" onClick={handleBrowse}>{t('keys.browse')}</Button>
+                            </div>
+                        )}
+                        <Input
+                            label={mode === 'generate' ? t('keys.passphrase') : t('keys.import_passphrase')}
+                            type="password"
+                            value={form.passphrase}
+                            onChange={(e) => setForm({ ...form, passphrase: e.target.value })}
+                        />
+                        <div className="flex justify-end gap-2">
+                            <Button size="sm" variant="ghost" onClick={() => setMode('none')}>{t('common.cancel')}</Button>
+                            <Button size="sm" onClick={handleCreate} disabled={busy}>
+                                {mode === 'generate' ? t('keys.generate') : t('keys.import')}
+                            </Button>
+                        </div>
+                    </div>
+                )}
+
+                {error && (
+                    <p className="text-xs text-red-500 flex items-center gap-1 mb-3">
+                        <Info size={12} /> {error}
+                    </p>
+                )}
+                {notice && (
+                    <p className="text-xs text-green-500 flex items-center gap-1 mb-3">
+                        <Check size={12} /> {notice}
+                    </p>
+                )}
+
+                <div className="space-y-3">
+                    {keys.length === 0 && (
+                        <p className="text-sm text-slate-500 dark:text-slate-400">{t('keys.empty')}</p>
+                    )}
+                    {keys.map(key => (
+                        <div key={key.id} className={cardClass}>
+                            <div className="flex items-start justify-between gap-3">
+                                <div className="min-w-0">
+                                    <div className="text-sm font-medium text-slate-900 dark:text-slate-100 flex items-center gap-2">
+                                        {key.name}
+                                        {key.encrypted && <Lock size={12} className="text-slate-400" />}
+                                    </div>
+                                    <div className="text-xs text-slate-500 dark:text-slate-400">
+                                        {key.key_type}{key.bits ? ` ${key.bits}` : ''}{key.comment ? ` · ${key.comment}` : ''}
+                                    </div>
+                                    <div className="text-xs font-mono text-slate-500 dark:text-slate-400 break-all">{key.fingerprint}</div>
+                                </div>
+                                <div className="flex gap-1 flex-shrink-0">
+                                    <Button size="icon" variant="ghost" title={t('keys.copy_public')} onClick={() => handleCopy(key)}><Copy size={14} /></Button>
+                                    <Button size="icon" variant="ghost" title={t('keys.export_public')} onClick={() => handleExport(key)}><Download size={14} /></Button>
+                                    <Button size="icon" variant="ghost" title={t('keys.delete')} onClick={() => handleDelete(key)}><Trash2 size={14} /></Button>
+                                </div>
+                            </div>
+
+                            <div className="flex gap-3 mt-2 text-xs">
+                                <button className="text-nebula-600 dark:text-nebula-400 hover:underline" onClick={() => setPassphraseFor(passphraseFor === key.id ? null : key.id!)}>
+                                    {key.encrypted ? t('keys.change_passphrase') : t('keys.set_passphrase')}
+                                </button>
+                                <button className="text-nebula-600 dark:text-nebula-400 hover:underline" onClick={() => {
+                                    setDeployFor(deployFor === key.id ? null : key.id!);
+                                    setDeploySession(sessions[0]?.id || '');
+                                }}>
+                                    {t('keys.deploy')}
+                                </button>
+                            </div>
+
+                            {passphraseFor === key.id && (
+                                <div className="grid grid-cols-2 gap-3 mt-3 items-end">
+                                    {key.encrypted && (
+                                        <Input
+                                            label={t('keys.current_passphrase')}
+                                            type="password"
+                                            value={passphraseForm.old}
+                                            onChange={(e) => setPassphraseForm({ ...passphraseForm, old: e.target.value })}
+                                        />
+                                    )}
+                                    <Input
+                                        label={t('keys.new_passphrase')}
+                                        placeholder={key.encrypted ? t('keys.new_passphrase_ph') : ''}
+                                        type="password"
+                                        value={passphraseForm.next}
+                                        onChange={(e) => setPassphraseForm({ ...passphraseForm, next: e.target.value })}
+                                    />
+                                    <Button size="sm" onClick={() => handleChangePassphrase(key)} disabled={busy}>{t('common.save')}</Button>
+                                </div>
+                            )}
+
+                            {deployFor === key.id && (
+                                sessions.length === 0 ? (
+                                    <p className="text-xs text-slate-500 dark:text-slate-400 mt-3">{t('keys.no_sessions')}</p>
+                                ) : (
+                                    <div className="flex gap-3 mt-3 items-end">
+                                        <Select
+                                            label={t('keys.deploy_to')}
+                                            value={deploySession}
+                                            onChange={(e) => setDeploySession(e.target.value)}
+                                            options={sessions.map(s => ({ value: s.id, label: s.label }))}
+                                        />
+                                        <Button size="sm" onClick={() => handleDeploy(key)} disabled={busy || !deploySession}>{t('keys.deploy')}</Button>
+                                    </div>
+                                )
+                            )}
+                        </div>
+                    ))}
+                </div>
+            </div>
+        </div>
+    );
+};
@@ -8,7 +8,10 @@ import {
   Info,
   ChevronRight,
   Bot,
-  ShieldCheck
+  ShieldCheck,
+  KeyRound,
+  Fingerprint,
+  ArrowLeftRight
 } from 'lucide-react';
 import { simpleCn } from '../utils';
 import { AppSettings } from '../types';
@@ -16,17 +19,22 @@ import { AppSettings } from '../types';
 import { SettingsGeneral } from './Settings/SettingsGeneral';
 import { SettingsAppearance } from './Settings/SettingsAppearance';
 import { SettingsSecurity } from './Settings/SettingsSecurity';
+import { SettingsKeys, DeployTarget } from './Settings/SettingsKeys';
+import { SettingsAgent } from './Settings/SettingsAgent';
+import { SettingsInventory } from './Settings/SettingsInventory';
 import { SettingsAI } from './Settings/SettingsAI';
 import { SettingsAbout } from './Settings/SettingsAbout';
 
 interface Props {
   isOpen: boolean;
   onClose: () => void;
+  sessions?: DeployTarget[]; // Open SSH sessions keys can be deployed to
+  onServersChanged?: () => void;
 }
 
-export const SettingsModal: React.FC<Props> = ({ isOpen, onClose }) => {
+export const SettingsModal: React.FC<Props> = ({ isOpen, onClose, sessions = [], onServersChanged }) => {
   const { t, settings: globalSettings, updateSettings } = useApp();
-  const [activeTab, setActiveTab] = useState<'general' | 'appearance' | 'ai' | 'security' | 'about'>('general');
+  const [activeTab, setActiveTab] = useState<'general' | 'appearance' | 'ai' | 'security' | 'keys' | 'agent' | 'inventory' | 'about'>('general');
   const [settings, setSettings] = useState<AppSettings>(globalSettings);
 
   useEffect(() => {
@@ -75,6 +83,9 @@ export const SettingsModal: React.FC<Props> = ({ isOpen, onClose }) => {
           <TabButton id="appearance" icon={Palette} label={t('settings.appearance')} />
           <TabButton id="ai" icon={Bot} label={t('settings.ai')} />
           <TabButton id="security" icon={ShieldCheck} label={t('settings.security')} />
+          <TabButton id="keys" icon={KeyRound} label={t('settings.keys')} />
+          <TabButton id="agent" icon={Fingerprint} label={t('settings.agent')} />
+          <TabButton id="inventory" icon={ArrowLeftRight} label={t('settings.inventory')} />
 
           <div className="mt-auto pt-3 border-t border-slate-200 dark:border-dark-border/50">
             <TabButton id="about" icon={Info} label={t('settings.about')} />
@@ -96,6 +107,18 @@ export const SettingsModal: React.FC<Props> = ({ isOpen, onClose }) => {
               <SettingsSecurity settings={settings} setSettings={setSettings} />
             )}
 
+            {activeTab === 'keys' && (
+              <SettingsKeys sessions={sessions} />
+            )}
+
+            {activeTab === 'agent' && (
+              <SettingsAgent settings={settings} setSettings={setSettings} />
+            )}
+
+            {activeTab === 'inventory' && (
+              <SettingsInventory onServersChanged={onServersChanged} />
+            )}
+
             {activeTab === 'ai' && (
               <SettingsAI settings={settings} setSettings={setSettings} />
             )}
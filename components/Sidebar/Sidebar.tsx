@@ -8,7 +8,8 @@ import {
     TerminalSquare,
     PanelLeftClose,
     ChevronRight,
-    ChevronDown
+    ChevronDown,
+    ListChecks
 } from 'lucide-react';
 import { useApp } from '../../contexts/AppContext';
 import { Server } from '../../types';
@@ -19,6 +20,7 @@ interface SidebarProps {
     onNewLocal: () => void;
     onNewConnection: () => void;
     onOpenSettings: () => void;
+    onRunCommand: () => void;
     servers: Server[];
     onConnect: (server: Server) => void;
     onEditServer: (server: Server) => void;
@@ -31,6 +33,7 @@ export const Sidebar: React.FC<SidebarProps> = ({
     onNewLocal,
     onNewConnection,
     onOpenSettings,
+    onRunCommand,
     servers,
     onConnect,
     onEditServer,
@@ -159,10 +162,17 @@ export const Sidebar: React.FC<SidebarProps> = ({
             </div>
 
             {/* Settings Button */}
-            <div className="border-t border-slate-200 dark:border-dark-border p-3 flex-shrink-0">
+            <div className="border-t border-slate-200 dark:border-dark-border p-3 flex-shrink-0 flex gap-2">
+                <button
+                    onClick={onRunCommand}
+                    className="flex items-center justify-center flex-1 p-2.5 rounded-lg hover:bg-slate-50 dark:hover:bg-dark-surface group transition-colors"
+                    title={t('sidebar.run_command')}
+                >
+                    <ListChecks size={18} className="text-slate-400 dark:text-slate-500 group-hover:text-slate-600 dark:group-hover:text-slate-300" />
+                </button>
                 <button
                     onClick={onOpenSettings}
-                    className="flex items-center justify-center w-full p-2.5 rounded-lg hover:bg-slate-50 dark:hover:bg-dark-surface group transition-colors"
+                    className="flex items-center justify-center flex-1 p-2.5 rounded-lg hover:bg-slate-50 dark:hover:bg-dark-surface group transition-colors"
                     title="Settings"
                 >
                     <Settings size={18} className="text-slate-400 dark:text-slate-500 group-hover:text-slate-600 dark:group-hover:text-slate-300" />
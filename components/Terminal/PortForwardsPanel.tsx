@@ -0,0 +1,207 @@
+import React, { useState, useEffect, useCallback } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { Play, Square, ArrowRight, ChevronDown, ChevronRight, AlertCircle } from 'lucide-react';
+import { Modal } from '../Modal';
+import { useApp } from '../../contexts/AppContext';
+import { PortForwardingRule, ForwardStatus, ForwardConnection } from '../../types';
+import { simpleCn } from '../../utils';
+
+export interface ForwardSummary {
+  listening: number;
+  failed: number;
+}
+
+interface PortForwardsPanelProps {
+  isOpen: boolean;
+  onClose: () => void;
+  sessionId: string;
+  rules: PortForwardingRule[];
+  connected: boolean;
+  onSummary?: (summary: ForwardSummary) => void;
+}
+
+const formatBytes = (bytes: number) => {
+  if (bytes < 1024) return `${bytes} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+  return `${(bytes / 1024 / 1024).toFixed(1)} MB`;
+};
+
+const describe = (rule: PortForwardingRule) =>
+  rule.rule_type === 'Dynamic'
+    ? 'SOCKS'
+    : `${rule.destination_host}:${rule.destination_port}`;
+
+export const PortForwardsPanel: React.FC<PortForwardsPanelProps> = ({ isOpen, onClose, sessionId, rules, connected, onSummary }) => {
+  const { t } = useApp();
+  const [statuses, setStatuses] = useState<Record<string, ForwardStatus>>({});
+  const [connections, setConnections] = useState<Record<string, Record<number, ForwardConnection>>>({});
+  const [expanded, setExpanded] = useState<string | null>(null);
+  const [errors, setErrors] = useState<Record<string, string>>({});
+
+  const refresh = useCallback(() => {
+    invoke<ForwardStatus[]>('get_port_forwards', { sessionId })
+      .then(list => setStatuses(prev => {
+        // Only running forwards are listed; keep the last word on the rest
+        const next: Record<string, ForwardStatus> = {};
+        for (const [id, status] of Object.entries(prev)) {
+          next[id] = status.state === 'error' ? status : { ...status, state: 'stopped', active_connections: 0 };
+        }
+        for (const status of list) next[status.rule_id] = status;
+        return next;
+      }))
+      .catch(() => {});
+  }, [sessionId]);
+
+  // Forwards are started by the backend on connect, so follow them even
+  // while the panel is closed to surface failures on the toolbar
+  useEffect(() => {
+    refresh();
+    const unlistenStatus = listen<ForwardStatus>('port_forward_status', (event) => {
+      const status = event.payload;
+      if (status.session_id !== sessionId) return;
+      setStatuses(prev => ({ ...prev, [status.rule_id]: status }));
+      if (status.state === 'stopped') {
+        setConnections(prev => ({ ...prev, [status.rule_id]: {} }));
+      }
+    });
+    const unlistenConnection = listen<ForwardConnection>('port_forward_connection', (event) => {
+      const connection = event.payload;
+      if (connection.session_id !== sessionId) return;
+      setConnections(prev => {
+        const forRule = { ...(prev[connection.rule_id] || {}) };
+        if (connection.state === 'closed') {
+          delete forRule[connection.connection_id];
+        } else {
+          forRule[connection.connection_id] = connection;
+        }
+        return { ...prev, [connection.rule_id]: forRule };
+      });
+    });
+    return () => {
+      unlistenStatus.then(f => f());
+      unlistenConnection.then(f => f());
+    };
+  }, [sessionId, refresh]);
+
+  useEffect(() => {
+    if (isOpen) refresh();
+  }, [isOpen, refresh]);
+
+  useEffect(() => {
+    const all = Object.values(statuses);
+    onSummary?.({
+      listening: all.filter(s => s.state === 'listening').length,
+      failed: all.filter(s => s.state === 'error').length,
+    });
+  }, [statuses, onSummary]);
+
+  const toggleExpanded = (ruleId: string) => {
+    if (expanded === ruleId) {
+      setExpanded(null);
+      return;
+    }
+    setExpanded(ruleId);
+    invoke<ForwardConnection[]>('get_port_forward_connections', { sessionId, ruleId })
+      .then(list => setConnections(prev => ({
+        ...prev,
+        [ruleId]: Object.fromEntries(list.map(c => [c.connection_id, c])),
+      })))
+      .catch(() => {});
+  };
+
+  const start = (rule: PortForwardingRule) => {
+    setErrors(prev => ({ ...prev, [rule.id]: '' }));
+    invoke('start_port_forward', { sessionId, rule })
+      .catch(e => setErrors(prev => ({ ...prev, [rule.id]: String(e) })));
+  };
+
+  const stop = (rule: PortForwardingRule) => {
+    invoke('stop_port_forward', { sessionId, ruleId: rule.id })
+      .catch(e => setErrors(prev => ({ ...prev, [rule.id]: String(e) })));
+  };
+
+  const stateClass = (state?: string) => simpleCn(
+    "px-1.5 py-0.5 rounded text-[10px] font-bold uppercase",
+    state === 'listening' ? "bg-green-100 text-green-700 dark:bg-green-900/30 dark:text-green-300" :
+      state === 'error' ? "bg-red-100 text-red-700 dark:bg-red-900/30 dark:text-red-300" :
+        state === 'starting' ? "bg-yellow-100 text-yellow-700 dark:bg-yellow-900/30 dark:text-yellow-300" :
+          "bg-slate-100 text-slate-500 dark:bg-dark-surface dark:text-slate-400"
+  );
+
+  return (
+    <Modal isOpen={isOpen} onClose={onClose} title={t('forwards.title')} maxWidth="3xl">
+      <div className="space-y-2 max-h-[70vh] overflow-y-auto">
+        {rules.length === 0 && (
+          <div className="text-center py-4 text-sm text-slate-400">{t('forwards.none')}</div>
+        )}
+        {rules.map(rule => {
+          const status = statuses[rule.id];
+          const running = status?.state === 'listening' || status?.state === 'starting';
+          const open = Object.values(connections[rule.id] || {});
+          const error = errors[rule.id] || status?.error;
+          return (
+            <div key={rule.id} className="p-3 bg-white dark:bg-dark-bg border border-slate-200 dark:border-dark-border rounded-lg text-xs">
+              <div className="flex items-center gap-3">
+                <button
+                  onClick={() => toggleExpanded(rule.id)}
+                  disabled={!running}
+                  className="text-slate-400 hover:text-slate-700 dark:hover:text-slate-200 disabled:opacity-30"
+                >
+                  {expanded === rule.id ? <ChevronDown size={14} /> : <ChevronRight size={14} />}
+                </button>
+                <span className="px-1.5 py-0.5 rounded text-[10px] font-bold uppercase bg-slate-100 dark:bg-dark-surface text-slate-600 dark:text-slate-300">
+                  {rule.rule_type}
+                </span>
+                <span className="font-mono font-medium">{status?.bind_address || `${rule.bind_address || 'localhost'}:${rule.source_port}`}</span>
+                <ArrowRight size={12} className="text-slate-400" />
+                <span className="font-mono text-slate-600 dark:text-slate-400">{describe(rule)}</span>
+                <span className={stateClass(status?.state)}>
+                  {t(`forwards.${status?.state || 'stopped'}`)}
+                </span>
+                <div className="ml-auto flex items-center gap-3 text-slate-500">
+                  {status && (
+                    <span title={t('forwards.traffic')}>
+                      {status.active_connections}/{status.total_connections} · ↓{formatBytes(status.bytes_in)} ↑{formatBytes(status.bytes_out)}
+                    </span>
+                  )}
+                  {running ? (
+                    <button onClick={() => stop(rule)} title={t('forwards.stop')} className="p-1 rounded hover:text-red-500">
+                      <Square size={14} />
+                    </button>
+                  ) : (
+                    <button onClick={() => start(rule)} disabled={!connected} title={t('forwards.start')} className="p-1 rounded hover:text-nebula-500 disabled:opacity-30">
+                      <Play size={14} />
+                    </button>
+                  )}
+                </div>
+              </div>
+              {error && (
+                <div className="mt-2 flex items-center gap-1 text-red-500">
+                  <AlertCircle size={12} /> {error}
+                </div>
+              )}
+              {expanded === rule.id && running && (
+                <div className="mt-2 pl-7 space-y-1">
+                  {open.length === 0 && (
+                    <div className="text-slate-400">{t('forwards.no_connections')}</div>
+                  )}
+                  {open.map(connection => (
+                    <div key={connection.connection_id} className="flex items-center gap-2 font-mono text-slate-600 dark:text-slate-400">
+                      <span>{connection.peer || t('forwards.remote_peer')}</span>
+                      <ArrowRight size={10} />
+                      <span>{connection.target}</span>
+                      <span className="ml-auto text-slate-400">
+                        {new Date(connection.opened_at * 1000).toLocaleTimeString()} · ↓{formatBytes(connection.bytes_in)} ↑{formatBytes(connection.bytes_out)}
+                      </span>
+                    </div>
+                  ))}
+                </div>
+              )}
+            </div>
+          );
+        })}
+      </div>
+    </Modal>
+  );
+};
@@ -0,0 +1,210 @@
+import React, { useState, useRef, useEffect, useCallback } from 'react';
+import { Terminal } from 'xterm';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { Play, Pause, Square, Trash2, Film } from 'lucide-react';
+import { Modal } from '../Modal';
+import { useApp } from '../../contexts/AppContext';
+import { RecordingFile, CastHeader, PlaybackEvent } from '../../types';
+import { generateId, simpleCn } from '../../utils';
+
+interface RecordingPlayerProps {
+  isOpen: boolean;
+  onClose: () => void;
+}
+
+const SPEEDS = [0.5, 1, 2, 4, 8];
+// Long pauses are shortened so idle sessions do not play back in real time
+const MAX_IDLE_SECONDS = 2;
+
+export const RecordingPlayer: React.FC<RecordingPlayerProps> = ({ isOpen, onClose }) => {
+  const { t } = useApp();
+  const [recordings, setRecordings] = useState<RecordingFile[]>([]);
+  const [selected, setSelected] = useState<RecordingFile | null>(null);
+  const [playing, setPlaying] = useState(false);
+  const [paused, setPaused] = useState(false);
+  const [speed, setSpeed] = useState(1);
+  const [position, setPosition] = useState(0);
+  const containerRef = useRef<HTMLDivElement>(null);
+  const termRef = useRef<Terminal | null>(null);
+  const playbackId = useRef('');
+  const unlistenRef = useRef<() => void>(undefined);
+
+  const refresh = useCallback(() => {
+    invoke<RecordingFile[]>('list_recordings').then(setRecordings).catch(console.error);
+  }, []);
+
+  const stop = useCallback(() => {
+    invoke('stop_playback', { playbackId: playbackId.current }).catch(() => {});
+    unlistenRef.current?.();
+    unlistenRef.current = undefined;
+    setPlaying(false);
+    setPaused(false);
+  }, []);
+
+  useEffect(() => {
+    if (isOpen) refresh();
+    return () => stop();
+  }, [isOpen, refresh, stop]);
+
+  useEffect(() => {
+    if (!isOpen || !selected || !containerRef.current) return;
+    const term = new Terminal({
+      fontFamily: '"JetBrains Mono", monospace',
+      fontSize: 13,
+      cols: selected.header.width,
+      rows: selected.header.height,
+      disableStdin: true,
+    });
+    term.open(containerRef.current);
+    termRef.current = term;
+    return () => {
+      term.dispose();
+      termRef.current = null;
+    };
+  }, [isOpen, selected]);
+
+  const play = async (recording: RecordingFile) => {
+    stop();
+    termRef.current?.reset();
+    setPosition(0);
+
+    // A fresh id per run, so events of a playback still winding down can't
+    // reach the new one
+    const id = `playback-${generateId()}`;
+    playbackId.current = id;
+    const unlistenEvent = await listen<PlaybackEvent>(`playback_event_${id}`, (event) => {
+      const { time, kind, data } = event.payload;
+      const term = termRef.current;
+      setPosition(time);
+      if (!term) return;
+      if (kind === 'o') {
+        term.write(data);
+      } else if (kind === 'r') {
+        const [cols, rows] = data.split('x').map(Number);
+        if (cols > 0 && rows > 0) term.resize(cols, rows);
+      } else if (kind === 'm') {
+        term.write(`\r\n\x1b[33m— ${data} —\x1b[0m\r\n`);
+      }
+    });
+    const unlistenEnd = await listen(`playback_end_${id}`, () => {
+      setPlaying(false);
+      setPaused(false);
+    });
+    unlistenRef.current = () => {
+      unlistenEvent();
+      unlistenEnd();
+    };
+
+    try {
+      await invoke<CastHeader>('play_recording', {
+        playbackId: id,
+        path: recording.path,
+        speed,
+        maxIdle: MAX_IDLE_SECONDS,
+      });
+      setPlaying(true);
+    } catch (e) {
+      console.error('Playback failed:', e);
+      stop();
+    }
+  };
+
+  const changeSpeed = (value: number) => {
+    setSpeed(value);
+    if (playing) {
+      invoke('set_playback_speed', { playbackId: playbackId.current, speed: value }).catch(console.error);
+    }
+  };
+
+  const togglePause = () => {
+    invoke('pause_playback', { playbackId: playbackId.current, paused: !paused })
+      .then(() => setPaused(!paused))
+      .catch(console.error);
+  };
+
+  const remove = async (recording: RecordingFile) => {
+    if (selected?.path === recording.path) {
+      stop();
+      setSelected(null);
+    }
+    await invoke('delete_recording', { path: recording.path }).catch(console.error);
+    refresh();
+  };
+
+  return (
+    <Modal isOpen={isOpen} onClose={() => { stop(); onClose(); }} title={t('terminal.recordings')} maxWidth="5xl">
+      <div className="flex gap-4 h-[70vh]">
+        <div className="w-64 flex-shrink-0 overflow-y-auto space-y-1">
+          {recordings.length === 0 && (
+            <div className="text-sm text-slate-400 p-2">{t('terminal.no_recordings')}</div>
+          )}
+          {recordings.map(recording => (
+            <div
+              key={recording.path}
+              onClick={() => { stop(); setSelected(recording); }}
+              className={simpleCn(
+                "group flex items-center gap-2 px-2 py-1.5 rounded-md cursor-pointer text-sm",
+                selected?.path === recording.path
+                  ? "bg-nebula-500/20 text-nebula-600 dark:text-nebula-300"
+                  : "text-slate-600 dark:text-slate-300 hover:bg-slate-100 dark:hover:bg-dark-surface"
+              )}
+            >
+              <Film size={14} className="flex-shrink-0" />
+              <div className="min-w-0 flex-1">
+                <div className="truncate">{recording.header.title || recording.name}</div>
+                <div className="text-xs text-slate-400">{new Date(recording.modified * 1000).toLocaleString()}</div>
+              </div>
+              <button
+                onClick={(e) => { e.stopPropagation(); remove(recording); }}
+                className="opacity-0 group-hover:opacity-100 text-slate-400 hover:text-red-500"
+              >
+                <Trash2 size={14} />
+              </button>
+            </div>
+          ))}
+        </div>
+
+        <div className="flex-1 min-w-0 flex flex-col gap-2">
+          {selected && (
+            <>
+              <div className="flex items-center gap-2">
+                {playing ? (
+                  <>
+                    <button onClick={togglePause} className="p-1.5 rounded bg-slate-100 dark:bg-dark-surface" title={paused ? t('terminal.play') : t('terminal.pause')}>
+                      {paused ? <Play size={14} /> : <Pause size={14} />}
+                    </button>
+                    <button onClick={stop} className="p-1.5 rounded bg-slate-100 dark:bg-dark-surface">
+                      <Square size={14} />
+                    </button>
+                  </>
+                ) : (
+                  <button onClick={() => play(selected)} className="p-1.5 rounded bg-nebula-500 text-white" title={t('terminal.play')}>
+                    <Play size={14} />
+                  </button>
+                )}
+                <span className="text-xs text-slate-400 tabular-nums">{position.toFixed(1)}s</span>
+                <div className="ml-auto flex items-center gap-1 text-xs text-slate-500">
+                  {t('terminal.speed')}
+                  {SPEEDS.map(value => (
+                    <button
+                      key={value}
+                      onClick={() => changeSpeed(value)}
+                      className={simpleCn(
+                        "px-1.5 py-0.5 rounded",
+                        speed === value ? "bg-nebula-500 text-white" : "hover:bg-slate-100 dark:hover:bg-dark-surface"
+                      )}
+                    >
+                      {value}x
+                    </button>
+                  ))}
+                </div>
+              </div>
+              <div ref={containerRef} className="flex-1 overflow-auto bg-black rounded-md p-2" />
+            </>
+          )}
+        </div>
+      </div>
+    </Modal>
+  );
+};
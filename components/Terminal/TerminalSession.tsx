@@ -1,8 +1,8 @@
 import React, { useState, useRef, useEffect, useCallback } from 'react';
-import { Session, ConnectionStatus, Pane } from '../../types';
+import { Session, ConnectionStatus, Pane, CertificateInfo, ReconnectStatus, RecordingInfo } from '../../types';
 import { vfs } from '../../services/mockFileSystem';
 import { simpleCn } from '../../utils';
-import { Terminal as TerminalIcon, FolderOpen, Activity, Command, Sparkles, MessageSquare, Wrench, Lightbulb, SplitSquareHorizontal, SplitSquareVertical, X, AlertCircle, Unplug, RefreshCw } from 'lucide-react';
+import { Terminal as TerminalIcon, FolderOpen, Activity, Command, Sparkles, MessageSquare, Wrench, Lightbulb, SplitSquareHorizontal, SplitSquareVertical, X, AlertCircle, Unplug, RefreshCw, KeyRound, Circle, Film, Network } from 'lucide-react';
 import { SFTPBrowser } from '../SFTP/SFTPBrowser';
 import { SystemDashboard } from './SystemDashboard';
 import { SnippetPanel } from './SnippetPanel';
@@ -16,6 +16,8 @@ import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { Server } from '../../types';
 import { FileEditor } from '../FileEditor';
+import { RecordingPlayer } from './RecordingPlayer';
+import { PortForwardsPanel, ForwardSummary } from './PortForwardsPanel';
 
 interface Props {
   session: Session;
@@ -325,6 +327,14 @@ function TerminalSessionComponent({ session, server, active, activeView, paneId,
 
   const unlistenDataRef = useRef<() => void>(undefined);
   const unlistenCloseRef = useRef<() => void>(undefined);
+  const unlistenAgentRef = useRef<() => void>(undefined);
+  const unlistenReconnectRef = useRef<() => void>(undefined);
+  const [reconnectMax, setReconnectMax] = useState(MAX_RECONNECT_ATTEMPTS);
+  const [agentForwarding, setAgentForwarding] = useState(false);
+  const [recording, setRecording] = useState<RecordingInfo | null>(null);
+  const [showRecordings, setShowRecordings] = useState(false);
+  const [showForwards, setShowForwards] = useState(false);
+  const [forwardSummary, setForwardSummary] = useState<ForwardSummary>({ listening: 0, failed: 0 });
   const reconnectTimerRef = useRef<NodeJS.Timeout>(undefined);
   const reconnectAttemptRef = useRef(0);
 
@@ -378,8 +388,16 @@ function TerminalSessionComponent({ session, server, active, activeView, paneId,
       // Cleanup previous listeners if any
       if (unlistenDataRef.current) unlistenDataRef.current();
       if (unlistenCloseRef.current) unlistenCloseRef.current();
+      if (unlistenAgentRef.current) unlistenAgentRef.current();
       if (reconnectTimerRef.current) clearTimeout(reconnectTimerRef.current);
 
+      // Flag the session while the server can use our agent
+      if (!isLocal) {
+        unlistenAgentRef.current = await listen<boolean>(`agent_forwarding_${session.id}`, (event) => {
+          setAgentForwarding(event.payload);
+        });
+      }
+
       // Listen for data from backend
       unlistenDataRef.current = await listen<number[]>(`${eventPrefix}_data_${session.id}`, (event) => {
         const data = new Uint8Array(event.payload);
@@ -389,26 +407,41 @@ function TerminalSessionComponent({ session, server, active, activeView, paneId,
       unlistenCloseRef.current = await listen(`${eventPrefix}_close_${session.id}`, () => {
         term.writeln('\r\n\x1b[33mConnection closed.\x1b[0m');
         onUpdateSession(session.id, { status: ConnectionStatus.DISCONNECTED });
+        // Lost SSH connections are retried by the backend before it closes
+        setIsReconnecting(false);
+      });
 
-        // Auto-reconnect logic
-        if (settings.auto_reconnect && !isLocal) {
-          if (reconnectAttemptRef.current < MAX_RECONNECT_ATTEMPTS) {
-            const delay = RECONNECT_DELAY_BASE * Math.pow(1.5, reconnectAttemptRef.current);
-            term.writeln(`\r\n\x1b[90mRetrying in ${Math.round(delay / 1000)}s...\x1b[0m`);
-
-            reconnectTimerRef.current = setTimeout(() => {
-              reconnectAttemptRef.current++;
-              setReconnectAttempt(reconnectAttemptRef.current);
-              connect(true);
-            }, delay);
-          } else {
-            term.writeln('\r\n\x1b[31mMax reconnection attempts reached.\x1b[0m');
-            setIsReconnecting(false);
+      if (!isLocal) {
+        unlistenReconnectRef.current?.();
+        const unlistenReconnecting = await listen<ReconnectStatus>(`ssh_reconnecting_${session.id}`, (event) => {
+          const { attempt, max_attempts, delay_ms, error } = event.payload;
+          if (attempt === 1) {
+            term.writeln(`\r\n\x1b[33mConnection lost: ${error}\x1b[0m`);
           }
-        } else {
+          term.writeln(`\x1b[90mReconnecting in ${Math.round(delay_ms / 1000)}s (attempt ${attempt}/${max_attempts})...\x1b[0m`);
+          reconnectAttemptRef.current = attempt;
+          setReconnectAttempt(attempt);
+          setReconnectMax(max_attempts);
+          setIsReconnecting(true);
+          setAgentForwarding(false);
+          onUpdateSession(session.id, { status: ConnectionStatus.CONNECTING });
+        });
+        const unlistenReconnected = await listen(`ssh_reconnected_${session.id}`, () => {
+          term.writeln('\x1b[32m✓ Reconnected.\x1b[0m\r\n');
+          reconnectAttemptRef.current = 0;
+          setReconnectAttempt(0);
           setIsReconnecting(false);
-        }
-      });
+          onUpdateSession(session.id, { status: ConnectionStatus.CONNECTED });
+        });
+        const unlistenReconnectFailed = await listen<string>(`ssh_reconnect_failed_${session.id}`, (event) => {
+          term.writeln(`\r\n\x1b[31mReconnection failed: ${event.payload}\x1b[0m`);
+        });
+        unlistenReconnectRef.current = () => {
+          unlistenReconnecting();
+          unlistenReconnected();
+          unlistenReconnectFailed();
+        };
+      }
 
       // Connect
       if (isLocal) {
@@ -424,11 +457,26 @@ function TerminalSessionComponent({ session, server, active, activeView, paneId,
           port: server.port || 22,
           username: server.username,
           password: server.password,
-          privateKey: null
+          privateKey: null,
+          // Stored secrets are resolved by the backend from the server id
+          serverId: typeof server.id === 'number' ? server.id : null
         });
       }
 
       term.writeln('\x1b[32m✓ Connection established.\x1b[0m\r\n');
+
+      // Warn before the login certificate runs out
+      if (!isLocal && typeof server.id === 'number') {
+        invoke<CertificateInfo | null>('get_server_certificate', { serverId: server.id })
+          .then(cert => {
+            if (cert?.expired) {
+              term.writeln(`\x1b[33m⚠ ${t('terminal.certificate_expired')}\x1b[0m`);
+            } else if (cert?.expires_soon) {
+              term.writeln(`\x1b[33m⚠ ${t('terminal.certificate_expiring')} ${new Date(cert.valid_before * 1000).toLocaleString()}\x1b[0m`);
+            }
+          })
+          .catch(() => {});
+      }
       onUpdateSession(session.id, { status: ConnectionStatus.CONNECTED });
       setIsReconnecting(false);
       reconnectAttemptRef.current = 0;
@@ -477,6 +525,24 @@ function TerminalSessionComponent({ session, server, active, activeView, paneId,
     }
   }, [server, session.id, settings.auto_reconnect, onUpdateSession, setReconnectAttempt, setIsReconnecting, xtermRef, fitAddonRef]);
 
+  // Recordings may also be started by the server's auto-record policy
+  useEffect(() => {
+    invoke<RecordingInfo | null>('get_recording_status', { sessionId: session.id })
+      .then(setRecording)
+      .catch(() => {});
+    const unlisten = listen<RecordingInfo | null>(`recording_status_${session.id}`, (event) => {
+      setRecording(event.payload);
+    });
+    return () => {
+      unlisten.then(f => f());
+    };
+  }, [session.id]);
+
+  const toggleRecording = () => {
+    const command = recording ? 'stop_recording' : 'start_recording';
+    invoke(command, { sessionId: session.id }).catch(e => console.error('Recording failed:', e));
+  };
+
   // Initialize Terminal
   useEffect(() => {
     if (xtermRef.current) return;
@@ -563,6 +629,8 @@ function TerminalSessionComponent({ session, server, active, activeView, paneId,
       window.removeEventListener('resize', handleResize);
       if (unlistenDataRef.current) unlistenDataRef.current();
       if (unlistenCloseRef.current) unlistenCloseRef.current();
+      if (unlistenAgentRef.current) unlistenAgentRef.current();
+      if (unlistenReconnectRef.current) unlistenReconnectRef.current();
       if (reconnectTimerRef.current) clearTimeout(reconnectTimerRef.current);
 
       term.dispose();
@@ -725,6 +793,17 @@ function TerminalSessionComponent({ session, server, active, activeView, paneId,
         </button>
       )}
 
+      {/* Agent forwarding warning */}
+      {agentForwarding && (
+        <div
+          className="absolute top-2 left-2 z-10 flex items-center gap-1 px-2 py-0.5 rounded-md bg-amber-500/20 border border-amber-500/40 text-amber-400 text-xs"
+          title={t('terminal.agent_forwarding_desc')}
+        >
+          <KeyRound size={12} />
+          {t('terminal.agent_forwarding')}
+        </div>
+      )}
+
       {/* Disconnected / Reconnecting Overlay */}
       {(session.status === ConnectionStatus.DISCONNECTED || session.status === ConnectionStatus.FAILED || isReconnecting) && (
         <div className="absolute inset-0 z-20 flex flex-col items-center justify-center bg-slate-950/80 backdrop-blur-sm">
@@ -732,12 +811,16 @@ function TerminalSessionComponent({ session, server, active, activeView, paneId,
             <div className="flex flex-col items-center gap-3">
               <div className="animate-spin rounded-full h-8 w-8 border-b-2 border-nebula-500"></div>
               <span className="text-slate-300 font-medium">
-                {t('terminal.reconnecting')} {reconnectAttempt > 0 && `(${reconnectAttempt}/${MAX_RECONNECT_ATTEMPTS})`}
+                {t('terminal.reconnecting')} {reconnectAttempt > 0 && `(${reconnectAttempt}/${reconnectMax})`}
               </span>
               <button
                 onClick={() => {
                   setIsReconnecting(false);
                   if (reconnectTimerRef.current) clearTimeout(reconnectTimerRef.current);
+                  // Stops a reconnect running in the backend
+                  if (server.protocol !== 'local') {
+                    invoke('disconnect_ssh', { id: session.id }).catch(() => {});
+                  }
                   reconnectAttemptRef.current = 0;
                   setReconnectAttempt(0);
                 }}
@@ -774,7 +857,7 @@ function TerminalSessionComponent({ session, server, active, activeView, paneId,
       )}
       <div className="flex-1 flex flex-col min-h-0 overflow-hidden relative bg-slate-50 dark:bg-dark-bg">
         {/* Top Bar */}
-        <div className="h-10 bg-slate-100/50 dark:bg-dark-surface/30 border-b border-slate-200 dark:border-dark-border flex items-center justify-center px-4 flex-shrink-0 backdrop-blur-sm">
+        <div className="relative h-10 bg-slate-100/50 dark:bg-dark-surface/30 border-b border-slate-200 dark:border-dark-border flex items-center justify-center px-4 flex-shrink-0 backdrop-blur-sm">
           <div className="flex items-center gap-1 px-1 py-1 bg-white dark:bg-dark-surface rounded-lg border border-slate-200 dark:border-dark-border shadow-sm">
             <button
               onClick={() => onUpdatePane?.(paneId, { activeView: 'terminal' })}
@@ -813,7 +896,56 @@ function TerminalSessionComponent({ session, server, active, activeView, paneId,
               {t('tabs.monitor')}
             </button>
           </div>
+          <div className="absolute right-4 flex items-center gap-1">
+            <button
+              onClick={toggleRecording}
+              disabled={session.status !== ConnectionStatus.CONNECTED && !recording}
+              title={recording ? t('terminal.stop_recording') : t('terminal.start_recording')}
+              className={simpleCn(
+                "px-2 py-1 rounded-md text-[11px] font-medium flex items-center gap-1 transition-colors disabled:opacity-40",
+                recording
+                  ? "bg-red-500/15 text-red-500"
+                  : "text-slate-500 hover:text-slate-800 dark:hover:text-slate-200 hover:bg-slate-100 dark:hover:bg-dark-bg"
+              )}
+            >
+              <Circle size={10} className={recording ? "fill-red-500 animate-pulse" : ""} />
+              {recording && t('terminal.recording')}
+            </button>
+            <button
+              onClick={() => setShowRecordings(true)}
+              title={t('terminal.recordings')}
+              className="p-1 rounded-md text-slate-500 hover:text-slate-800 dark:hover:text-slate-200 hover:bg-slate-100 dark:hover:bg-dark-bg"
+            >
+              <Film size={14} />
+            </button>
+            {server.protocol !== 'local' && (
+              <button
+                onClick={() => setShowForwards(true)}
+                title={forwardSummary.failed > 0 ? t('forwards.failed') : t('forwards.title')}
+                className={simpleCn(
+                  "px-1 py-1 rounded-md text-[11px] font-medium flex items-center gap-1 hover:bg-slate-100 dark:hover:bg-dark-bg",
+                  forwardSummary.failed > 0
+                    ? "text-red-500"
+                    : "text-slate-500 hover:text-slate-800 dark:hover:text-slate-200"
+                )}
+              >
+                <Network size={14} />
+                {forwardSummary.listening > 0 && forwardSummary.listening}
+              </button>
+            )}
+          </div>
         </div>
+        <RecordingPlayer isOpen={showRecordings} onClose={() => setShowRecordings(false)} />
+        {server.protocol !== 'local' && (
+          <PortForwardsPanel
+            isOpen={showForwards}
+            onClose={() => setShowForwards(false)}
+            sessionId={session.id}
+            rules={server.forwarding_rules || []}
+            connected={session.status === ConnectionStatus.CONNECTED}
+            onSummary={setForwardSummary}
+          />
+        )}
 
         {/* View Content */}
         <div className="flex-1 min-h-0 relative">
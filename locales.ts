@@ -25,6 +25,7 @@ export const translations = {
       new_btn: 'New',
       collapse: 'Collapse Sidebar',
       expand: 'Expand Sidebar',
+      run_command: 'Run on Servers',
     },
     right_sidebar: {
       ai: 'AI Assistant',
@@ -75,6 +76,24 @@ export const translations = {
       test_connection: 'Test Connection',
       testing: 'Testing...',
       settings_title: 'Preferences',
+      forward_agent: 'Forward SSH agent',
+      forward_agent_desc: 'Anyone with root on this server can use your keys while connected.',
+      record_sessions: 'Record sessions',
+      record_sessions_desc: 'Every session to this server is saved as an asciicast recording.',
+      certificate: 'Certificate',
+      certificate_principals: 'Principals',
+      certificate_any: 'any',
+      certificate_valid_until: 'Valid until',
+      certificate_expired: 'Expired on',
+      key: 'Key',
+      key_none: 'None (password, agent or ~/.ssh keys)',
+      password_saved: 'A password is saved; leave blank to keep it.',
+      password_reveal: 'Show',
+      password_copy: 'Copy',
+      password_remove: 'Remove',
+      password_removed: 'The saved password will be removed.',
+      password_keep: 'Keep it',
+      password_copied: 'Copied; the clipboard is cleared in {secs}s.',
     },
     settings: {
       title: 'Settings',
@@ -107,6 +126,24 @@ export const translations = {
       ai_desc: 'Configure your AI assistant provider and settings.',
       security: 'Security',
       security_desc: 'Manage your master password and security settings.',
+      session_log: 'Session Logs',
+      session_log_desc: 'Append everything terminals print to plain-text log files.',
+      session_log_enabled: 'Log terminal sessions',
+      session_log_timestamps: 'Prefix lines with timestamps',
+      session_log_strip_ansi: 'Strip colors and escape codes',
+      session_log_directory: 'Log directory',
+      session_log_directory_default: 'Default (app data/logs/sessions)',
+      session_log_pattern: 'Subdirectories',
+      session_log_pattern_desc: '{server}, {host} and {date} are replaced, e.g. {server}/{date}',
+      session_log_max_size: 'Max file size (MB)',
+      session_log_retention: 'Keep for (days)',
+      session_log_saved: 'Saved; applies to new sessions',
+      keys: 'SSH Keys',
+      keys_desc: 'Generate, import and deploy the keys saved in NebulaSSH.',
+      agent: 'SSH Agent',
+      agent_desc: 'Unlock stored keys into the built-in agent for logins, forwarding and local terminals.',
+      inventory: 'Import / Export',
+      inventory_desc: 'Import hosts from an OpenSSH config file or export your servers.',
     },
     terminal: {
       welcome: 'Welcome to Nebula SSH',
@@ -123,6 +160,18 @@ export const translations = {
       connection_failed: 'Connection Failed',
       disconnected: 'Disconnected',
       reconnect: 'Reconnect',
+      agent_forwarding: 'Agent forwarded',
+      agent_forwarding_desc: 'This server can use your SSH keys while the session is open',
+      certificate_expiring: 'Your SSH certificate expires at',
+      certificate_expired: 'Your SSH certificate has expired; the key was used on its own',
+      start_recording: 'Start recording',
+      stop_recording: 'Stop recording',
+      recording: 'REC',
+      recordings: 'Recordings',
+      no_recordings: 'No recordings yet',
+      play: 'Play',
+      pause: 'Pause',
+      speed: 'Speed',
     },
     security: {
       locked_title: 'Nebula SSH Locked',
@@ -224,6 +273,138 @@ export const translations = {
       execute: 'Execute',
       add_to_snippets: 'Add to Snippets',
       save_snippet_title: 'Save as Snippet',
+    },
+    fanout: {
+      title: 'Run on Servers',
+      new_run: 'New Run',
+      history: 'Past Runs',
+      no_runs: 'No runs yet',
+      command_placeholder: 'Command to run on every matching server',
+      group: 'Group',
+      any_group: 'Any group',
+      tags: 'Tags (any of)',
+      parallelism: 'Parallelism',
+      timeout: 'Timeout (s, 0 = none)',
+      targets: 'Matching servers',
+      run: 'Run',
+      stop: 'Stop',
+      host: 'Host',
+      status: 'Status',
+      exit: 'Exit',
+      duration: 'Duration',
+      succeeded: 'succeeded',
+      failed: 'failed',
+      truncated: 'Output truncated',
+      state_waiting: 'Waiting',
+      state_connecting: 'Connecting',
+      state_running: 'Running',
+      state_ok: 'Succeeded',
+      state_failed: 'Failed',
+      state_error: 'Error',
+      state_timed_out: 'Timed out',
+      state_cancelled: 'Cancelled',
+    },
+    prompts: {
+      host_key_title: 'Unknown Host',
+      host_key_desc: 'The authenticity of this host cannot be established:',
+      host_key_hint: 'Compare the fingerprint with the one your administrator gave you. Trusting it adds the key to known hosts.',
+      trust: 'Trust and Connect',
+      reject: 'Reject',
+      passphrase_title: 'Key Passphrase',
+      passphrase_desc: 'Enter the passphrase for',
+      passphrase_retry: 'Wrong passphrase, try again.',
+      passphrase: 'Passphrase',
+      remember: 'Remember for this server',
+      cancel: 'Cancel',
+      submit: 'Continue',
+      challenge_title: 'Authentication Required',
+      agent_title: 'Allow use of agent key?',
+      agent_desc: 'wants to sign with the agent key',
+      allow: 'Allow',
+      deny: 'Deny',
+      vault_title: 'Confirm master password',
+      vault_desc: 'Enter your master password to access saved secrets.',
+    },
+    keys: {
+      generate: 'Generate',
+      import: 'Import',
+      name: 'Name',
+      name_required: 'Enter a name for the key',
+      type: 'Type',
+      bits: 'Size',
+      default_bits: 'Default',
+      comment: 'Comment',
+      source: 'Private key',
+      source_ph: 'Paste a PEM, OpenSSH or PuTTY key, or enter a file path',
+      browse: 'Choose file...',
+      passphrase: 'Passphrase (optional)',
+      import_passphrase: 'Passphrase of the key, if any',
+      empty: 'No keys saved yet.',
+      copy_public: 'Copy public key',
+      export_public: 'Save public key',
+      delete: 'Delete',
+      delete_confirm: 'Delete this key? Servers using it will fall back to other login methods.',
+      change_passphrase: 'Change passphrase',
+      set_passphrase: 'Set passphrase',
+      current_passphrase: 'Current passphrase',
+      new_passphrase: 'New passphrase',
+      new_passphrase_ph: 'Leave empty to remove',
+      passphrase_changed: 'Passphrase updated',
+      copied: 'Public key copied',
+      exported: 'Public key saved to',
+      deploy: 'Deploy to server',
+      deploy_to: 'Session',
+      deployed: 'Key added to authorized_keys',
+      already_deployed: 'The server already has this key',
+      no_sessions: 'Open an SSH session to deploy the key to.',
+    },
+    agent: {
+      local_terminals: 'Use in local terminals',
+      local_terminals_desc: 'Set SSH_AUTH_SOCK in new local terminals so ssh and git use the built-in agent.',
+      no_socket: 'The agent socket is not available on this platform.',
+      add_key: 'Add key',
+      key: 'Key',
+      choose_key: 'Choose a key',
+      lifetime: 'Lifetime',
+      lifetime_forever: 'Until locked',
+      hours: 'hours',
+      confirm: 'Confirm each use',
+      confirm_desc: 'Ask before the key signs anything, like ssh-add -c.',
+      add: 'Add to agent',
+      loaded: 'Loaded keys',
+      none_loaded: 'No keys in the agent.',
+      remove: 'Remove',
+      remove_all: 'Remove all',
+    },
+    forwards: {
+      title: 'Port forwards',
+      none: 'No forwarding rules configured for this server.',
+      start: 'Start',
+      stop: 'Stop',
+      starting: 'Starting',
+      listening: 'Listening',
+      stopped: 'Stopped',
+      error: 'Error',
+      failed: 'A port forward failed',
+      traffic: 'Active/total connections and traffic',
+      no_connections: 'No open connections.',
+      remote_peer: 'remote',
+    },
+    inventory: {
+      import: 'Import from ssh_config',
+      config_path: 'Config file',
+      preview: 'Preview',
+      no_hosts: 'No hosts found in this file.',
+      create: 'New',
+      update: 'Update',
+      unchanged: 'Unchanged',
+      import_selected: 'Import selected',
+      imported: '{created} created, {updated} updated, {unchanged} unchanged',
+      export: 'Export servers',
+      format: 'Format',
+      include_secrets: 'Include saved passwords and passphrases',
+      include_secrets_desc: 'The file will hold secrets in plain text. Requires your master password.',
+      exported: 'Exported {count} servers to',
     }
   },
   zh: {
@@ -251,6 +432,7 @@ export const translations = {
       new_btn: '新建',
       collapse: '收起侧边栏',
       expand: '展开侧边栏',
+      run_command: '批量执行',
     },
     right_sidebar: {
       ai: 'AI 助手',
@@ -301,6 +483,24 @@ export const translations = {
       test_connection: '测试连接',
       testing: '测试中...',
       settings_title: '偏好设置',
+      forward_agent: '转发 SSH 代理',
+      forward_agent_desc: '连接期间，拥有此服务器 root 权限的人都可以使用你的密钥。',
+      record_sessions: '录制会话',
+      record_sessions_desc: '与此服务器的每个会话都会保存为 asciicast 录像。',
+      certificate: '证书',
+      certificate_principals: '主体',
+      certificate_any: '任意',
+      certificate_valid_until: '有效期至',
+      certificate_expired: '已于以下时间过期',
+      key: '密钥',
+      key_none: '无（密码、代理或 ~/.ssh 密钥）',
+      password_saved: '已保存密码；留空则保留。',
+      password_reveal: '显示',
+      password_copy: '复制',
+      password_remove: '移除',
+      password_removed: '保存后将移除已保存的密码。',
+      password_keep: '保留',
+      password_copied: '已复制；剪贴板将在 {secs} 秒后清除。',
     },
     settings: {
       title: '设置',
@@ -333,6 +533,24 @@ export const translations = {
       ai_desc: '配置您的 AI 助手提供商和参数。',
       security: '安全',
       security_desc: '管理您的主密码和安全设置。',
+      session_log: '会话日志',
+      session_log_desc: '将终端输出的所有内容追加到纯文本日志文件。',
+      session_log_enabled: '记录终端会话',
+      session_log_timestamps: '在每行前添加时间戳',
+      session_log_strip_ansi: '去除颜色和转义序列',
+      session_log_directory: '日志目录',
+      session_log_directory_default: '默认（应用数据/logs/sessions）',
+      session_log_pattern: '子目录',
+      session_log_pattern_desc: '{server}、{host} 和 {date} 会被替换，例如 {server}/{date}',
+      session_log_max_size: '单个文件上限（MB）',
+      session_log_retention: '保留天数',
+      session_log_saved: '已保存，对新会话生效',
+      keys: 'SSH 密钥',
+      keys_desc: '生成、导入和部署保存在 NebulaSSH 中的密钥。',
+      agent: 'SSH 代理',
+      agent_desc: '将保存的密钥解锁到内置代理，用于登录、代理转发和本地终端。',
+      inventory: '导入 / 导出',
+      inventory_desc: '从 OpenSSH 配置文件导入主机，或导出服务器。',
     },
     terminal: {
       welcome: '欢迎使用 Nebula SSH',
@@ -349,6 +567,18 @@ export const translations = {
       connection_failed: '连接失败',
       disconnected: '已断开',
       reconnect: '重新连接',
+      agent_forwarding: '代理已转发',
+      agent_forwarding_desc: '会话打开期间，此服务器可以使用你的 SSH 密钥',
+      certificate_expiring: '你的 SSH 证书将于以下时间过期：',
+      certificate_expired: '你的 SSH 证书已过期，已仅使用密钥登录',
+      start_recording: '开始录制',
+      stop_recording: '停止录制',
+      recording: '录制中',
+      recordings: '录像',
+      no_recordings: '暂无录像',
+      play: '播放',
+      pause: '暂停',
+      speed: '速度',
     },
     security: {
       locked_title: 'Nebula SSH 已锁定',
@@ -450,6 +680,138 @@ export const translations = {
       execute: '执行',
       add_to_snippets: '添加到指令',
       save_snippet_title: '保存为指令',
+    },
+    fanout: {
+      title: '批量执行',
+      new_run: '新建执行',
+      history: '历史记录',
+      no_runs: '暂无执行记录',
+      command_placeholder: '在所有匹配的服务器上执行的命令',
+      group: '分组',
+      any_group: '任意分组',
+      tags: '标签（任一）',
+      parallelism: '并发数',
+      timeout: '超时（秒，0 为不限）',
+      targets: '匹配的服务器',
+      run: '执行',
+      stop: '停止',
+      host: '主机',
+      status: '状态',
+      exit: '退出码',
+      duration: '耗时',
+      succeeded: '成功',
+      failed: '失败',
+      truncated: '输出已截断',
+      state_waiting: '等待中',
+      state_connecting: '连接中',
+      state_running: '执行中',
+      state_ok: '成功',
+      state_failed: '失败',
+      state_error: '错误',
+      state_timed_out: '超时',
+      state_cancelled: '已取消',
+    },
+    prompts: {
+      host_key_title: '未知主机',
+      host_key_desc: '无法确认此主机的真实性：',
+      host_key_hint: '请与管理员提供的指纹进行核对。信任后该密钥将加入已知主机列表。',
+      trust: '信任并连接',
+      reject: '拒绝',
+      passphrase_title: '密钥口令',
+      passphrase_desc: '请输入以下密钥的口令',
+      passphrase_retry: '口令错误，请重试。',
+      passphrase: '口令',
+      remember: '为此服务器记住',
+      cancel: '取消',
+      submit: '继续',
+      challenge_title: '需要身份验证',
+      agent_title: '允许使用代理密钥？',
+      agent_desc: '请求使用代理密钥签名：',
+      allow: '允许',
+      deny: '拒绝',
+      vault_title: '确认主密码',
+      vault_desc: '输入主密码以访问已保存的机密。',
+    },
+    keys: {
+      generate: '生成',
+      import: '导入',
+      name: '名称',
+      name_required: '请输入密钥名称',
+      type: '类型',
+      bits: '长度',
+      default_bits: '默认',
+      comment: '注释',
+      source: '私钥',
+      source_ph: '粘贴 PEM、OpenSSH 或 PuTTY 密钥，或输入文件路径',
+      browse: '选择文件...',
+      passphrase: '密码短语（可选）',
+      import_passphrase: '密钥的密码短语（如有）',
+      empty: '尚未保存任何密钥。',
+      copy_public: '复制公钥',
+      export_public: '保存公钥',
+      delete: '删除',
+      delete_confirm: '删除此密钥？使用它的服务器将改用其他登录方式。',
+      change_passphrase: '修改密码短语',
+      set_passphrase: '设置密码短语',
+      current_passphrase: '当前密码短语',
+      new_passphrase: '新密码短语',
+      new_passphrase_ph: '留空以移除',
+      passphrase_changed: '密码短语已更新',
+      copied: '公钥已复制',
+      exported: '公钥已保存到',
+      deploy: '部署到服务器',
+      deploy_to: '会话',
+      deployed: '密钥已添加到 authorized_keys',
+      already_deployed: '服务器上已有此密钥',
+      no_sessions: '请先打开一个 SSH 会话以部署密钥。',
+    },
+    agent: {
+      local_terminals: '用于本地终端',
+      local_terminals_desc: '在新的本地终端中设置 SSH_AUTH_SOCK，让 ssh 和 git 使用内置代理。',
+      no_socket: '此平台上没有代理套接字。',
+      add_key: '添加密钥',
+      key: '密钥',
+      choose_key: '选择密钥',
+      lifetime: '有效期',
+      lifetime_forever: '直到锁定',
+      hours: '小时',
+      confirm: '每次使用前确认',
+      confirm_desc: '密钥签名前先询问，类似 ssh-add -c。',
+      add: '添加到代理',
+      loaded: '已加载的密钥',
+      none_loaded: '代理中没有密钥。',
+      remove: '移除',
+      remove_all: '全部移除',
+    },
+    forwards: {
+      title: '端口转发',
+      none: '此服务器未配置转发规则。',
+      start: '启动',
+      stop: '停止',
+      starting: '启动中',
+      listening: '监听中',
+      stopped: '已停止',
+      error: '错误',
+      failed: '有端口转发失败',
+      traffic: '活动/总连接数与流量',
+      no_connections: '没有打开的连接。',
+      remote_peer: '远程',
+    },
+    inventory: {
+      import: '从 ssh_config 导入',
+      config_path: '配置文件',
+      preview: '预览',
+      no_hosts: '此文件中未找到主机。',
+      create: '新建',
+      update: '更新',
+      unchanged: '未变化',
+      import_selected: '导入所选',
+      imported: '新建 {created}，更新 {updated}，未变化 {unchanged}',
+      export: '导出服务器',
+      format: '格式',
+      include_secrets: '包含已保存的密码和口令',
+      include_secrets_desc: '文件将以明文保存机密，需要主密码。',
+      exported: '已导出 {count} 台服务器到',
     }
   }
 };
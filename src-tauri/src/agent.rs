@@ -0,0 +1,436 @@
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::rsa::Rsa;
+use serde::Serialize;
+use signature::Signer;
+use ssh_encoding::Encode;
+use ssh_key::private::RsaKeypair;
+use ssh_key::{HashAlg, PrivateKey};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use crate::db::Database;
+use crate::keys::{load_key, put_string, read_string};
+use crate::prompts::{new_request_id, PromptAnswer, PromptState};
+
+// SSH agent protocol (draft-miller-ssh-agent) message numbers
+pub(crate) const SSH_AGENT_FAILURE: u8 = 5;
+const SSH_AGENT_SUCCESS: u8 = 6;
+pub(crate) const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+const SSH_AGENT_IDENTITIES_ANSWER: u8 = 12;
+pub(crate) const SSH_AGENTC_SIGN_REQUEST: u8 = 13;
+const SSH_AGENT_SIGN_RESPONSE: u8 = 14;
+const SSH_AGENTC_REMOVE_IDENTITY: u8 = 18;
+const SSH_AGENTC_REMOVE_ALL_IDENTITIES: u8 = 19;
+const SSH_AGENT_RSA_SHA2_256: u32 = 2;
+const SSH_AGENT_RSA_SHA2_512: u32 = 4;
+
+pub(crate) const MAX_MESSAGE_LEN: usize = 256 * 1024;
+const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A decrypted key held by the agent
+#[derive(Clone)]
+pub struct LoadedKey {
+    pub key_id: i64,
+    pub name: String,
+    pub key: PrivateKey,
+    pub confirm: bool,
+    expires_at: Option<Instant>,
+}
+
+impl LoadedKey {
+    fn blob(&self) -> Vec<u8> {
+        let mut blob = Vec::new();
+        let _ = self.key.public_key().key_data().encode(&mut blob);
+        blob
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentKeyInfo {
+    pub key_id: i64,
+    pub name: String,
+    pub key_type: String,
+    pub fingerprint: String,
+    pub confirm: bool,
+    pub expires_in_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentStatus {
+    pub socket_path: Option<String>, // None when the socket isn't served
+    pub keys: Vec<AgentKeyInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentConfirmRequest {
+    pub request_id: String,
+    pub key_name: String,
+    pub fingerprint: String,
+    pub client: String, // Who wants the signature
+}
+
+/// Keys unlocked into the built-in agent, in the order they were added
+pub struct AgentState {
+    keys: Mutex<Vec<LoadedKey>>,
+    socket_path: Mutex<Option<PathBuf>>,
+}
+
+impl AgentState {
+    pub fn new() -> Self {
+        Self {
+            keys: Mutex::new(Vec::new()),
+            socket_path: Mutex::new(None),
+        }
+    }
+
+    /// Loaded keys that haven't outlived their lifetime
+    pub fn loaded_keys(&self) -> Vec<LoadedKey> {
+        let mut keys = self.keys.lock().unwrap();
+        keys.retain(|k| k.expires_at.map_or(true, |at| at > Instant::now()));
+        keys.clone()
+    }
+
+    fn add(&self, key: LoadedKey) {
+        let mut keys = self.keys.lock().unwrap();
+        keys.retain(|k| k.key_id != key.key_id);
+        keys.push(key);
+    }
+
+    fn remove_where(&self, matches: impl Fn(&LoadedKey) -> bool) -> bool {
+        let mut keys = self.keys.lock().unwrap();
+        let before = keys.len();
+        keys.retain(|k| !matches(k));
+        keys.len() != before
+    }
+
+    /// Drop every key, e.g. when the app locks
+    pub fn clear(&self) {
+        self.keys.lock().unwrap().clear();
+    }
+
+    /// Socket local tools can use as SSH_AUTH_SOCK
+    pub fn socket_path(&self) -> Option<PathBuf> {
+        self.socket_path.lock().unwrap().clone()
+    }
+}
+
+/// Ask the user before a key marked for confirmation signs anything
+pub fn approve(app: &AppHandle, key: &LoadedKey, client: &str) -> Result<(), String> {
+    if !key.confirm {
+        return Ok(());
+    }
+
+    let request_id = new_request_id();
+    let request = AgentConfirmRequest {
+        request_id: request_id.clone(),
+        key_name: key.name.clone(),
+        fingerprint: key.key.fingerprint(HashAlg::Sha256).to_string(),
+        client: client.to_string(),
+    };
+    match app.state::<PromptState>().ask(app, "agent_confirm_prompt", &request_id, request)? {
+        PromptAnswer::Confirm(true) => Ok(()),
+        _ => Err(format!("Use of key {} was denied", key.name)),
+    }
+}
+
+fn openssl_rsa(keypair: &RsaKeypair) -> Result<PKey<openssl::pkey::Private>, String> {
+    let bn = |m: &ssh_key::Mpint| {
+        BigNum::from_slice(m.as_positive_bytes().unwrap_or_default()).map_err(|e| e.to_string())
+    };
+    let (p, q, d) = (bn(&keypair.private.p)?, bn(&keypair.private.q)?, bn(&keypair.private.d)?);
+
+    // CRT exponents: d mod (p - 1) and d mod (q - 1)
+    let mut ctx = BigNumContext::new().map_err(|e| e.to_string())?;
+    let one = BigNum::from_u32(1).map_err(|e| e.to_string())?;
+    let crt_exponent = |prime: &BigNum, ctx: &mut BigNumContext| -> Result<BigNum, String> {
+        let mut prime_minus_one = BigNum::new().map_err(|e| e.to_string())?;
+        prime_minus_one.checked_sub(prime, &one).map_err(|e| e.to_string())?;
+        let mut exponent = BigNum::new().map_err(|e| e.to_string())?;
+        exponent.nnmod(&d, &prime_minus_one, ctx).map_err(|e| e.to_string())?;
+        Ok(exponent)
+    };
+    let dmp1 = crt_exponent(&p, &mut ctx)?;
+    let dmq1 = crt_exponent(&q, &mut ctx)?;
+
+    let rsa = Rsa::from_private_components(
+        bn(&keypair.public.n)?,
+        bn(&keypair.public.e)?,
+        d,
+        p,
+        q,
+        dmp1,
+        dmq1,
+        bn(&keypair.private.iqmp)?,
+    )
+    .map_err(|e| e.to_string())?;
+    PKey::from_rsa(rsa).map_err(|e| e.to_string())
+}
+
+/// SSH signature blob over `data`; `flags` pick the RSA hash
+fn sign(key: &PrivateKey, data: &[u8], flags: u32) -> Result<Vec<u8>, String> {
+    if let Some(rsa) = key.key_data().rsa() {
+        let (algorithm, digest) = if flags & SSH_AGENT_RSA_SHA2_512 != 0 {
+            ("rsa-sha2-512", MessageDigest::sha512())
+        } else if flags & SSH_AGENT_RSA_SHA2_256 != 0 {
+            ("rsa-sha2-256", MessageDigest::sha256())
+        } else {
+            ("ssh-rsa", MessageDigest::sha1())
+        };
+        let pkey = openssl_rsa(rsa)?;
+        let mut signer = openssl::sign::Signer::new(digest, &pkey).map_err(|e| e.to_string())?;
+        signer.update(data).map_err(|e| e.to_string())?;
+        let signature = signer.sign_to_vec().map_err(|e| e.to_string())?;
+
+        let mut blob = Vec::new();
+        put_string(&mut blob, algorithm.as_bytes());
+        put_string(&mut blob, &signature);
+        return Ok(blob);
+    }
+
+    let signature: ssh_key::Signature = key.try_sign(data).map_err(|e| e.to_string())?;
+    let mut blob = Vec::new();
+    signature.encode(&mut blob).map_err(|e| e.to_string())?;
+    Ok(blob)
+}
+
+/// Answer one agent protocol request from `client`
+pub(crate) fn handle_request(app: &AppHandle, request: &[u8], client: &str) -> Vec<u8> {
+    let agent = app.state::<AgentState>();
+    let Some((&kind, mut body)) = request.split_first() else {
+        return vec![SSH_AGENT_FAILURE];
+    };
+
+    match kind {
+        SSH_AGENTC_REQUEST_IDENTITIES => {
+            let keys = agent.loaded_keys();
+            let mut response = vec![SSH_AGENT_IDENTITIES_ANSWER];
+            response.extend_from_slice(&(keys.len() as u32).to_be_bytes());
+            for key in keys {
+                put_string(&mut response, &key.blob());
+                put_string(&mut response, key.name.as_bytes());
+            }
+            response
+        }
+        SSH_AGENTC_SIGN_REQUEST => {
+            let result = (|| -> Result<Vec<u8>, String> {
+                let blob = read_string(&mut body)?;
+                let data = read_string(&mut body)?;
+                let flags = body.get(..4).map_or(0, |b| u32::from_be_bytes(b.try_into().unwrap()));
+                let key = agent
+                    .loaded_keys()
+                    .into_iter()
+                    .find(|k| k.blob() == blob)
+                    .ok_or("Key not loaded")?;
+                approve(app, &key, client)?;
+                println!("Agent signing with {}", key.name);
+                sign(&key.key, data, flags)
+            })();
+            match result {
+                Ok(signature) => {
+                    let mut response = vec![SSH_AGENT_SIGN_RESPONSE];
+                    put_string(&mut response, &signature);
+                    response
+                }
+                Err(e) => {
+                    eprintln!("Agent sign request refused: {}", e);
+                    vec![SSH_AGENT_FAILURE]
+                }
+            }
+        }
+        SSH_AGENTC_REMOVE_IDENTITY => match read_string(&mut body) {
+            Ok(blob) if agent.remove_where(|k| k.blob() == blob) => vec![SSH_AGENT_SUCCESS],
+            _ => vec![SSH_AGENT_FAILURE],
+        },
+        SSH_AGENTC_REMOVE_ALL_IDENTITIES => {
+            agent.clear();
+            vec![SSH_AGENT_SUCCESS]
+        }
+        // Keys are only added from the app, and locking is the app lock's job
+        _ => vec![SSH_AGENT_FAILURE],
+    }
+}
+
+#[cfg(unix)]
+async fn serve_client(app: AppHandle, mut stream: tokio::net::UnixStream) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    loop {
+        let mut len = [0u8; 4];
+        if stream.read_exact(&mut len).await.is_err() {
+            return;
+        }
+        let len = u32::from_be_bytes(len) as usize;
+        if len == 0 || len > MAX_MESSAGE_LEN {
+            return;
+        }
+        let mut request = vec![0u8; len];
+        if stream.read_exact(&mut request).await.is_err() {
+            return;
+        }
+
+        // Confirmation prompts block until the user answers
+        let handle = app.clone();
+        let serve = move || handle_request(&handle, &request, "a program using the agent socket");
+        let response = match tauri::async_runtime::spawn_blocking(serve).await {
+            Ok(response) => response,
+            Err(_) => vec![SSH_AGENT_FAILURE],
+        };
+
+        let mut frame = (response.len() as u32).to_be_bytes().to_vec();
+        frame.extend_from_slice(&response);
+        if stream.write_all(&frame).await.is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(unix)]
+fn agent_socket_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = match dirs::runtime_dir() {
+        Some(dir) => dir.join("nebulassh"),
+        None => app.path().app_data_dir().map_err(|e| e.to_string())?,
+    };
+    Ok(dir.join("agent.sock"))
+}
+
+/// Serve the agent on a Unix socket only the current user can reach, and
+/// drop keys once their lifetime is up
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn({
+        let app = app.clone();
+        async move {
+            loop {
+                tokio::time::sleep(EXPIRY_CHECK_INTERVAL).await;
+                app.state::<AgentState>().loaded_keys();
+            }
+        }
+    });
+
+    #[cfg(unix)]
+    tauri::async_runtime::spawn(async move {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+        let result = async {
+            let path = agent_socket_path(&app)?;
+            if let Some(dir) = path.parent() {
+                std::fs::DirBuilder::new()
+                    .recursive(true)
+                    .mode(0o700)
+                    .create(dir)
+                    .map_err(|e| e.to_string())?;
+            }
+            // A socket left behind by a previous run
+            let _ = std::fs::remove_file(&path);
+            let listener = tokio::net::UnixListener::bind(&path).map_err(|e| e.to_string())?;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).map_err(|e| e.to_string())?;
+            Ok::<_, String>((path, listener))
+        }
+        .await;
+
+        let (path, listener) = match result {
+            Ok(bound) => bound,
+            Err(e) => {
+                eprintln!("Failed to start SSH agent: {}", e);
+                return;
+            }
+        };
+        println!("SSH agent listening on {}", path.display());
+        *app.state::<AgentState>().socket_path.lock().unwrap() = Some(path);
+
+        while let Ok((stream, _)) = listener.accept().await {
+            tauri::async_runtime::spawn(serve_client(app.clone(), stream));
+        }
+    });
+}
+
+fn key_info(key: &LoadedKey) -> AgentKeyInfo {
+    AgentKeyInfo {
+        key_id: key.key_id,
+        name: key.name.clone(),
+        key_type: key.key.algorithm().as_str().to_string(),
+        fingerprint: key.key.fingerprint(HashAlg::Sha256).to_string(),
+        confirm: key.confirm,
+        expires_in_secs: key.expires_at.map(|at| at.saturating_duration_since(Instant::now()).as_secs()),
+    }
+}
+
+#[tauri::command]
+pub fn get_agent_status(agent: State<AgentState>) -> AgentStatus {
+    AgentStatus {
+        socket_path: agent.socket_path().map(|p| p.to_string_lossy().into_owned()),
+        keys: agent.loaded_keys().iter().map(key_info).collect(),
+    }
+}
+
+/// Unlock a stored key into the agent. With `confirm` every signature needs
+/// the user's approval; `lifetime_secs` removes the key again after that long.
+#[tauri::command]
+pub fn agent_add_key(
+    db: State<Database>,
+    agent: State<AgentState>,
+    key_id: i64,
+    passphrase: Option<String>,
+    confirm: Option<bool>,
+    lifetime_secs: Option<u64>,
+) -> Result<AgentKeyInfo, String> {
+    let (record, key) = load_key(&db, key_id)?;
+    let key = if key.is_encrypted() {
+        let passphrase = passphrase.filter(|p| !p.is_empty()).ok_or("Passphrase required")?;
+        key.decrypt(passphrase).map_err(|_| "Incorrect passphrase".to_string())?
+    } else {
+        key
+    };
+
+    let loaded = LoadedKey {
+        key_id,
+        name: record.name,
+        key,
+        confirm: confirm.unwrap_or(false),
+        expires_at: lifetime_secs.filter(|&s| s > 0).map(|s| Instant::now() + Duration::from_secs(s)),
+    };
+    let info = key_info(&loaded);
+    println!("Added key {} to the agent", info.fingerprint);
+    agent.add(loaded);
+    Ok(info)
+}
+
+#[tauri::command]
+pub fn agent_remove_key(agent: State<AgentState>, key_id: i64) -> bool {
+    agent.remove_where(|k| k.key_id == key_id)
+}
+
+#[tauri::command]
+pub fn agent_remove_all(agent: State<AgentState>) {
+    agent.clear();
+}
+
+#[tauri::command]
+pub fn respond_agent_confirm(prompts: State<'_, PromptState>, request_id: String, allow: bool) -> Result<(), String> {
+    prompts.answer(&request_id, PromptAnswer::Confirm(allow))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use signature::Verifier;
+    use ssh_encoding::Decode;
+
+    #[test]
+    fn test_rsa_signatures_follow_flags() {
+        let rsa = Rsa::generate(2048).unwrap();
+        let pem = String::from_utf8(PKey::from_rsa(rsa).unwrap().private_key_to_pem_pkcs8().unwrap()).unwrap();
+        let key = crate::keys::parse_private_key(&pem, None).unwrap();
+
+        for (flags, name) in [(SSH_AGENT_RSA_SHA2_256, "rsa-sha2-256"), (SSH_AGENT_RSA_SHA2_512, "rsa-sha2-512")] {
+            let blob = sign(&key, b"data", flags).unwrap();
+            let mut reader = &blob[..];
+            assert_eq!(read_string(&mut reader).unwrap(), name.as_bytes());
+
+            let signature = ssh_key::Signature::decode(&mut &blob[..]).unwrap();
+            Verifier::verify(key.public_key().key_data(), b"data", &signature).unwrap();
+        }
+    }
+}
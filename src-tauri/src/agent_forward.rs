@@ -0,0 +1,228 @@
+use std::ffi::c_void;
+use std::io::{self, Read, Write};
+use std::os::raw::{c_char, c_int};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use libssh2_sys::{LIBSSH2_CHANNEL, LIBSSH2_SESSION};
+use ssh2::ErrorCode;
+use tauri::{AppHandle, Emitter, Manager, Window};
+use crate::agent::{
+    handle_request, AgentState, MAX_MESSAGE_LEN, SSH_AGENTC_REQUEST_IDENTITIES, SSH_AGENTC_SIGN_REQUEST,
+    SSH_AGENT_FAILURE,
+};
+use crate::transport::SshTransport;
+
+const LIBSSH2_CALLBACK_AUTHAGENT: c_int = 7;
+const LIBSSH2_ERROR_EAGAIN: c_int = -37;
+// Safety net for picking up new channels when no wakeup arrives
+const DISPATCH_FALLBACK: Duration = Duration::from_millis(500);
+
+extern "C" {
+    // Exported by libssh2, but not declared by libssh2-sys
+    fn libssh2_session_callback_set(session: *mut LIBSSH2_SESSION, cbtype: c_int, callback: *mut c_void) -> *mut c_void;
+}
+
+// auth-agent@openssh.com channels libssh2 accepted, as (session, channel),
+// waiting for `serve` to pick them up
+static OPENED: Mutex<Vec<(usize, usize)>> = Mutex::new(Vec::new());
+
+// Runs inside whichever libssh2 call read the channel open, with the
+// transport's I/O lock held, so it must only queue the channel
+extern "C" fn on_agent_channel(session: *mut LIBSSH2_SESSION, channel: *mut LIBSSH2_CHANNEL, _abstract: *mut *mut c_void) {
+    if let Ok(mut opened) = OPENED.lock() {
+        opened.push((session as usize, channel as usize));
+    }
+}
+
+fn take_opened(session: usize) -> Vec<usize> {
+    let mut opened = OPENED.lock().unwrap();
+    let (ours, rest): (Vec<_>, Vec<_>) = opened.drain(..).partition(|(s, _)| *s == session);
+    *opened = rest;
+    ours.into_iter().map(|(_, channel)| channel).collect()
+}
+
+/// A channel libssh2 opened on the server's behalf. ssh2 cannot wrap
+/// these, so it is driven through libssh2 directly.
+struct AgentChannel(*mut LIBSSH2_CHANNEL);
+
+// Only used through the transport, which serializes every libssh2 call
+unsafe impl Send for AgentChannel {}
+
+fn io_result(rc: isize) -> io::Result<usize> {
+    match rc {
+        n if n >= 0 => Ok(n as usize),
+        n if n == LIBSSH2_ERROR_EAGAIN as isize => Err(io::ErrorKind::WouldBlock.into()),
+        n => Err(io::Error::other(format!("Agent channel error {}", n))),
+    }
+}
+
+fn check(rc: c_int) -> Result<(), ssh2::Error> {
+    match rc {
+        0 => Ok(()),
+        rc => Err(ssh2::Error::new(ErrorCode::Session(rc), "Agent channel error")),
+    }
+}
+
+impl AgentChannel {
+    fn close(&mut self) -> Result<(), ssh2::Error> {
+        check(unsafe { libssh2_sys::libssh2_channel_close(self.0) })
+    }
+
+    fn free(&mut self) -> Result<(), ssh2::Error> {
+        check(unsafe { libssh2_sys::libssh2_channel_free(self.0) })
+    }
+}
+
+impl Read for AgentChannel {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        io_result(unsafe { libssh2_sys::libssh2_channel_read_ex(self.0, 0, buf.as_mut_ptr() as *mut c_char, buf.len()) })
+    }
+}
+
+impl Write for AgentChannel {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io_result(unsafe { libssh2_sys::libssh2_channel_write_ex(self.0, 0, buf.as_ptr() as *const c_char, buf.len()) })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Accept agent channels on `transport`. Call before requesting forwarding,
+/// so a channel the server opens right away is not refused.
+pub fn enable(transport: &SshTransport) {
+    set_callback(transport, on_agent_channel as *mut c_void);
+}
+
+/// Refuse agent channels again, e.g. when the server declined forwarding
+pub fn disable(transport: &SshTransport) {
+    set_callback(transport, std::ptr::null_mut());
+}
+
+fn set_callback(transport: &SshTransport, callback: *mut c_void) {
+    let session = transport.raw_session();
+    transport.attempt(|| unsafe {
+        libssh2_session_callback_set(session, LIBSSH2_CALLBACK_AUTHAGENT, callback);
+    });
+}
+
+/// Serve the agent channels the server opens until the connection closes.
+/// `agent_forwarding_{id}` tells the UI while the server can use our keys.
+pub async fn serve(window: Window, id: String, transport: Arc<SshTransport>) {
+    let app = window.app_handle().clone();
+    let session = transport.raw_session() as usize;
+    let event = format!("agent_forwarding_{}", id);
+    let _ = window.emit(&event, true);
+    println!("Agent forwarding active for session: {}", id);
+
+    let mut progress = transport.subscribe();
+    while !transport.is_closed() {
+        for channel in take_opened(session) {
+            println!("Forwarded agent connection from {}:{}", transport.host, transport.port);
+            let channel = AgentChannel(channel as *mut LIBSSH2_CHANNEL);
+            tauri::async_runtime::spawn(serve_channel(app.clone(), transport.clone(), channel));
+        }
+        tokio::select! {
+            _ = progress.changed() => {}
+            _ = tokio::time::sleep(DISPATCH_FALLBACK) => {}
+        }
+    }
+
+    // Channels still queued are freed along with the session
+    take_opened(session);
+    let _ = window.emit(&event, false);
+}
+
+async fn read_exact(transport: &SshTransport, channel: &mut AgentChannel, buf: &mut [u8]) -> io::Result<()> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match transport.read(channel, &mut buf[filled..]).await? {
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            n => filled += n,
+        }
+    }
+    Ok(())
+}
+
+async fn serve_channel(app: AppHandle, transport: Arc<SshTransport>, mut channel: AgentChannel) {
+    let client = format!("{} (forwarded agent)", transport.host);
+
+    loop {
+        let mut len = [0u8; 4];
+        if read_exact(&transport, &mut channel, &mut len).await.is_err() {
+            break;
+        }
+        let len = u32::from_be_bytes(len) as usize;
+        if len == 0 || len > MAX_MESSAGE_LEN {
+            break;
+        }
+        let mut request = vec![0u8; len];
+        if read_exact(&transport, &mut channel, &mut request).await.is_err() {
+            break;
+        }
+
+        // Confirmation prompts and the system agent may block
+        let handle = app.clone();
+        let from = client.clone();
+        let response = match tauri::async_runtime::spawn_blocking(move || answer(&handle, &request, &from)).await {
+            Ok(response) => response,
+            Err(_) => vec![SSH_AGENT_FAILURE],
+        };
+
+        let mut frame = (response.len() as u32).to_be_bytes().to_vec();
+        frame.extend_from_slice(&response);
+        if transport.write_all(&mut channel, &frame).await.is_err() {
+            break;
+        }
+    }
+
+    // Once the transport is closed the session frees its channels itself
+    let _ = transport.call(|| channel.close()).await;
+    let _ = transport.call(|| channel.free()).await;
+}
+
+/// Answer a request from the server. Keys loaded into the built-in agent
+/// take precedence; without any, the request goes to the system agent.
+/// Remote hosts may list keys and sign, but never add, remove or lock.
+fn answer(app: &AppHandle, request: &[u8], client: &str) -> Vec<u8> {
+    if !matches!(request.first(), Some(&SSH_AGENTC_REQUEST_IDENTITIES) | Some(&SSH_AGENTC_SIGN_REQUEST)) {
+        return vec![SSH_AGENT_FAILURE];
+    }
+    if !app.state::<AgentState>().loaded_keys().is_empty() {
+        return handle_request(app, request, client);
+    }
+    match relay_to_system_agent(request) {
+        Ok(response) => response,
+        Err(e) => {
+            eprintln!("Forwarded agent request failed: {}", e);
+            vec![SSH_AGENT_FAILURE]
+        }
+    }
+}
+
+#[cfg(unix)]
+fn relay_to_system_agent(request: &[u8]) -> Result<Vec<u8>, String> {
+    use std::os::unix::net::UnixStream;
+
+    let path = std::env::var_os("SSH_AUTH_SOCK").ok_or("No SSH agent is running")?;
+    let mut stream = UnixStream::connect(path).map_err(|e| e.to_string())?;
+    let mut frame = (request.len() as u32).to_be_bytes().to_vec();
+    frame.extend_from_slice(request);
+    stream.write_all(&frame).map_err(|e| e.to_string())?;
+
+    let mut len = [0u8; 4];
+    stream.read_exact(&mut len).map_err(|e| e.to_string())?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_MESSAGE_LEN {
+        return Err("Agent response too large".to_string());
+    }
+    let mut response = vec![0u8; len];
+    stream.read_exact(&mut response).map_err(|e| e.to_string())?;
+    Ok(response)
+}
+
+#[cfg(not(unix))]
+fn relay_to_system_agent(_request: &[u8]) -> Result<Vec<u8>, String> {
+    Err("Forwarding the system agent is only supported on Unix".to_string())
+}
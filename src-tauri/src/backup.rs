@@ -0,0 +1,345 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use argon2::Argon2;
+use rand::RngCore;
+use tauri::{AppHandle, Manager, State};
+use crate::db::Database;
+use crate::migrations::SCHEMA_VERSION;
+use crate::repositories::settings;
+
+const MAGIC: &[u8] = b"NEBULASSH-BACKUP\n";
+// Layout version of the archive itself, independent of the schema version
+const ARCHIVE_FORMAT: u32 = 1;
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+const SCHEDULED_PREFIX: &str = "nebula-backup-";
+const BACKUP_EXTENSION: &str = "nbk";
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Plain-text header stored after the magic line. The payload that follows
+/// is a SQLCipher database keyed from the backup passphrase.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub format: u32,
+    pub schema_version: i32,
+    pub created_at: i64,
+    pub kdf: String,
+    pub salt: String,   // Hex
+    pub sha256: String, // Hex digest of the payload
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupSchedule {
+    pub enabled: bool,
+    pub interval_hours: u32,
+    pub keep: u32, // Number of rolling backups to retain
+    pub directory: Option<String>, // Defaults to <app data>/backups
+    pub last_backup_at: Option<i64>,
+    pub has_passphrase: bool,
+}
+
+/// Argon2id over the backup passphrase; independent of the master password
+fn derive_backup_key(passphrase: &str, salt: &[u8]) -> Result<String, String> {
+    let mut key = [0u8; KEY_LEN];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| e.to_string())?;
+    Ok(hex::encode(key))
+}
+
+fn encode_archive(info: &BackupInfo, payload: &[u8]) -> Result<Vec<u8>, String> {
+    let header = serde_json::to_string(info).map_err(|e| e.to_string())?;
+    let mut archive = Vec::with_capacity(MAGIC.len() + header.len() + 1 + payload.len());
+    archive.extend_from_slice(MAGIC);
+    archive.extend_from_slice(header.as_bytes());
+    archive.push(b'\n');
+    archive.extend_from_slice(payload);
+    Ok(archive)
+}
+
+/// Split an archive into its header and payload, checking the format and
+/// the payload checksum
+fn decode_archive(archive: &[u8]) -> Result<(BackupInfo, &[u8]), String> {
+    let rest = archive
+        .strip_prefix(MAGIC)
+        .ok_or("Not a NebulaSSH backup file")?;
+    let header_end = rest
+        .iter()
+        .position(|&b| b == b'\n')
+        .ok_or("Backup header is truncated")?;
+    let info: BackupInfo = serde_json::from_slice(&rest[..header_end])
+        .map_err(|e| format!("Invalid backup header: {}", e))?;
+    let payload = &rest[header_end + 1..];
+
+    if info.format != ARCHIVE_FORMAT {
+        return Err(format!("Unsupported backup format {}", info.format));
+    }
+    if payload.len() as u64 != info.size || hex::encode(Sha256::digest(payload)) != info.sha256 {
+        return Err("Backup checksum mismatch, the file is damaged".to_string());
+    }
+    Ok((info, payload))
+}
+
+/// Scratch file next to nebula.db, so the payload never leaves the app dir
+fn scratch_path(db: &Database, name: &str) -> PathBuf {
+    db.get_path().with_extension(name)
+}
+
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let tmp = path.with_extension("partial");
+    std::fs::write(&tmp, contents).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    std::fs::rename(&tmp, path).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp);
+        e.to_string()
+    })
+}
+
+fn create_archive(db: &Database, path: &Path, passphrase: &str) -> Result<BackupInfo, String> {
+    if passphrase.is_empty() {
+        return Err("Backup passphrase must not be empty".to_string());
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let key = derive_backup_key(passphrase, &salt)?;
+
+    let scratch = scratch_path(db, "db.export");
+    let _ = std::fs::remove_file(&scratch);
+    let exported = db.export_encrypted(&scratch, &key).map_err(|e| e.to_string());
+    let payload = exported.and_then(|version| {
+        std::fs::read(&scratch).map(|data| (version, data)).map_err(|e| e.to_string())
+    });
+    let _ = std::fs::remove_file(&scratch);
+    // Stamp the schema the copy actually has, which lags SCHEMA_VERSION for
+    // a database that isn't fully migrated
+    let (schema_version, payload) = payload?;
+
+    let info = BackupInfo {
+        format: ARCHIVE_FORMAT,
+        schema_version,
+        created_at: chrono::Utc::now().timestamp(),
+        kdf: "argon2id".to_string(),
+        salt: hex::encode(salt),
+        sha256: hex::encode(Sha256::digest(&payload)),
+        size: payload.len() as u64,
+    };
+    write_atomically(path, &encode_archive(&info, &payload)?)?;
+    println!("Backup written to {} ({} bytes)", path.display(), info.size);
+    Ok(info)
+}
+
+fn read_archive(path: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))
+}
+
+/// Write an encrypted backup of the whole database to `path`
+#[tauri::command]
+pub fn create_backup(db: State<Database>, path: String, passphrase: String) -> Result<BackupInfo, String> {
+    create_archive(&db, Path::new(&path), &passphrase)
+}
+
+/// Read a backup's header and verify its checksum without restoring it
+#[tauri::command]
+pub fn inspect_backup(path: String) -> Result<BackupInfo, String> {
+    let archive = read_archive(&path)?;
+    decode_archive(&archive).map(|(info, _)| info)
+}
+
+/// Replace the current database with the contents of a backup
+#[tauri::command]
+pub fn restore_backup(db: State<Database>, path: String, passphrase: String) -> Result<BackupInfo, String> {
+    let archive = read_archive(&path)?;
+    let (info, payload) = decode_archive(&archive)?;
+    if info.schema_version > SCHEMA_VERSION {
+        return Err(format!(
+            "Backup uses schema version {}, newer than this version of the app supports ({})",
+            info.schema_version, SCHEMA_VERSION
+        ));
+    }
+
+    let salt = hex::decode(&info.salt).map_err(|e| e.to_string())?;
+    let key = derive_backup_key(&passphrase, &salt)?;
+
+    let scratch = scratch_path(&db, "db.import");
+    std::fs::write(&scratch, payload).map_err(|e| e.to_string())?;
+    let restored = db.restore_encrypted(&scratch, &key);
+    let _ = std::fs::remove_file(&scratch);
+    restored.map_err(|e| match e.sqlite_error_code() {
+        // SQLCipher reports a wrong key as a non-database file
+        Some(rusqlite::ErrorCode::NotADatabase) => "Incorrect backup passphrase".to_string(),
+        _ => e.to_string(),
+    })?;
+
+    println!("Restored backup from {} (schema version {})", path, info.schema_version);
+    Ok(info)
+}
+
+fn default_backup_dir(db: &Database) -> PathBuf {
+    db.get_path()
+        .parent()
+        .map(|dir| dir.join("backups"))
+        .unwrap_or_else(|| PathBuf::from("backups"))
+}
+
+fn load_schedule(db: &Database) -> Result<BackupSchedule, String> {
+    let get = |key: &str| settings::get_setting(db, key).map_err(|e| e.to_string());
+    Ok(BackupSchedule {
+        enabled: get("backup_enabled")?.is_some_and(|v| v == "true"),
+        interval_hours: get("backup_interval_hours")?.and_then(|v| v.parse().ok()).unwrap_or(24),
+        keep: get("backup_keep")?.and_then(|v| v.parse().ok()).unwrap_or(7),
+        directory: get("backup_directory")?.filter(|v| !v.is_empty()),
+        last_backup_at: get("backup_last_at")?.and_then(|v| v.parse().ok()),
+        has_passphrase: get("backup_passphrase")?.is_some_and(|v| !v.is_empty()),
+    })
+}
+
+#[tauri::command]
+pub fn get_backup_schedule(db: State<Database>) -> Result<BackupSchedule, String> {
+    load_schedule(&db)
+}
+
+/// Save the rolling backup schedule. The passphrase is kept in the encrypted
+/// settings table; pass `None` to keep the current one.
+#[tauri::command]
+pub fn set_backup_schedule(
+    db: State<Database>,
+    schedule: BackupSchedule,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    if let Some(passphrase) = passphrase {
+        settings::set_setting(&db, "backup_passphrase", &passphrase).map_err(|e| e.to_string())?;
+    }
+    if schedule.enabled && !load_schedule(&db)?.has_passphrase {
+        return Err("Scheduled backups need a backup passphrase".to_string());
+    }
+
+    let values = [
+        ("backup_enabled", schedule.enabled.to_string()),
+        ("backup_interval_hours", schedule.interval_hours.max(1).to_string()),
+        ("backup_keep", schedule.keep.max(1).to_string()),
+        ("backup_directory", schedule.directory.unwrap_or_default()),
+    ];
+    for (key, value) in values {
+        settings::set_setting(&db, key, &value).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Delete the oldest scheduled backups beyond `keep`
+fn prune_backups(dir: &Path, keep: usize) -> Result<(), String> {
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(|e| e.to_string())?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|p| {
+            p.extension().is_some_and(|ext| ext == BACKUP_EXTENSION)
+                && p.file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with(SCHEDULED_PREFIX))
+        })
+        .collect();
+    // Timestamped names sort chronologically
+    backups.sort();
+    let excess = backups.len().saturating_sub(keep);
+    for old in &backups[..excess] {
+        println!("Removing old backup {}", old.display());
+        std::fs::remove_file(old).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn run_scheduled_backup(db: &Database) -> Result<(), String> {
+    // Locked database: nothing to back up until the user unlocks
+    let Ok(schedule) = load_schedule(db) else {
+        return Ok(());
+    };
+    if !schedule.enabled {
+        return Ok(());
+    }
+    let now = chrono::Utc::now();
+    let due = schedule
+        .last_backup_at
+        .map_or(true, |last| now.timestamp() - last >= schedule.interval_hours as i64 * 3600);
+    if !due {
+        return Ok(());
+    }
+
+    let passphrase = settings::get_setting(db, "backup_passphrase")
+        .map_err(|e| e.to_string())?
+        .ok_or("No backup passphrase configured")?;
+    let dir = schedule
+        .directory
+        .map(PathBuf::from)
+        .unwrap_or_else(|| default_backup_dir(db));
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let name = format!("{}{}.{}", SCHEDULED_PREFIX, now.format("%Y%m%d-%H%M%S"), BACKUP_EXTENSION);
+    create_archive(db, &dir.join(name), &passphrase)?;
+    settings::set_setting(db, "backup_last_at", &now.timestamp().to_string()).map_err(|e| e.to_string())?;
+    prune_backups(&dir, schedule.keep as usize)
+}
+
+/// Background task that takes rolling backups while the database is unlocked
+pub fn start_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(SCHEDULE_CHECK_INTERVAL).await;
+            let app = app.clone();
+            let result = tauri::async_runtime::spawn_blocking(move || {
+                run_scheduled_backup(&app.state::<Database>())
+            })
+            .await;
+            if let Ok(Err(e)) = result {
+                eprintln!("Scheduled backup failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info_for(payload: &[u8]) -> BackupInfo {
+        BackupInfo {
+            format: ARCHIVE_FORMAT,
+            schema_version: SCHEMA_VERSION,
+            created_at: 1_700_000_000,
+            kdf: "argon2id".to_string(),
+            salt: hex::encode([7u8; SALT_LEN]),
+            sha256: hex::encode(Sha256::digest(payload)),
+            size: payload.len() as u64,
+        }
+    }
+
+    #[test]
+    fn test_archive_round_trip() {
+        // Payload may contain newlines; only the first one ends the header
+        let payload = b"SQLite\nformat\n\x00\x01";
+        let archive = encode_archive(&info_for(payload), payload).unwrap();
+
+        let (info, decoded) = decode_archive(&archive).unwrap();
+        assert_eq!(decoded, payload);
+        assert_eq!(info.schema_version, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_archive_rejects_damage() {
+        let payload = b"payload";
+        let mut archive = encode_archive(&info_for(payload), payload).unwrap();
+        *archive.last_mut().unwrap() ^= 0xFF;
+        assert!(decode_archive(&archive).unwrap_err().contains("checksum"));
+
+        assert!(decode_archive(b"not a backup").is_err());
+    }
+
+    #[test]
+    fn test_backup_key_depends_on_salt() {
+        let a = derive_backup_key("correct horse", &[1u8; SALT_LEN]).unwrap();
+        let b = derive_backup_key("correct horse", &[2u8; SALT_LEN]).unwrap();
+        assert_eq!(a.len(), KEY_LEN * 2);
+        assert_ne!(a, b);
+    }
+}
@@ -0,0 +1,284 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::Utc;
+use serde::Serialize;
+use ssh2::{MethodType, Session};
+use ssh_key::certificate::CertType;
+use ssh_key::{Algorithm, Certificate, HashAlg, PublicKey};
+use std::collections::HashMap;
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::State;
+use crate::db::Database;
+use crate::known_hosts::{fingerprint, wildcard_match};
+use crate::models::KnownHost;
+use crate::repositories::{keys, known_hosts, servers};
+use crate::ssh_utils::expand_home;
+
+/// Certificates closer than this to expiry are flagged
+pub const EXPIRY_WARNING_SECS: i64 = 60 * 60;
+pub const CERT_AUTHORITY: &str = "cert-authority";
+
+// Offered when fetching a host certificate; libssh2 lists these but can only
+// use them for user authentication
+const HOST_CERT_ALGORITHMS: &str = "ssh-ed25519-cert-v01@openssh.com,\
+    ecdsa-sha2-nistp256-cert-v01@openssh.com,ecdsa-sha2-nistp384-cert-v01@openssh.com,\
+    ecdsa-sha2-nistp521-cert-v01@openssh.com,rsa-sha2-512-cert-v01@openssh.com,\
+    rsa-sha2-256-cert-v01@openssh.com,ssh-rsa-cert-v01@openssh.com";
+const PROBE_TIMEOUT_MS: u32 = 10_000;
+
+/// What the UI shows about a certificate
+#[derive(Debug, Clone, Serialize)]
+pub struct CertificateInfo {
+    pub key_id: String,
+    pub serial: u64,
+    pub cert_type: String,
+    pub principals: Vec<String>,
+    pub valid_after: u64,
+    pub valid_before: u64,
+    pub ca_fingerprint: String,
+    pub expired: bool,
+    pub expires_soon: bool,
+}
+
+pub fn certificate_info(cert: &Certificate) -> CertificateInfo {
+    let now = Utc::now().timestamp();
+    let valid_before = cert.valid_before().min(i64::MAX as u64) as i64;
+    CertificateInfo {
+        key_id: cert.key_id().to_string(),
+        serial: cert.serial(),
+        cert_type: if cert.cert_type().is_host() { "host" } else { "user" }.to_string(),
+        principals: cert.valid_principals().to_vec(),
+        valid_after: cert.valid_after(),
+        valid_before: cert.valid_before(),
+        ca_fingerprint: cert.signature_key().fingerprint(HashAlg::Sha256).to_string(),
+        expired: valid_before <= now,
+        expires_soon: valid_before > now && valid_before - now < EXPIRY_WARNING_SECS,
+    }
+}
+
+pub fn parse_certificate(content: &str) -> Result<Certificate, String> {
+    Certificate::from_openssh(content.trim()).map_err(|e| format!("Invalid OpenSSH certificate: {}", e))
+}
+
+/// Where OpenSSH looks for a key file's certificate: `id_ed25519-cert.pub`
+pub fn certificate_path(key_path: &Path) -> PathBuf {
+    let mut path = key_path.as_os_str().to_owned();
+    path.push("-cert.pub");
+    PathBuf::from(path)
+}
+
+/// Certificate to present with a key file, if one sits next to it
+pub fn certificate_for_key_file(key_path: &str) -> Option<String> {
+    std::fs::read_to_string(certificate_path(&expand_home(key_path))).ok()
+}
+
+/// Check a host certificate the way OpenSSH does for `@cert-authority`
+/// entries: signed by one of `authorities`, currently valid, issued for
+/// `hostname` (no principals means any host) and without critical options.
+pub fn validate_host_certificate(
+    cert: &Certificate,
+    authorities: &[KnownHost],
+    hostname: &str,
+    now: u64,
+) -> Result<(), String> {
+    if cert.cert_type() != CertType::Host {
+        return Err("not a host certificate".to_string());
+    }
+    let trusted: Vec<_> = authorities
+        .iter()
+        .filter_map(|ca| STANDARD.decode(&ca.public_key).ok())
+        .filter_map(|blob| PublicKey::from_bytes(&blob).ok())
+        .map(|key| key.fingerprint(HashAlg::Sha256))
+        .collect();
+    cert.validate_at(now, &trusted)
+        .map_err(|_| "not signed by a trusted authority, or outside its validity period".to_string())?;
+
+    let principals = cert.valid_principals();
+    if !principals.is_empty() && !principals.iter().any(|p| wildcard_match(p, hostname)) {
+        return Err(format!("not issued for {} (principals: {})", hostname, principals.join(", ")));
+    }
+    if !cert.critical_options().is_empty() {
+        return Err("has unsupported critical options".to_string());
+    }
+    Ok(())
+}
+
+/// The host certificate the server behind `tcp` presents, if any.
+///
+/// libssh2 cannot finish a key exchange with a certificate host key, but it
+/// keeps the key blob the server sent, so a handshake offering nothing but
+/// certificate types reveals it. The certificate proves nothing on its own:
+/// it is only trusted once the real session shows the server holds the
+/// certified key.
+pub fn fetch_host_certificate(tcp: TcpStream) -> Option<Certificate> {
+    let mut session = Session::new().ok()?;
+    session.set_tcp_stream(tcp);
+    session.set_timeout(PROBE_TIMEOUT_MS);
+    session.method_pref(MethodType::HostKey, HOST_CERT_ALGORITHMS).ok()?;
+    let _ = session.handshake();
+    let (blob, _) = session.host_key()?;
+    Certificate::from_bytes(blob).ok()
+}
+
+/// Host certificates probed so far by host and port, so connecting again does
+/// not cost another connection and key exchange. `None` records a host that
+/// presented no certificate. Kept for the life of the process.
+pub struct HostCertificateCache {
+    probed: Mutex<HashMap<(String, u16), Option<Certificate>>>,
+}
+
+impl HostCertificateCache {
+    pub fn new() -> Self {
+        Self {
+            probed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, host: &str, port: u16) -> Option<Option<Certificate>> {
+        self.probed.lock().unwrap().get(&(host.to_string(), port)).cloned()
+    }
+
+    pub fn insert(&self, host: &str, port: u16, certificate: Option<Certificate>) {
+        self.probed.lock().unwrap().insert((host.to_string(), port), certificate);
+    }
+
+    /// Probe again next time, e.g. after the host failed verification
+    pub fn forget(&self, host: &str, port: u16) {
+        self.probed.lock().unwrap().remove(&(host.to_string(), port));
+    }
+}
+
+/// Host key algorithms that make the server present the key `cert` certifies
+pub fn certified_key_algorithms(cert: &Certificate) -> String {
+    match cert.public_key().algorithm() {
+        Algorithm::Rsa { .. } => "rsa-sha2-512,rsa-sha2-256,ssh-rsa".to_string(),
+        algorithm => algorithm.as_str().to_string(),
+    }
+}
+
+/// Whether `key_blob`, as presented by the server, is the key `cert` certifies
+pub fn certifies(cert: &Certificate, key_blob: &[u8]) -> bool {
+    PublicKey::from(cert.public_key().clone())
+        .to_bytes()
+        .is_ok_and(|blob| blob == key_blob)
+}
+
+/// Certificate of the key a saved server authenticates with: the one next to
+/// its key file, or the one attached to its key in the key store
+#[tauri::command]
+pub fn get_server_certificate(db: State<Database>, server_id: i64) -> Result<Option<CertificateInfo>, String> {
+    let server = servers::get_server(&db, server_id).map_err(|e| e.to_string())?.ok_or("Server not found")?;
+    let content = match server.private_key_path.as_deref().filter(|p| !p.trim().is_empty()) {
+        Some(path) => certificate_for_key_file(path),
+        None => match server.key_id {
+            Some(key_id) => keys::get_certificate(&db, key_id).map_err(|e| e.to_string())?,
+            None => None,
+        },
+    };
+    content.map(|c| parse_certificate(&c).map(|cert| certificate_info(&cert))).transpose()
+}
+
+#[tauri::command]
+pub fn get_key_certificate(db: State<Database>, key_id: i64) -> Result<Option<CertificateInfo>, String> {
+    let content = keys::get_certificate(&db, key_id).map_err(|e| e.to_string())?;
+    content.map(|c| parse_certificate(&c).map(|cert| certificate_info(&cert))).transpose()
+}
+
+/// Attach the certificate the CA issued for a stored key, given as the
+/// `-cert.pub` text or a path to it. `None` removes the certificate.
+#[tauri::command]
+pub fn set_key_certificate(
+    db: State<Database>,
+    key_id: i64,
+    certificate: Option<String>,
+) -> Result<Option<CertificateInfo>, String> {
+    let Some(certificate) = certificate.filter(|c| !c.trim().is_empty()) else {
+        keys::set_certificate(&db, key_id, None).map_err(|e| e.to_string())?;
+        db.flush().map_err(|e| e.to_string())?;
+        return Ok(None);
+    };
+    let content = if certificate.contains("-cert-v01@openssh.com ") {
+        certificate
+    } else {
+        std::fs::read_to_string(expand_home(certificate.trim()))
+            .map_err(|e| format!("Failed to read certificate {}: {}", certificate.trim(), e))?
+    };
+
+    let cert = parse_certificate(&content)?;
+    let record = keys::get_key(&db, key_id).map_err(|e| e.to_string())?.ok_or("Key not found")?;
+    let key = PublicKey::from_openssh(&record.public_key).map_err(|e| e.to_string())?;
+    if cert.public_key() != key.key_data() {
+        return Err("The certificate was not issued for this key".to_string());
+    }
+    if cert.cert_type().is_host() {
+        return Err("This is a host certificate, not a user certificate".to_string());
+    }
+
+    keys::set_certificate(&db, key_id, Some(content.trim())).map_err(|e| e.to_string())?;
+    db.flush().map_err(|e| e.to_string())?;
+    Ok(Some(certificate_info(&cert)))
+}
+
+/// Trust host certificates signed by `public_key` (an OpenSSH public key
+/// line) for hosts matching `hosts`, like an `@cert-authority` line
+#[tauri::command]
+pub fn add_cert_authority(db: State<Database>, hosts: String, public_key: String) -> Result<KnownHost, String> {
+    let key = PublicKey::from_openssh(public_key.trim()).map_err(|e| format!("Invalid public key: {}", e))?;
+    let blob = key.to_bytes().map_err(|e| e.to_string())?;
+    let mut entry = KnownHost {
+        id: None,
+        host: hosts.trim().to_string(),
+        key_type: key.algorithm().as_str().to_string(),
+        public_key: STANDARD.encode(&blob),
+        fingerprint: fingerprint(&blob),
+        comment: Some(key.comment().to_string()).filter(|c| !c.is_empty()),
+        marker: Some(CERT_AUTHORITY.to_string()),
+        created_at: 0,
+    };
+    entry.id = Some(known_hosts::add_known_host(&db, &entry).map_err(|e| e.to_string())?);
+    db.flush().map_err(|e| e.to_string())?;
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ssh_key::certificate::Builder;
+    use ssh_key::rand_core::OsRng;
+    use ssh_key::PrivateKey;
+
+    fn authority(key: &PrivateKey) -> KnownHost {
+        KnownHost {
+            id: None,
+            host: "*.example.com".to_string(),
+            key_type: "ssh-ed25519".to_string(),
+            public_key: STANDARD.encode(key.public_key().to_bytes().unwrap()),
+            fingerprint: String::new(),
+            comment: None,
+            marker: Some(CERT_AUTHORITY.to_string()),
+            created_at: 0,
+        }
+    }
+
+    fn host_certificate(ca: &PrivateKey, principal: &str) -> Certificate {
+        let host = PrivateKey::random(&mut OsRng, Algorithm::Ed25519).unwrap();
+        let mut builder = Builder::new_with_random_nonce(&mut OsRng, host.public_key(), 1_000, 2_000).unwrap();
+        builder.cert_type(CertType::Host).unwrap();
+        builder.valid_principal(principal).unwrap();
+        builder.sign(ca).unwrap()
+    }
+
+    #[test]
+    fn test_validate_host_certificate() {
+        let ca = PrivateKey::random(&mut OsRng, Algorithm::Ed25519).unwrap();
+        let other = PrivateKey::random(&mut OsRng, Algorithm::Ed25519).unwrap();
+        let cert = host_certificate(&ca, "db.example.com");
+
+        assert!(validate_host_certificate(&cert, &[authority(&ca)], "db.example.com", 1_500).is_ok());
+        assert!(validate_host_certificate(&cert, &[authority(&ca)], "web.example.com", 1_500).is_err());
+        assert!(validate_host_certificate(&cert, &[authority(&ca)], "db.example.com", 2_500).is_err());
+        assert!(validate_host_certificate(&cert, &[authority(&other)], "db.example.com", 1_500).is_err());
+    }
+}
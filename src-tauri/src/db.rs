@@ -1,10 +1,12 @@
-use rusqlite::{Connection, Result};
+use rusqlite::{Connection, DatabaseName, Result};
 use std::sync::Mutex;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Manager};
+use crate::migrations::{self, MigrationReport};
 
 pub struct Database {
     conn: Mutex<Option<Connection>>,
+    key: Mutex<Option<String>>, // Raw SQLCipher key, kept to reopen after a restore
     path: PathBuf,
 }
 
@@ -24,6 +26,7 @@ impl Database {
         
         Ok(Database {
             conn: Mutex::new(None),
+            key: Mutex::new(None),
             path: db_path,
         })
     }
@@ -43,7 +46,6 @@ impl Database {
         
         // Set the key - SQLCipher expects x'...' format
         let formatted_key = format!("x'{}'", key);
-        eprintln!("Setting PRAGMA key = {}", formatted_key);
         conn.pragma_update(None, "key", &formatted_key)?;
         
         // Set synchronous mode to FULL to ensure data is written to disk
@@ -55,20 +57,19 @@ impl Database {
             Ok(_) => {
                 eprintln!("Database unlocked successfully");
                 *conn_guard = Some(conn);
+                *self.key.lock().unwrap() = Some(key);
                 // Release the lock before calling init methods
                 drop(conn_guard);
                 
-                // Initialize schema now that we have access
-                self.init_schema()?;
+                // Initialize schema now that we have access. A database
+                // that failed to migrate must not stay open half upgraded.
+                if let Err(e) = self.init_schema() {
+                    *self.conn.lock().unwrap() = None;
+                    *self.key.lock().unwrap() = None;
+                    return Err(e);
+                }
                 self.init_default_settings()?;
                 
-                // Force write to disk by setting a pragma
-                self.query(|conn| {
-                    eprintln!("Forcing database file creation");
-                    conn.pragma_update(None, "user_version", &1)?;
-                    Ok(())
-                })?;
-                
                 eprintln!("Database file created successfully");
                 eprintln!("Database path: {:?}", self.path);
                 Ok(())
@@ -91,7 +92,6 @@ impl Database {
         
         // Rekey (encrypt)
         let formatted_key = format!("x'{}'", key);
-        eprintln!("Setting PRAGMA rekey = {}", formatted_key);
         conn.pragma_update(None, "rekey", &formatted_key)?;
         
         // Set synchronous mode to FULL
@@ -99,6 +99,7 @@ impl Database {
         conn.pragma_update(None, "synchronous", "FULL")?;
         
         *conn_guard = Some(conn);
+        *self.key.lock().unwrap() = Some(key);
         // Release the lock before calling init methods
         drop(conn_guard);
         
@@ -108,6 +109,131 @@ impl Database {
         Ok(())
     }
     
+    /// Close the connection and forget the key; every query fails until the
+    /// next unlock
+    pub fn lock(&self) {
+        let mut conn_guard = self.conn.lock().unwrap();
+        if conn_guard.take().is_some() {
+            eprintln!("Database locked");
+        }
+        *self.key.lock().unwrap() = None;
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.conn.lock().unwrap().is_some()
+    }
+
+    /// Re-encrypt the open database with a new key
+    pub fn rekey(&self, key: String) -> Result<()> {
+        self.query(|conn| {
+            eprintln!("Re-keying database");
+            conn.pragma_update(None, "rekey", format!("x'{}'", key))
+        })?;
+        *self.key.lock().unwrap() = Some(key);
+        Ok(())
+    }
+
+    /// Copy the whole database into a new SQLCipher file at `target`,
+    /// encrypted with `key` instead of the master key. Returns the schema
+    /// version of the copy.
+    pub fn export_encrypted(&self, target: &Path, key: &str) -> Result<i32> {
+        self.query(|conn| {
+            conn.query_row("PRAGMA wal_checkpoint(FULL);", [], |_| Ok(()))?;
+            let version: i32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+            conn.execute(
+                "ATTACH DATABASE ?1 AS backup KEY ?2",
+                [target.to_string_lossy().as_ref(), &format!("x'{}'", key)],
+            )?;
+            // sqlcipher_export leaves user_version alone, so carry it over
+            let result = conn
+                .query_row("SELECT sqlcipher_export('backup')", [], |_| Ok(()))
+                .and_then(|_| conn.pragma_update(Some(DatabaseName::Attached("backup")), "user_version", version));
+            conn.execute("DETACH DATABASE backup", [])?;
+            result.map(|_| version)
+        })
+    }
+
+    /// Replace the database with the SQLCipher file at `source` (encrypted
+    /// with `key`). The copy is re-encrypted with the master key, checked,
+    /// migrated and then renamed over nebula.db, so a failure at any step
+    /// leaves the current database in place.
+    pub fn restore_encrypted(&self, source: &Path, key: &str) -> Result<()> {
+        let staged = self.path.with_extension("db.restore");
+        let _ = std::fs::remove_file(&staged);
+
+        let staged_result = self.query(|conn| {
+            conn.execute(
+                "ATTACH DATABASE ?1 AS restored KEY ?2",
+                [source.to_string_lossy().as_ref(), &format!("x'{}'", key)],
+            )?;
+            // No KEY: the staged copy inherits the master key
+            conn.execute(
+                "ATTACH DATABASE ?1 AS staged",
+                [staged.to_string_lossy().as_ref()],
+            )?;
+            let result = (|| {
+                let check: String = conn.query_row("PRAGMA restored.integrity_check", [], |row| row.get(0))?;
+                if check != "ok" {
+                    return Err(rusqlite::Error::SqliteFailure(
+                        rusqlite::ffi::Error::new(11), // SQLITE_CORRUPT
+                        Some(format!("Backup failed integrity check: {}", check)),
+                    ));
+                }
+                let version: i32 = conn.pragma_query_value(Some(DatabaseName::Attached("restored")), "user_version", |row| row.get(0))?;
+                conn.query_row("SELECT sqlcipher_export('staged', 'restored')", [], |_| Ok(()))?;
+                conn.pragma_update(Some(DatabaseName::Attached("staged")), "user_version", version)
+            })();
+            conn.execute("DETACH DATABASE staged", [])?;
+            conn.execute("DETACH DATABASE restored", [])?;
+            result
+        });
+        if let Err(e) = staged_result {
+            let _ = std::fs::remove_file(&staged);
+            return Err(e);
+        }
+
+        let key = self.key.lock().unwrap().clone().ok_or(rusqlite::Error::InvalidQuery)?;
+        let mut conn_guard = self.conn.lock().unwrap();
+
+        // Bring the staged copy up to the current schema before it goes live
+        let migrated = Connection::open(&staged).and_then(|staged_conn| {
+            staged_conn.pragma_update(None, "key", format!("x'{}'", key))?;
+            Self::run_migrations(&staged_conn)
+        });
+        if let Err(e) = migrated {
+            let _ = std::fs::remove_file(&staged);
+            return Err(e);
+        }
+
+        // Closing checkpoints the WAL; stale -wal/-shm files must not be
+        // applied to the new file
+        drop(conn_guard.take());
+        for suffix in ["db-wal", "db-shm"] {
+            let _ = std::fs::remove_file(self.path.with_extension(suffix));
+        }
+        let swapped = std::fs::rename(&staged, &self.path);
+        if swapped.is_err() {
+            let _ = std::fs::remove_file(&staged);
+        }
+
+        // Reopen whichever file is now in place
+        let conn = Connection::open(&self.path)?;
+        conn.pragma_update(None, "key", format!("x'{}'", key))?;
+        conn.pragma_update(None, "synchronous", "FULL")?;
+        conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))?;
+        *conn_guard = Some(conn);
+        drop(conn_guard);
+
+        if let Err(e) = swapped {
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(1),
+                Some(format!("Failed to swap in restored database: {}", e)),
+            ));
+        }
+        self.init_default_settings()
+    }
+    
     // Helper to check if DB exists (for migration logic)
     #[allow(dead_code)]
     pub fn exists(&self) -> bool {
@@ -186,76 +312,20 @@ impl Database {
     fn init_schema(&self) -> Result<()> {
         let guard = self.conn.lock().unwrap();
         let conn = guard.as_ref().ok_or(rusqlite::Error::InvalidQuery)?; // Should be unlocked
-        
-        // Servers table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS servers (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT NOT NULL,
-                host TEXT NOT NULL,
-                port INTEGER DEFAULT 22,
-                username TEXT NOT NULL,
-                password TEXT,
-                private_key_path TEXT,
-                server_group TEXT,
-                tags TEXT,
-                color TEXT,
-                created_at INTEGER NOT NULL,
-                updated_at INTEGER NOT NULL
-            )",
-            [],
-        )?;
+        Self::run_migrations(conn)
+    }
 
-        // Migrations for existing databases
-        // We ignore errors because the columns might already exist
-        let _ = conn.execute("ALTER TABLE servers ADD COLUMN server_group TEXT", []);
-        let _ = conn.execute("ALTER TABLE servers ADD COLUMN tags TEXT", []);
-        let _ = conn.execute("ALTER TABLE servers ADD COLUMN color TEXT", []);
-        let _ = conn.execute("ALTER TABLE servers ADD COLUMN forwarding_rules TEXT", []);
-        let _ = conn.execute("ALTER TABLE servers ADD COLUMN jump_host_id INTEGER", []);
-        
-        // Command history table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS command_history (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                server_id INTEGER,
-                command TEXT NOT NULL,
-                executed_at INTEGER NOT NULL,
-                FOREIGN KEY (server_id) REFERENCES servers(id) ON DELETE CASCADE
-            )",
-            [],
-        )?;
-        
-        // Create index for faster queries
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_history_server_time 
-             ON command_history(server_id, executed_at DESC)",
-            [],
-        )?;
-        
-        // Snippets table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS snippets (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT NOT NULL,
-                command TEXT NOT NULL,
-                category TEXT NOT NULL,
-                description TEXT,
-                created_at INTEGER NOT NULL
-            )",
-            [],
-        )?;
-        
-        // Settings table
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS settings (
-                key TEXT PRIMARY KEY,
-                value TEXT NOT NULL
-            )",
-            [],
-        )?;
-        
-        Ok(())
+    fn run_migrations(conn: &Connection) -> Result<()> {
+        let report = migrations::migrate(conn, false)?;
+        report.into_result().map(|_| ()).map_err(|message| {
+            rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(1), Some(message))
+        })
+    }
+
+    /// Pending migrations for the open database; with `dry_run` they are
+    /// executed and rolled back to check they would succeed
+    pub fn migration_report(&self, dry_run: bool) -> Result<MigrationReport> {
+        self.query(|conn| migrations::migrate(conn, dry_run))
     }
     
     fn init_default_settings(&self) -> Result<()> {
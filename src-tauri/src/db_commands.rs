@@ -1,6 +1,7 @@
 use crate::db::Database;
+use crate::migrations::MigrationReport;
 use crate::models::{AppSettings, CommandHistory, Server};
-use crate::repositories::{history, servers, settings};
+use crate::repositories::{credentials, history, servers, settings};
 use tauri::State;
 
 #[tauri::command]
@@ -10,12 +11,23 @@ pub fn get_servers(db: State<Database>) -> Result<Vec<Server>, String> {
 
 #[tauri::command]
 pub fn save_server(db: State<Database>, server: Server) -> Result<i64, String> {
+    // Refuse jump host chains that loop back or point at missing servers
+    crate::routing::resolve_route(&db, server.id, server.jump_host_id)?;
+
     let result = if let Some(_) = server.id {
         servers::update_server(&db, &server).map_err(|e| e.to_string())?;
         Ok(server.id.unwrap())
     } else {
         servers::create_server(&db, &server).map_err(|e| e.to_string())
     };
+
+    // Secrets go to the vault; leaving them out keeps what is stored
+    if let Ok(id) = result {
+        if server.password.is_some() || server.key_passphrase.is_some() {
+            credentials::set_server_secrets(&db, id, server.password.as_deref(), server.key_passphrase.as_deref())
+                .map_err(|e| e.to_string())?;
+        }
+    }
     
     // Force flush to disk
     db.flush().map_err(|e| e.to_string())?;
@@ -42,6 +54,12 @@ pub fn get_command_history(
     history::get_history(&db, server_id, limit.unwrap_or(100)).map_err(|e| e.to_string())
 }
 
+/// Schema version and pending migrations; `dry_run` also checks they apply
+#[tauri::command]
+pub fn get_migration_status(db: State<Database>, dry_run: bool) -> Result<MigrationReport, String> {
+    db.migration_report(dry_run).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn get_app_settings(db: State<Database>) -> Result<AppSettings, String> {
     settings::get_all_settings(&db).map_err(|e| e.to_string())
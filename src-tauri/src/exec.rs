@@ -0,0 +1,293 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use ssh2::Channel;
+use tauri::{Emitter, State, Window};
+use tokio::sync::watch;
+use crate::ssh::SshState;
+use crate::ssh_utils::shell_quote;
+use crate::transport::SshTransport;
+
+const READ_BUFFER: usize = 32 * 1024;
+
+/// A command to run on its own channel
+#[derive(Debug, Clone, Default)]
+pub struct ExecRequest {
+    pub command: String,
+    pub stdin: Option<Vec<u8>>,
+    pub env: HashMap<String, String>,
+    pub timeout: Option<Duration>,
+    // With a PTY, closing the channel on cancel or timeout hangs up the
+    // remote command; stderr is then merged into stdout
+    pub pty: bool,
+}
+
+/// Optional parts of an `exec_command` call
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ExecOptions {
+    pub stdin: Option<String>,
+    pub env: HashMap<String, String>,
+    pub timeout_secs: Option<u64>,
+    pub pty: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// How a command ended. Cancelled and timed out commands have neither an
+/// exit status nor a signal.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecResult {
+    pub exit_status: Option<i32>,
+    pub exit_signal: Option<String>, // Without the SIG prefix, e.g. "KILL"
+    pub error_message: Option<String>,
+    pub timed_out: bool,
+    pub cancelled: bool,
+    pub duration_ms: u64,
+}
+
+fn valid_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Set the request's environment on `channel`. Servers only accept the
+/// variables listed in their AcceptEnv, so the rest are exported by the
+/// returned command instead.
+async fn apply_env(transport: &SshTransport, channel: &mut Channel, request: &ExecRequest) -> Result<String, String> {
+    let mut exports = String::new();
+    let mut names: Vec<_> = request.env.keys().collect();
+    names.sort();
+    for name in names {
+        if !valid_env_name(name) {
+            return Err(format!("Invalid environment variable name: {}", name));
+        }
+        let value = &request.env[name];
+        if transport.call(|| channel.setenv(name, value)).await.is_err() {
+            exports.push_str(&format!("export {}={}; ", name, shell_quote(value)));
+        }
+    }
+    Ok(format!("{}{}", exports, request.command))
+}
+
+/// Run `request` on `transport`, passing output to `on_output` as it
+/// arrives, until it exits, `timeout` passes or `cancel` turns true
+pub async fn run(
+    transport: &SshTransport,
+    request: &ExecRequest,
+    mut cancel: watch::Receiver<bool>,
+    mut on_output: impl FnMut(OutputStream, &[u8]),
+) -> Result<ExecResult, String> {
+    let started = Instant::now();
+    let deadline = request.timeout.map(|timeout| tokio::time::Instant::now() + timeout);
+    let mut channel = transport.channel_session().await?;
+    if request.pty {
+        transport
+            .call(|| channel.request_pty("dumb", None, None))
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let command = apply_env(transport, &mut channel, request).await?;
+    transport.call(|| channel.exec(&command)).await.map_err(|e| e.to_string())?;
+
+    let stdin = request.stdin.as_deref().unwrap_or_default();
+    let mut written = 0;
+    let mut eof_sent = false;
+    let mut stdout_done = false;
+    let mut stderr_done = false;
+    let mut buf = vec![0u8; READ_BUFFER];
+    let mut stopped = None;
+
+    while !(stdout_done && stderr_done) {
+        if transport.is_closed() {
+            return Err("Connection closed".to_string());
+        }
+        let mut progress = transport.subscribe();
+        let mut busy = false;
+
+        for stream in [OutputStream::Stdout, OutputStream::Stderr] {
+            let done = match stream {
+                OutputStream::Stdout => &mut stdout_done,
+                OutputStream::Stderr => &mut stderr_done,
+            };
+            if *done {
+                continue;
+            }
+            let read = transport.attempt(|| match stream {
+                OutputStream::Stdout => channel.read(&mut buf),
+                OutputStream::Stderr => channel.stderr().read(&mut buf),
+            });
+            match read {
+                Ok(0) => *done = channel.eof(),
+                Ok(n) => {
+                    on_output(stream, &buf[..n]);
+                    busy = true;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+
+        if written < stdin.len() {
+            match transport.attempt(|| channel.write(&stdin[written..])) {
+                Ok(n) => {
+                    written += n;
+                    busy = true;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                // The command stopped reading its input; the rest is dropped
+                Err(_) => written = stdin.len(),
+            }
+        } else if !eof_sent {
+            match transport.attempt(|| channel.send_eof()) {
+                Err(e) if crate::transport::would_block(&e) => {}
+                _ => eof_sent = true,
+            }
+        }
+        if busy {
+            continue;
+        }
+
+        tokio::select! {
+            _ = transport.wait(&mut progress) => {}
+            changed = cancel.changed() => {
+                if changed.is_err() || *cancel.borrow() {
+                    stopped = Some(false);
+                    break;
+                }
+            }
+            _ = async { tokio::time::sleep_until(deadline.unwrap()).await }, if deadline.is_some() => {
+                stopped = Some(true);
+                break;
+            }
+        }
+    }
+
+    let mut result = ExecResult {
+        exit_status: None,
+        exit_signal: None,
+        error_message: None,
+        timed_out: stopped == Some(true),
+        cancelled: stopped == Some(false),
+        duration_ms: 0,
+    };
+    if stopped.is_some() {
+        let _ = transport.call(|| channel.close()).await;
+    } else {
+        let _ = transport.call(|| channel.wait_close()).await;
+        let signal = channel.exit_signal().map_err(|e| e.to_string())?;
+        if signal.exit_signal.is_some() {
+            result.exit_signal = signal.exit_signal;
+            result.error_message = signal.error_message.filter(|m| !m.is_empty());
+        } else {
+            result.exit_status = Some(channel.exit_status().map_err(|e| e.to_string())?);
+        }
+    }
+    result.duration_ms = started.elapsed().as_millis() as u64;
+    Ok(result)
+}
+
+pub struct ExecState {
+    running: Mutex<HashMap<String, watch::Sender<bool>>>,
+}
+
+impl ExecState {
+    pub fn new() -> Self {
+        Self {
+            running: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Register a run that `cancel` can stop
+    pub fn register(&self, exec_id: &str) -> Result<watch::Receiver<bool>, String> {
+        let mut running = self.running.lock().unwrap();
+        if running.contains_key(exec_id) {
+            return Err(format!("Exec {} is already running", exec_id));
+        }
+        let (cancel, rx) = watch::channel(false);
+        running.insert(exec_id.to_string(), cancel);
+        Ok(rx)
+    }
+
+    pub fn unregister(&self, exec_id: &str) {
+        self.running.lock().unwrap().remove(exec_id);
+    }
+
+    pub fn cancel(&self, exec_id: &str) -> bool {
+        match self.running.lock().unwrap().get(exec_id) {
+            Some(cancel) => {
+                cancel.send_replace(true);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Run a command on a connected session's transport. Output streams as
+/// `exec_stdout_{exec_id}` and `exec_stderr_{exec_id}` events (bytes);
+/// the result carries the exit status or signal.
+#[tauri::command]
+pub async fn exec_command(
+    window: Window,
+    ssh_state: State<'_, SshState>,
+    exec_state: State<'_, ExecState>,
+    session_id: String,
+    exec_id: String,
+    command: String,
+    options: Option<ExecOptions>,
+) -> Result<ExecResult, String> {
+    let transport = ssh_state.transport(&session_id)?;
+    let options = options.unwrap_or_default();
+    let request = ExecRequest {
+        command,
+        stdin: options.stdin.map(String::into_bytes),
+        env: options.env,
+        timeout: options.timeout_secs.filter(|s| *s > 0).map(Duration::from_secs),
+        pty: options.pty,
+    };
+
+    let cancel = exec_state.register(&exec_id)?;
+    let stdout_event = format!("exec_stdout_{}", exec_id);
+    let stderr_event = format!("exec_stderr_{}", exec_id);
+    let result = run(&transport, &request, cancel, |stream, data| {
+        let event = match stream {
+            OutputStream::Stdout => &stdout_event,
+            OutputStream::Stderr => &stderr_event,
+        };
+        let _ = window.emit(event, data.to_vec());
+    })
+    .await;
+    exec_state.unregister(&exec_id);
+    result
+}
+
+/// Stop a running `exec_command`; it then returns with `cancelled` set
+#[tauri::command]
+pub fn cancel_exec(exec_state: State<'_, ExecState>, exec_id: String) -> Result<(), String> {
+    if exec_state.cancel(&exec_id) {
+        Ok(())
+    } else {
+        Err("Exec not running".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_helpers() {
+        assert!(valid_env_name("LANG"));
+        assert!(valid_env_name("_X1"));
+        assert!(!valid_env_name("1X"));
+        assert!(!valid_env_name("A-B"));
+    }
+}
@@ -0,0 +1,347 @@
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, Manager, State, Window};
+use tokio::sync::{watch, Semaphore};
+use crate::db::Database;
+use crate::exec::{self, ExecRequest, OutputStream};
+use crate::models::{FanoutHostResult, FanoutRun, Server, ServerSelector};
+use crate::repositories::{fanout, servers};
+use crate::routing::credentials_for;
+use crate::transport::SshTransport;
+
+const DEFAULT_PARALLELISM: u32 = 10;
+const MAX_PARALLELISM: u32 = 64;
+// Per host; live output is streamed in full, only the stored copy is cut
+const MAX_STORED_OUTPUT: usize = 256 * 1024;
+const KEPT_RUNS: i64 = 100;
+
+// Events carry the run id rather than being named after it: hosts start
+// emitting before start_fanout has returned the id to the caller
+
+#[derive(Clone, Serialize)]
+struct HostOutput {
+    run_id: i64,
+    server_id: i64,
+    stream: &'static str, // "stdout" or "stderr"
+    data: Vec<u8>,
+}
+
+#[derive(Clone, Serialize)]
+struct HostState {
+    run_id: i64,
+    server_id: i64,
+    state: &'static str, // "connecting" or "running"
+}
+
+#[derive(Clone, Serialize)]
+struct HostFinished {
+    run_id: i64,
+    result: FanoutHostResult,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FanoutRunDetail {
+    pub run: FanoutRun,
+    pub results: Vec<FanoutHostResult>,
+}
+
+/// Runs in progress, each with the signal that cancels it
+pub struct FanoutState {
+    running: Mutex<HashMap<i64, watch::Sender<bool>>>,
+}
+
+impl FanoutState {
+    pub fn new() -> Self {
+        Self {
+            running: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn register(&self, run_id: i64) -> watch::Receiver<bool> {
+        let (cancel, rx) = watch::channel(false);
+        self.running.lock().unwrap().insert(run_id, cancel);
+        rx
+    }
+
+    fn unregister(&self, run_id: i64) {
+        self.running.lock().unwrap().remove(&run_id);
+    }
+
+    fn is_running(&self, run_id: i64) -> bool {
+        self.running.lock().unwrap().contains_key(&run_id)
+    }
+
+    fn cancel(&self, run_id: i64) -> bool {
+        match self.running.lock().unwrap().get(&run_id) {
+            Some(cancel) => {
+                cancel.send_replace(true);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Output kept for the stored result: up to `MAX_STORED_OUTPUT` bytes
+struct Capture {
+    data: Vec<u8>,
+    truncated: bool,
+}
+
+impl Capture {
+    fn push(&mut self, chunk: &[u8]) {
+        let room = MAX_STORED_OUTPUT.saturating_sub(self.data.len());
+        self.data.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        self.truncated |= chunk.len() > room;
+    }
+}
+
+/// Connect to one server, run the command and close the connection again
+async fn run_host(
+    window: &Window,
+    run_id: i64,
+    server: &Server,
+    request: &ExecRequest,
+    mut cancel: watch::Receiver<bool>,
+) -> FanoutHostResult {
+    let server_id = server.id.unwrap_or_default();
+    let mut result = FanoutHostResult {
+        server_id,
+        server_name: server.name.clone(),
+        host: server.host.clone(),
+        exit_status: None,
+        exit_signal: None,
+        error: None,
+        timed_out: false,
+        cancelled: false,
+        duration_ms: 0,
+        output: String::new(),
+        truncated: false,
+    };
+    if *cancel.borrow() {
+        result.cancelled = true;
+        return result;
+    }
+
+    let emit_state = |state| {
+        let _ = window.emit("fanout_host", HostState { run_id, server_id, state });
+    };
+    emit_state("connecting");
+    let app = window.app_handle();
+    let mut credentials = credentials_for(server);
+    // Dropping the connect future below leaves the blocking handshake running,
+    // so it also gets the signal and stops before prompting the user
+    credentials.cancel = Some(cancel.clone());
+    let connect = SshTransport::connect(app, &server.host, server.port as u16, &mut credentials, server.jump_host_id);
+    let transport = tokio::select! {
+        transport = connect => transport,
+        _ = cancel.wait_for(|cancelled| *cancelled) => {
+            result.cancelled = true;
+            return result;
+        }
+    };
+    credentials.wipe();
+    let transport = match transport {
+        Ok(transport) => transport,
+        Err(e) => {
+            result.error = Some(e);
+            return result;
+        }
+    };
+
+    emit_state("running");
+    let mut capture = Capture { data: Vec::new(), truncated: false };
+    let exec = exec::run(&transport, request, cancel, |stream, data| {
+        capture.push(data);
+        let stream = match stream {
+            OutputStream::Stdout => "stdout",
+            OutputStream::Stderr => "stderr",
+        };
+        let _ = window.emit("fanout_output", HostOutput { run_id, server_id, stream, data: data.to_vec() });
+    })
+    .await;
+    transport.close();
+
+    match exec {
+        Ok(exec) => {
+            result.exit_status = exec.exit_status;
+            result.exit_signal = exec.exit_signal;
+            result.error = exec.error_message;
+            result.timed_out = exec.timed_out;
+            result.cancelled = exec.cancelled;
+            result.duration_ms = exec.duration_ms;
+        }
+        Err(e) => result.error = Some(e),
+    }
+    result.output = String::from_utf8_lossy(&capture.data).into_owned();
+    result.truncated = capture.truncated;
+    result
+}
+
+/// Run every host of a started run, at most `parallelism` at a time
+async fn run_all(window: Window, run: FanoutRun, targets: Vec<Server>, request: ExecRequest, cancel: watch::Receiver<bool>) {
+    let run_id = run.id.unwrap_or_default();
+    let app = window.app_handle().clone();
+    let request = Arc::new(request);
+    let permits = Arc::new(Semaphore::new(run.parallelism as usize));
+
+    let mut tasks = Vec::new();
+    for server in targets {
+        let (window, request, permits, cancel) = (window.clone(), request.clone(), permits.clone(), cancel.clone());
+        tasks.push(tauri::async_runtime::spawn(async move {
+            let Ok(_permit) = permits.acquire().await else { return };
+            let result = run_host(&window, run_id, &server, &request, cancel).await;
+            let db = window.state::<Database>();
+            if let Err(e) = fanout::add_result(&db, run_id, &result) {
+                eprintln!("Failed to save fan-out result for {}: {}", server.name, e);
+            }
+            let _ = window.emit("fanout_result", HostFinished { run_id, result });
+        }));
+    }
+    for task in tasks {
+        let _ = task.await;
+    }
+
+    let db = app.state::<Database>();
+    if let Err(e) = fanout::finish_run(&db, run_id).and_then(|_| fanout::prune_runs(&db, KEPT_RUNS)) {
+        eprintln!("Failed to finish fan-out run {}: {}", run_id, e);
+    }
+    let _ = db.flush();
+    app.state::<FanoutState>().unregister(run_id);
+    println!("Fan-out run {} finished", run_id);
+    let finished = fanout::get_run(&db, run_id).ok().flatten().unwrap_or(run);
+    let _ = window.emit("fanout_done", finished);
+}
+
+/// Saved servers a selector picks, so the targets can be reviewed first
+#[tauri::command]
+pub fn preview_fanout_targets(db: State<Database>, selector: ServerSelector) -> Result<Vec<Server>, String> {
+    servers::select_servers(&db, &selector).map_err(|e| e.to_string())
+}
+
+/// Start running `command` on every server `selector` picks. Progress is
+/// reported per host through `fanout_host`, `fanout_output` and
+/// `fanout_result` events, then `fanout_done` with the summary.
+#[tauri::command]
+pub fn start_fanout(
+    window: Window,
+    db: State<Database>,
+    fanout_state: State<FanoutState>,
+    command: String,
+    selector: ServerSelector,
+    parallelism: Option<u32>,
+    timeout_secs: Option<u64>,
+) -> Result<FanoutRun, String> {
+    if command.trim().is_empty() {
+        return Err("Command is empty".to_string());
+    }
+    if selector.is_empty() {
+        return Err("Select a group, tags or servers to run on".to_string());
+    }
+    let targets = servers::select_servers(&db, &selector).map_err(|e| e.to_string())?;
+    if targets.is_empty() {
+        return Err("No saved servers match the selection".to_string());
+    }
+
+    let mut run = FanoutRun {
+        id: None,
+        command,
+        selector,
+        parallelism: parallelism.unwrap_or(DEFAULT_PARALLELISM).clamp(1, MAX_PARALLELISM),
+        timeout_secs: timeout_secs.filter(|s| *s > 0),
+        started_at: Utc::now().timestamp(),
+        finished_at: None,
+        total: targets.len() as u32,
+        succeeded: 0,
+        failed: 0,
+    };
+    let run_id = fanout::create_run(&db, &run).map_err(|e| e.to_string())?;
+    run.id = Some(run_id);
+    let cancel = fanout_state.register(run_id);
+    println!("Fan-out run {}: {} hosts, {} at a time", run_id, run.total, run.parallelism);
+
+    let request = ExecRequest {
+        command: run.command.clone(),
+        timeout: run.timeout_secs.map(Duration::from_secs),
+        ..Default::default()
+    };
+    tauri::async_runtime::spawn(run_all(window, run.clone(), targets, request, cancel));
+    Ok(run)
+}
+
+/// Stop a run: hosts still waiting are skipped, running commands cancelled
+#[tauri::command]
+pub fn cancel_fanout(fanout_state: State<FanoutState>, run_id: i64) -> Result<(), String> {
+    if fanout_state.cancel(run_id) {
+        Ok(())
+    } else {
+        Err("Run is not in progress".to_string())
+    }
+}
+
+#[tauri::command]
+pub fn list_fanout_runs(db: State<Database>) -> Result<Vec<FanoutRun>, String> {
+    fanout::get_runs(&db, KEPT_RUNS).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_fanout_run(db: State<Database>, run_id: i64) -> Result<FanoutRunDetail, String> {
+    let run = fanout::get_run(&db, run_id).map_err(|e| e.to_string())?.ok_or("Run not found")?;
+    let results = fanout::get_results(&db, run_id).map_err(|e| e.to_string())?;
+    Ok(FanoutRunDetail { run, results })
+}
+
+#[tauri::command]
+pub fn delete_fanout_run(db: State<Database>, fanout_state: State<FanoutState>, run_id: i64) -> Result<(), String> {
+    if fanout_state.is_running(run_id) {
+        return Err("Run is still in progress".to_string());
+    }
+    fanout::delete_run(&db, run_id).map_err(|e| e.to_string())?;
+    db.flush().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(id: i64, group: Option<&str>, tags: &[&str]) -> Server {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": format!("host{}", id),
+            "host": format!("10.0.0.{}", id),
+            "port": 22,
+            "username": "root",
+            "group": group,
+            "tags": tags,
+            "created_at": 0,
+            "updated_at": 0,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_selector_matches() {
+        let web = server(1, Some("prod-web"), &["nginx", "eu"]);
+        let db = server(2, Some("prod-db"), &["eu"]);
+        let by_group = ServerSelector { group: Some("prod-web".to_string()), ..Default::default() };
+        let by_tag = ServerSelector { tags: vec!["nginx".to_string(), "us".to_string()], ..Default::default() };
+        let both = ServerSelector { group: Some("prod-db".to_string()), tags: vec!["nginx".to_string()], server_ids: vec![] };
+
+        assert!(by_group.matches(&web) && !by_group.matches(&db));
+        assert!(by_tag.matches(&web) && !by_tag.matches(&db));
+        assert!(!both.matches(&web) && !both.matches(&db));
+        assert!(ServerSelector { server_ids: vec![2], ..Default::default() }.matches(&db));
+    }
+
+    #[test]
+    fn test_capture_truncates() {
+        let mut capture = Capture { data: Vec::new(), truncated: false };
+        capture.push(&vec![b'a'; MAX_STORED_OUTPUT - 1]);
+        capture.push(b"bc");
+        assert_eq!(capture.data.len(), MAX_STORED_OUTPUT);
+        assert!(capture.truncated);
+    }
+}
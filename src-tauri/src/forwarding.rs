@@ -0,0 +1,555 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use ssh2::Channel;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use crate::models::PortForwardingRule;
+use crate::socks;
+use crate::ssh::SshState;
+use crate::transport::{SshTransport, Traffic};
+
+// How often traffic counters are pushed to the UI while they change
+const STATUS_INTERVAL: Duration = Duration::from_secs(1);
+const DEFAULT_BIND_ADDRESS: &str = "127.0.0.1";
+// Like OpenSSH, remote forwards listen on the server's loopback by default
+const DEFAULT_REMOTE_BIND_ADDRESS: &str = "localhost";
+const SOCKS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+// How long a restart waits for the previous instance to give up its port
+const RELEASE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Snapshot of a forward, emitted as `port_forward_status`
+#[derive(Debug, Clone, Serialize)]
+pub struct ForwardStatus {
+    pub session_id: String,
+    pub rule_id: String,
+    pub rule_type: String,
+    pub state: String, // "starting", "listening", "stopped" or "error"
+    pub bind_address: String,
+    pub active_connections: usize,
+    pub total_connections: usize,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub error: Option<String>,
+}
+
+/// One connection through a forward, emitted as `port_forward_connection`
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionStatus {
+    pub session_id: String,
+    pub rule_id: String,
+    pub connection_id: u64,
+    pub peer: String, // empty when unknown, as for remote forwards
+    pub target: String,
+    pub state: String, // "open" or "closed"
+    pub opened_at: i64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+}
+
+/// Forwards running on each session, keyed by session id then rule id
+pub struct ForwardState {
+    forwards: Mutex<HashMap<String, HashMap<String, Arc<Forward>>>>,
+    // Rules whose connection dropped, kept for the session to restart them
+    // once it reconnects
+    interrupted: Mutex<HashMap<String, Vec<PortForwardingRule>>>,
+}
+
+impl ForwardState {
+    pub fn new() -> Self {
+        Self {
+            forwards: Mutex::new(HashMap::new()),
+            interrupted: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Rules of `session_id` that stopped because its connection was lost
+    pub fn take_interrupted(&self, session_id: &str) -> Vec<PortForwardingRule> {
+        self.interrupted.lock().unwrap().remove(session_id).unwrap_or_default()
+    }
+
+    fn insert(&self, forward: &Arc<Forward>) -> Option<Arc<Forward>> {
+        self.forwards
+            .lock()
+            .unwrap()
+            .entry(forward.session_id.clone())
+            .or_default()
+            .insert(forward.rule.id.clone(), forward.clone())
+    }
+
+    fn get(&self, session_id: &str, rule_id: &str) -> Option<Arc<Forward>> {
+        let forwards = self.forwards.lock().unwrap();
+        forwards.get(session_id).and_then(|rules| rules.get(rule_id)).cloned()
+    }
+
+    // Only removes `forward` itself, not a newer instance of the same rule
+    fn remove(&self, forward: &Arc<Forward>) {
+        let mut forwards = self.forwards.lock().unwrap();
+        if let Some(rules) = forwards.get_mut(&forward.session_id) {
+            if rules.get(&forward.rule.id).is_some_and(|f| Arc::ptr_eq(f, forward)) {
+                rules.remove(&forward.rule.id);
+            }
+            if rules.is_empty() {
+                forwards.remove(&forward.session_id);
+            }
+        }
+    }
+}
+
+struct Phase {
+    state: &'static str,
+    bind_address: String,
+    error: Option<String>,
+}
+
+struct Connection {
+    id: u64,
+    peer: String,
+    target: String,
+    opened_at: i64,
+    traffic: Traffic,
+    // Counters as last reported, to skip unchanged updates
+    reported: Mutex<(u64, u64)>,
+}
+
+struct Forward {
+    app: AppHandle,
+    session_id: String,
+    rule: PortForwardingRule,
+    transport: Arc<SshTransport>,
+    stop: watch::Sender<bool>,
+    // Set once the listener is gone and the port can be bound again
+    released: watch::Sender<bool>,
+    traffic: Arc<Traffic>,
+    active: AtomicUsize,
+    total: AtomicUsize,
+    connections: Mutex<HashMap<u64, Arc<Connection>>>,
+    next_connection: AtomicU64,
+    phase: Mutex<Phase>,
+}
+
+impl Forward {
+    fn status(&self) -> ForwardStatus {
+        let phase = self.phase.lock().unwrap();
+        ForwardStatus {
+            session_id: self.session_id.clone(),
+            rule_id: self.rule.id.clone(),
+            rule_type: self.rule.rule_type.clone(),
+            state: phase.state.to_string(),
+            bind_address: phase.bind_address.clone(),
+            active_connections: self.active.load(Ordering::SeqCst),
+            total_connections: self.total.load(Ordering::SeqCst),
+            bytes_in: self.traffic.received(),
+            bytes_out: self.traffic.sent(),
+            error: phase.error.clone(),
+        }
+    }
+
+    fn emit(&self) {
+        let _ = self.app.emit("port_forward_status", self.status());
+    }
+
+    fn connection_status(&self, connection: &Connection, state: &str) -> ConnectionStatus {
+        ConnectionStatus {
+            session_id: self.session_id.clone(),
+            rule_id: self.rule.id.clone(),
+            connection_id: connection.id,
+            peer: connection.peer.clone(),
+            target: connection.target.clone(),
+            state: state.to_string(),
+            opened_at: connection.opened_at,
+            bytes_in: connection.traffic.received(),
+            bytes_out: connection.traffic.sent(),
+        }
+    }
+
+    fn emit_connection(&self, connection: &Connection, state: &str) {
+        *connection.reported.lock().unwrap() = (connection.traffic.sent(), connection.traffic.received());
+        let _ = self.app.emit("port_forward_connection", self.connection_status(connection, state));
+    }
+
+    /// Report open connections whose counters moved since the last update
+    fn emit_connection_updates(&self) {
+        let connections: Vec<Arc<Connection>> = self.connections.lock().unwrap().values().cloned().collect();
+        for connection in connections {
+            let current = (connection.traffic.sent(), connection.traffic.received());
+            if *connection.reported.lock().unwrap() != current {
+                self.emit_connection(&connection, "open");
+            }
+        }
+    }
+
+    fn set_state(&self, state: &'static str, error: Option<String>) {
+        {
+            let mut phase = self.phase.lock().unwrap();
+            phase.state = state;
+            if error.is_some() {
+                phase.error = error;
+            }
+        }
+        self.emit();
+    }
+
+    /// Record a problem with one connection; the forward keeps running
+    fn report_error(&self, error: String) {
+        eprintln!("Port forward {} ({}): {}", self.rule.id, self.session_id, error);
+        self.phase.lock().unwrap().error = Some(error);
+        self.emit();
+    }
+
+    /// Resolves when the forward is stopped or its session goes away
+    async fn stopped(&self) {
+        let mut stop = self.stop.subscribe();
+        tokio::select! {
+            _ = stop.wait_for(|stopped| *stopped) => {}
+            _ = self.transport.closed() => {}
+        }
+    }
+
+    fn release(&self) {
+        self.released.send_replace(true);
+    }
+
+    /// Stop the forward and wait until its listener is closed
+    async fn shut_down(&self) {
+        self.stop.send_replace(true);
+        let mut released = self.released.subscribe();
+        if tokio::time::timeout(RELEASE_TIMEOUT, released.wait_for(|released| *released)).await.is_err() {
+            eprintln!("Port forward {} did not release its listener in time", self.rule.id);
+        }
+    }
+
+    /// Pump one connection until either end closes or the forward stops
+    async fn serve(&self, channel: Channel, stream: TcpStream, peer: String, target: String) {
+        let connection = Arc::new(Connection {
+            id: self.next_connection.fetch_add(1, Ordering::SeqCst) + 1,
+            peer,
+            target,
+            opened_at: chrono::Utc::now().timestamp(),
+            traffic: Traffic::child_of(&self.traffic),
+            reported: Mutex::new((0, 0)),
+        });
+        self.connections.lock().unwrap().insert(connection.id, connection.clone());
+        self.active.fetch_add(1, Ordering::SeqCst);
+        self.total.fetch_add(1, Ordering::SeqCst);
+        self.emit();
+        self.emit_connection(&connection, "open");
+
+        tokio::select! {
+            _ = self.transport.pump(channel, stream, &connection.traffic) => {}
+            _ = self.stopped() => {}
+        }
+
+        self.connections.lock().unwrap().remove(&connection.id);
+        self.active.fetch_sub(1, Ordering::SeqCst);
+        self.emit();
+        self.emit_connection(&connection, "closed");
+    }
+
+    /// Handle a connection accepted on a local listener: straight to the
+    /// rule's destination, or wherever the SOCKS client asks for
+    async fn accept_local(&self, stream: TcpStream) {
+        let peer = stream.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+        if self.rule.rule_type == "Dynamic" {
+            return self.accept_socks(stream, peer).await;
+        }
+
+        let dest_host = self.rule.destination_host.clone().unwrap_or_else(|| "127.0.0.1".to_string());
+        let dest_port = self.rule.destination_port.unwrap_or(80);
+        match self.transport.channel_direct_tcpip(&dest_host, dest_port).await {
+            Ok(channel) => self.serve(channel, stream, peer, format!("{}:{}", dest_host, dest_port)).await,
+            Err(e) => self.report_error(format!("Channel to {}:{} failed: {}", dest_host, dest_port, e)),
+        }
+    }
+
+    async fn accept_socks(&self, mut stream: TcpStream, peer: String) {
+        let request = match tokio::time::timeout(SOCKS_HANDSHAKE_TIMEOUT, socks::accept(&mut stream)).await {
+            Ok(Ok(request)) => request,
+            Ok(Err(e)) => return self.report_error(format!("SOCKS handshake from {} failed: {}", peer, e)),
+            Err(_) => return self.report_error(format!("SOCKS handshake from {} timed out", peer)),
+        };
+
+        // Host names go to the server as-is, so they resolve on the remote side
+        let target = format!("{}:{}", request.host, request.port);
+        match self.transport.channel_direct_tcpip(&request.host, request.port).await {
+            Ok(channel) => {
+                if request.reply_success(&mut stream).await.is_ok() {
+                    self.serve(channel, stream, peer, target).await;
+                }
+            }
+            Err(e) => {
+                let _ = request.reply_failure(&mut stream).await;
+                self.report_error(format!("Channel to {} failed: {}", target, e));
+            }
+        }
+    }
+}
+
+/// Start `rule` on a session's transport. A rule that is already running is
+/// restarted. Failures are returned and also reported as a status event.
+pub async fn start_forward(
+    app: &AppHandle,
+    session_id: &str,
+    transport: Arc<SshTransport>,
+    rule: PortForwardingRule,
+) -> Result<(), String> {
+    let (stop, _) = watch::channel(false);
+    let (released, _) = watch::channel(false);
+    let forward = Arc::new(Forward {
+        app: app.clone(),
+        session_id: session_id.to_string(),
+        rule,
+        transport,
+        stop,
+        released,
+        traffic: Arc::new(Traffic::default()),
+        active: AtomicUsize::new(0),
+        total: AtomicUsize::new(0),
+        connections: Mutex::new(HashMap::new()),
+        next_connection: AtomicU64::new(0),
+        phase: Mutex::new(Phase {
+            state: "starting",
+            bind_address: String::new(),
+            error: None,
+        }),
+    });
+
+    // The previous instance of the rule has to close its listener first, or
+    // binding the same port again fails
+    if let Some(previous) = app.state::<ForwardState>().insert(&forward) {
+        previous.shut_down().await;
+    }
+
+    let started = match forward.rule.rule_type.as_str() {
+        "Local" | "Dynamic" => start_local(&forward).await,
+        "Remote" => start_remote(&forward).await,
+        other => Err(format!("Unsupported forwarding type: {}", other)),
+    };
+
+    match started {
+        Ok(bind_address) => {
+            println!("Port forward {} listening on {}", forward.rule.id, bind_address);
+            forward.phase.lock().unwrap().bind_address = bind_address;
+            forward.set_state("listening", None);
+            tauri::async_runtime::spawn(report(forward));
+            Ok(())
+        }
+        Err(e) => {
+            forward.release();
+            app.state::<ForwardState>().remove(&forward);
+            forward.set_state("error", Some(e.clone()));
+            Err(e)
+        }
+    }
+}
+
+/// Push counters while they change, then report the forward as stopped
+async fn report(forward: Arc<Forward>) {
+    let mut last = None;
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(STATUS_INTERVAL) => {}
+            _ = forward.stopped() => break,
+        }
+        let snapshot = (
+            forward.active.load(Ordering::SeqCst),
+            forward.traffic.sent(),
+            forward.traffic.received(),
+        );
+        if last != Some(snapshot) {
+            forward.emit();
+            forward.emit_connection_updates();
+            last = Some(snapshot);
+        }
+    }
+
+    let state = forward.app.state::<ForwardState>();
+    state.remove(&forward);
+    // A restart already put a newer instance in place, whose status the UI
+    // now shows
+    if state.get(&forward.session_id, &forward.rule.id).is_some() {
+        forward.phase.lock().unwrap().state = "stopped";
+        println!("Port forward {} replaced", forward.rule.id);
+        return;
+    }
+    // A transport closed under a session that is still open was lost, not
+    // disconnected, so the session may bring the rule back
+    let lost = !*forward.stop.borrow()
+        && forward.transport.is_closed()
+        && forward.app.state::<SshState>().sessions.lock().unwrap().contains_key(&forward.session_id);
+    if lost {
+        state
+            .interrupted
+            .lock()
+            .unwrap()
+            .entry(forward.session_id.clone())
+            .or_default()
+            .push(forward.rule.clone());
+    }
+    forward.set_state("stopped", None);
+    println!("Port forward {} stopped", forward.rule.id);
+}
+
+/// Listen locally and open a direct-tcpip channel for every accepted
+/// connection: to the destination (ssh -L), or to the target requested by a
+/// SOCKS4/5 client (ssh -D). Returns the bound address.
+async fn start_local(forward: &Arc<Forward>) -> Result<String, String> {
+    let rule = &forward.rule;
+    let bind_host = rule.bind_address.clone().unwrap_or_else(|| DEFAULT_BIND_ADDRESS.to_string());
+    // The SOCKS proxy takes no credentials, so anyone who can reach it can
+    // reach the remote network: keep it to this machine
+    if rule.rule_type == "Dynamic" && !is_loopback(&bind_host, rule.source_port).await {
+        return Err(format!("Dynamic forwards can only listen on a loopback address, not {}", bind_host));
+    }
+    let listener = TcpListener::bind((bind_host.as_str(), rule.source_port))
+        .await
+        .map_err(|e| format!("Failed to bind {}:{}: {}", bind_host, rule.source_port, e))?;
+    let bind_address = listener.local_addr().map_err(|e| e.to_string())?.to_string();
+    let forward = forward.clone();
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let stream = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        forward.report_error(format!("Accept failed: {}", e));
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        continue;
+                    }
+                },
+                _ = forward.stopped() => break,
+            };
+
+            let forward = forward.clone();
+            tauri::async_runtime::spawn(async move { forward.accept_local(stream).await });
+        }
+        drop(listener);
+        forward.release();
+    });
+
+    Ok(bind_address)
+}
+
+// Whether every address `host` resolves to is a loopback address
+async fn is_loopback(host: &str, port: u16) -> bool {
+    match tokio::net::lookup_host((host, port)).await {
+        Ok(addrs) => {
+            let addrs: Vec<_> = addrs.collect();
+            !addrs.is_empty() && addrs.iter().all(|addr| addr.ip().is_loopback())
+        }
+        Err(_) => false,
+    }
+}
+
+/// Have the server listen on `source_port` and connect every forwarded
+/// channel to the destination on this machine (ssh -R). Returns the address
+/// bound on the server.
+async fn start_remote(forward: &Arc<Forward>) -> Result<String, String> {
+    let rule = &forward.rule;
+    let bind_host = rule.bind_address.clone().unwrap_or_else(|| DEFAULT_REMOTE_BIND_ADDRESS.to_string());
+    let (mut listener, bound_port) = forward
+        .transport
+        .forward_listen(&bind_host, rule.source_port)
+        .await
+        .map_err(|e| format!("Server refused to listen on {}:{}: {}", bind_host, rule.source_port, e))?;
+
+    let dest_host = rule.destination_host.clone().unwrap_or_else(|| "127.0.0.1".to_string());
+    let dest_port = rule.destination_port.unwrap_or(80);
+    let forward = forward.clone();
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let channel = tokio::select! {
+                accepted = forward.transport.call(|| listener.accept()) => match accepted {
+                    Ok(channel) => channel,
+                    Err(e) => {
+                        if forward.transport.is_closed() {
+                            break;
+                        }
+                        forward.report_error(format!("Accept failed: {}", e));
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        continue;
+                    }
+                },
+                _ = forward.stopped() => break,
+            };
+
+            let forward = forward.clone();
+            let dest_host = dest_host.clone();
+            tauri::async_runtime::spawn(async move {
+                match TcpStream::connect((dest_host.as_str(), dest_port)).await {
+                    Ok(stream) => {
+                        // libssh2 does not expose the originator address of a
+                        // forwarded-tcpip channel, so the peer stays unknown
+                        let target = format!("{}:{}", dest_host, dest_port);
+                        forward.serve(channel, stream, String::new(), target).await
+                    }
+                    Err(e) => {
+                        forward.report_error(format!("Connection to {}:{} failed: {}", dest_host, dest_port, e));
+                        let mut channel = channel;
+                        let _ = forward.transport.call(|| channel.close()).await;
+                    }
+                }
+            });
+        }
+
+        forward.transport.cancel_listener(listener);
+        forward.release();
+    });
+
+    Ok(format!("{}:{}", bind_host, bound_port))
+}
+
+#[tauri::command]
+pub async fn start_port_forward(
+    app: AppHandle,
+    ssh_state: tauri::State<'_, SshState>,
+    session_id: String,
+    rule: PortForwardingRule,
+) -> Result<(), String> {
+    let transport = ssh_state.transport(&session_id)?;
+    start_forward(&app, &session_id, transport, rule).await
+}
+
+#[tauri::command]
+pub fn stop_port_forward(
+    state: tauri::State<'_, ForwardState>,
+    session_id: String,
+    rule_id: String,
+) -> Result<(), String> {
+    let forward = state.get(&session_id, &rule_id).ok_or("Port forward not running")?;
+    forward.stop.send_replace(true);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_port_forwards(
+    state: tauri::State<'_, ForwardState>,
+    session_id: String,
+) -> Result<Vec<ForwardStatus>, String> {
+    let forwards = state.forwards.lock().unwrap();
+    Ok(forwards
+        .get(&session_id)
+        .map(|rules| rules.values().map(|f| f.status()).collect())
+        .unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn get_port_forward_connections(
+    state: tauri::State<'_, ForwardState>,
+    session_id: String,
+    rule_id: String,
+) -> Result<Vec<ConnectionStatus>, String> {
+    let forward = state.get(&session_id, &rule_id).ok_or("Port forward not running")?;
+    let connections = forward.connections.lock().unwrap();
+    let mut statuses: Vec<ConnectionStatus> = connections
+        .values()
+        .map(|c| forward.connection_status(c, "open"))
+        .collect();
+    statuses.sort_by_key(|c| c.connection_id);
+    Ok(statuses)
+}
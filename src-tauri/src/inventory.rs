@@ -0,0 +1,718 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use tauri::State;
+use crate::db::Database;
+use crate::models::{PortForwardingRule, Server};
+use crate::repositories::{credentials, servers};
+use crate::ssh_config::{HostConfig, SshConfig};
+use crate::ssh_utils::expand_home;
+use crate::vault::VaultState;
+
+// Forwarding rules created by the importer carry this id prefix, so a
+// re-import replaces them without touching rules added in the app
+const IMPORTED_RULE_PREFIX: &str = "ssh-config-";
+
+// Bumped whenever the layout of the JSON export changes
+const EXPORT_VERSION: u32 = 1;
+
+/// A field that an import would change on an existing server
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// One `Host` alias mapped onto a server row
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportEntry {
+    pub alias: String,
+    pub action: String, // "create", "update" or "unchanged"
+    pub existing_id: Option<i64>,
+    pub server: Server,
+    pub jump_host: Option<String>, // ProxyJump hop, by alias or host name
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportPreview {
+    pub entries: Vec<ImportEntry>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportSummary {
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub warnings: Vec<String>,
+}
+
+fn default_config_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Failed to get home directory")?;
+    Ok(home.join(".ssh").join("config"))
+}
+
+fn load_config(path: Option<String>) -> Result<SshConfig, String> {
+    let path = match path {
+        Some(p) => PathBuf::from(p),
+        None => default_config_path()?,
+    };
+    SshConfig::load(&path)
+}
+
+/// Split `[bind:]port`, as used by the *Forward options
+fn parse_listen(spec: &str) -> Option<(Option<String>, u16)> {
+    match spec.rsplit_once(':') {
+        Some((bind, port)) => {
+            let bind = bind.trim_start_matches('[').trim_end_matches(']');
+            let bind = if bind == "*" { "0.0.0.0" } else { bind };
+            Some((Some(bind.to_string()).filter(|b| !b.is_empty()), port.parse().ok()?))
+        }
+        None => Some((None, spec.parse().ok()?)),
+    }
+}
+
+/// Split `host:port` (`[v6]:port` or `host/port` also accepted)
+fn parse_target(spec: &str) -> Option<(String, u16)> {
+    let (host, port) = spec.rsplit_once(':').or_else(|| spec.rsplit_once('/'))?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    Some((host.to_string(), port.parse().ok()?))
+}
+
+fn forwarding_rules(host: &HostConfig, warnings: &mut Vec<String>) -> Vec<PortForwardingRule> {
+    let mut rules = Vec::new();
+    for (key, rule_type, letter) in [
+        ("LocalForward", "Local", "L"),
+        ("RemoteForward", "Remote", "R"),
+        ("DynamicForward", "Dynamic", "D"),
+    ] {
+        for args in host.all(key) {
+            let listen = args.first().and_then(|a| parse_listen(a));
+            // Dynamic forwards have no fixed destination
+            let target = match rule_type {
+                "Dynamic" => Some(None),
+                _ => args.get(1).and_then(|a| parse_target(a)).map(Some),
+            };
+            let (Some((bind_address, source_port)), Some(target)) = (listen, target) else {
+                warnings.push(format!("{}: skipped unsupported {} {}", host.alias, key, args.join(" ")));
+                continue;
+            };
+
+            rules.push(PortForwardingRule {
+                id: format!("{}{}-{}", IMPORTED_RULE_PREFIX, letter, source_port),
+                rule_type: rule_type.to_string(),
+                source_port,
+                destination_host: target.as_ref().map(|(h, _)| h.clone()),
+                destination_port: target.map(|(_, p)| p),
+                bind_address,
+            });
+        }
+    }
+    rules
+}
+
+/// The hop a `ProxyJump` value reaches the host through: the last entry,
+/// without user or port
+fn proxy_jump_hop(value: &str) -> Option<String> {
+    if value.eq_ignore_ascii_case("none") {
+        return None;
+    }
+    let hop = value.rsplit(',').next()?.trim();
+    let hop = hop.rsplit_once('@').map(|(_, h)| h).unwrap_or(hop);
+    let hop = match hop.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or(v6),
+        None => hop.split(':').next().unwrap_or(hop),
+    };
+    Some(hop.to_string()).filter(|h| !h.is_empty())
+}
+
+fn find_existing<'a>(existing: &'a [Server], name: &str, host: &str, port: i32, username: &str) -> Option<&'a Server> {
+    existing
+        .iter()
+        .find(|s| s.name == name)
+        .or_else(|| existing.iter().find(|s| s.host == host && s.port == port && s.username == username))
+}
+
+fn describe_rules(rules: &Option<Vec<PortForwardingRule>>) -> Option<String> {
+    rules.as_ref().filter(|r| !r.is_empty()).map(|rules| {
+        rules
+            .iter()
+            .map(|r| match (&r.destination_host, r.destination_port) {
+                (Some(host), Some(port)) => format!("{} {} -> {}:{}", r.rule_type, r.source_port, host, port),
+                _ => format!("{} {}", r.rule_type, r.source_port),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    })
+}
+
+/// Map every concrete alias in `config` onto a server row and compare it
+/// with what is already stored
+fn build_preview(config: &SshConfig, existing: &[Server], default_user: &str) -> ImportPreview {
+    let mut warnings = config.warnings.clone();
+    let mut entries = Vec::new();
+
+    for alias in config.aliases() {
+        let host_config = config.resolve(&alias);
+        let host = host_config
+            .get("HostName")
+            .map(|h| h.replace("%h", &alias))
+            .unwrap_or_else(|| alias.clone());
+        let port = host_config.get("Port").and_then(|p| p.parse().ok()).unwrap_or(22);
+        let username = host_config.get("User").map(str::to_string);
+        let identity_file = host_config.get("IdentityFile").map(str::to_string);
+        let rules = forwarding_rules(&host_config, &mut warnings);
+        let jump_host = host_config.get("ProxyJump").and_then(proxy_jump_hop);
+        let forward_agent = match host_config.get("ForwardAgent") {
+            Some(v) if v.eq_ignore_ascii_case("yes") => Some(true),
+            Some(v) if v.eq_ignore_ascii_case("no") => Some(false),
+            Some(v) => {
+                warnings.push(format!("{}: ForwardAgent {} is not supported and was ignored", alias, v));
+                None
+            }
+            None => None,
+        };
+
+        if host_config.get("ProxyJump").is_some_and(|v| v.contains(',')) {
+            warnings.push(format!(
+                "{}: only the last ProxyJump hop is linked; earlier hops come from that host's own settings",
+                alias
+            ));
+        }
+        if host_config.get("ProxyCommand").is_some() {
+            warnings.push(format!("{}: ProxyCommand is not supported and was ignored", alias));
+        }
+
+        let lookup_user = username.clone().unwrap_or_else(|| default_user.to_string());
+        let current = find_existing(existing, &alias, &host, port, &lookup_user);
+
+        let mut server = match current {
+            Some(current) => current.clone(),
+            None => Server {
+                id: None,
+                name: alias.clone(),
+                host: host.clone(),
+                port,
+                username: lookup_user.clone(),
+                password: None,
+                private_key_path: None,
+                key_passphrase: None,
+                credential_id: None,
+                has_password: false,
+                has_key_passphrase: false,
+                key_id: None,
+                forward_agent: false,
+                record_sessions: false,
+                group: None,
+                tags: None,
+                color: None,
+                forwarding_rules: None,
+                jump_host_id: None,
+                created_at: 0,
+                updated_at: 0,
+            },
+        };
+
+        // Only what the config specifies overrides the stored row
+        server.host = host;
+        server.port = port;
+        if let Some(username) = username {
+            server.username = username;
+        }
+        if identity_file.is_some() {
+            server.private_key_path = identity_file;
+        }
+        if let Some(forward_agent) = forward_agent {
+            server.forward_agent = forward_agent;
+        }
+        if !rules.is_empty() {
+            let mut merged: Vec<PortForwardingRule> = server
+                .forwarding_rules
+                .take()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|r| !r.id.starts_with(IMPORTED_RULE_PREFIX))
+                .collect();
+            merged.extend(rules);
+            server.forwarding_rules = Some(merged);
+        }
+
+        let mut changes = Vec::new();
+        if let Some(current) = current {
+            let mut compare = |field: &str, old: Option<String>, new: Option<String>| {
+                if old != new {
+                    changes.push(FieldChange { field: field.to_string(), old, new });
+                }
+            };
+            compare("host", Some(current.host.clone()), Some(server.host.clone()));
+            compare("port", Some(current.port.to_string()), Some(server.port.to_string()));
+            compare("username", Some(current.username.clone()), Some(server.username.clone()));
+            compare("private_key_path", current.private_key_path.clone(), server.private_key_path.clone());
+            compare("forward_agent", Some(current.forward_agent.to_string()), Some(server.forward_agent.to_string()));
+            compare("forwarding_rules", describe_rules(&current.forwarding_rules), describe_rules(&server.forwarding_rules));
+
+            let current_jump = current
+                .jump_host_id
+                .and_then(|id| existing.iter().find(|s| s.id == Some(id)))
+                .map(|s| s.name.clone());
+            if jump_host.is_some() && current_jump != jump_host {
+                compare("jump_host", current_jump, jump_host.clone());
+            }
+        }
+
+        let action = match (current, changes.is_empty()) {
+            (None, _) => "create",
+            (Some(_), false) => "update",
+            (Some(_), true) => "unchanged",
+        };
+
+        entries.push(ImportEntry {
+            alias,
+            action: action.to_string(),
+            existing_id: current.and_then(|s| s.id),
+            server,
+            jump_host,
+            changes,
+        });
+    }
+
+    ImportPreview { entries, warnings }
+}
+
+fn default_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "root".to_string())
+}
+
+/// Parse ~/.ssh/config (or `path`) and show what importing it would do
+#[tauri::command]
+pub fn preview_ssh_config_import(db: State<Database>, path: Option<String>) -> Result<ImportPreview, String> {
+    let config = load_config(path)?;
+    let existing = servers::get_all_servers(&db).map_err(|e| e.to_string())?;
+    Ok(build_preview(&config, &existing, &default_user()))
+}
+
+/// Create or update servers for the selected aliases (all when `aliases` is
+/// omitted), then link ProxyJump hops to their servers
+#[tauri::command]
+pub fn import_ssh_config(
+    db: State<Database>,
+    path: Option<String>,
+    aliases: Option<Vec<String>>,
+) -> Result<ImportSummary, String> {
+    let config = load_config(path)?;
+    let existing = servers::get_all_servers(&db).map_err(|e| e.to_string())?;
+    let preview = build_preview(&config, &existing, &default_user());
+
+    let mut summary = ImportSummary { created: 0, updated: 0, unchanged: 0, warnings: preview.warnings };
+    let mut ids: HashMap<String, i64> = HashMap::new();
+    let mut imported = Vec::new();
+
+    for mut entry in preview.entries {
+        if aliases.as_ref().is_some_and(|selected| !selected.contains(&entry.alias)) {
+            continue;
+        }
+        match entry.action.as_str() {
+            "create" => {
+                let id = servers::create_server(&db, &entry.server).map_err(|e| e.to_string())?;
+                entry.server.id = Some(id);
+                summary.created += 1;
+            }
+            "update" => {
+                servers::update_server(&db, &entry.server).map_err(|e| e.to_string())?;
+                summary.updated += 1;
+            }
+            _ => summary.unchanged += 1,
+        }
+        if let Some(id) = entry.server.id {
+            ids.insert(entry.alias.clone(), id);
+        }
+        imported.push(entry);
+    }
+
+    // Second pass, now that every imported alias has an id
+    for mut entry in imported {
+        let Some(jump_host) = entry.jump_host.as_deref() else {
+            continue;
+        };
+        let jump_id = ids.get(jump_host).copied().or_else(|| {
+            existing
+                .iter()
+                .find(|s| s.name == jump_host || s.host == jump_host)
+                .and_then(|s| s.id)
+        });
+        let Some(jump_id) = jump_id else {
+            summary.warnings.push(format!("{}: jump host '{}' not found", entry.alias, jump_host));
+            continue;
+        };
+        if entry.server.jump_host_id == Some(jump_id) {
+            continue;
+        }
+        if let Err(e) = crate::routing::resolve_route(&db, entry.server.id, Some(jump_id)) {
+            summary.warnings.push(format!("{}: {}", entry.alias, e));
+            continue;
+        }
+        entry.server.jump_host_id = Some(jump_id);
+        servers::update_server(&db, &entry.server).map_err(|e| e.to_string())?;
+    }
+
+    db.flush().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+/// Versioned JSON export document
+#[derive(Debug, Serialize)]
+struct ExportDocument {
+    version: u32,
+    exported_at: i64,
+    includes_secrets: bool,
+    servers: Vec<ExportedServer>,
+}
+
+/// A server in the JSON export. `Server` never serializes its secrets, so
+/// they are added here when the export includes them.
+#[derive(Debug, Serialize)]
+struct ExportedServer {
+    #[serde(flatten)]
+    server: Server,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key_passphrase: Option<String>,
+}
+
+/// A `Host` alias for a server; ssh aliases can't contain whitespace
+fn host_alias(server: &Server) -> String {
+    let alias: String = server
+        .name
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-");
+    if alias.is_empty() { server.host.clone() } else { alias }
+}
+
+fn quote_arg(value: &str) -> String {
+    if value.contains(char::is_whitespace) {
+        format!("\"{}\"", value)
+    } else {
+        value.to_string()
+    }
+}
+
+fn forward_spec(rule: &PortForwardingRule) -> Option<(&'static str, String)> {
+    let listen = match &rule.bind_address {
+        Some(bind) if bind.contains(':') => format!("[{}]:{}", bind, rule.source_port),
+        Some(bind) => format!("{}:{}", bind, rule.source_port),
+        None => rule.source_port.to_string(),
+    };
+    let target = || match (&rule.destination_host, rule.destination_port) {
+        (Some(host), Some(port)) if host.contains(':') => Some(format!("[{}]:{}", host, port)),
+        (Some(host), Some(port)) => Some(format!("{}:{}", host, port)),
+        _ => None,
+    };
+    match rule.rule_type.as_str() {
+        "Local" => Some(("LocalForward", format!("{} {}", listen, target()?))),
+        "Remote" => Some(("RemoteForward", format!("{} {}", listen, target()?))),
+        "Dynamic" => Some(("DynamicForward", listen)),
+        _ => None,
+    }
+}
+
+/// Render servers as an OpenSSH client config. Passwords have no place in
+/// ssh_config and are never written.
+fn render_ssh_config(servers: &[Server]) -> String {
+    let mut out = format!(
+        "# Exported from NebulaSSH on {}\n",
+        chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    for server in servers {
+        out.push_str(&format!("\nHost {}\n", host_alias(server)));
+        if let Some(group) = &server.group {
+            out.push_str(&format!("    # Group: {}\n", group));
+        }
+        out.push_str(&format!("    HostName {}\n", server.host));
+        out.push_str(&format!("    Port {}\n", server.port));
+        out.push_str(&format!("    User {}\n", server.username));
+        if let Some(key) = &server.private_key_path {
+            out.push_str(&format!("    IdentityFile {}\n", quote_arg(key)));
+        }
+        if let Some(jump) = server
+            .jump_host_id
+            .and_then(|id| servers.iter().find(|s| s.id == Some(id)))
+        {
+            // The hop's own block carries its ProxyJump, so one alias is enough
+            out.push_str(&format!("    ProxyJump {}\n", host_alias(jump)));
+        }
+        if server.forward_agent {
+            out.push_str("    ForwardAgent yes\n");
+        }
+        for rule in server.forwarding_rules.iter().flatten() {
+            if let Some((key, spec)) = forward_spec(rule) {
+                out.push_str(&format!("    {} {}\n", key, spec));
+            }
+        }
+    }
+    out
+}
+
+fn render_json(servers: Vec<Server>, include_secrets: bool) -> Result<String, String> {
+    let document = ExportDocument {
+        version: EXPORT_VERSION,
+        exported_at: chrono::Utc::now().timestamp(),
+        includes_secrets: include_secrets,
+        servers: servers
+            .into_iter()
+            .map(|mut server| {
+                let (password, key_passphrase) = if include_secrets {
+                    (server.password.take(), server.key_passphrase.take())
+                } else {
+                    (None, None)
+                };
+                ExportedServer { server, password, key_passphrase }
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&document).map_err(|e| e.to_string())
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn render_csv(servers: &[Server], include_secrets: bool) -> Result<String, String> {
+    let mut header = vec![
+        "id", "name", "host", "port", "username", "private_key_path", "group", "tags", "color",
+        "jump_host_id", "forwarding_rules",
+    ];
+    if include_secrets {
+        header.extend(["password", "key_passphrase"]);
+    }
+
+    let mut out = header.join(",");
+    out.push('\n');
+    for server in servers {
+        let rules = match &server.forwarding_rules {
+            Some(rules) if !rules.is_empty() => serde_json::to_string(rules).map_err(|e| e.to_string())?,
+            _ => String::new(),
+        };
+        let mut row = vec![
+            server.id.map(|id| id.to_string()).unwrap_or_default(),
+            server.name.clone(),
+            server.host.clone(),
+            server.port.to_string(),
+            server.username.clone(),
+            server.private_key_path.clone().unwrap_or_default(),
+            server.group.clone().unwrap_or_default(),
+            server.tags.as_ref().map(|t| t.join(";")).unwrap_or_default(),
+            server.color.clone().unwrap_or_default(),
+            server.jump_host_id.map(|id| id.to_string()).unwrap_or_default(),
+            rules,
+        ];
+        if include_secrets {
+            row.push(server.password.clone().unwrap_or_default());
+            row.push(server.key_passphrase.clone().unwrap_or_default());
+        }
+        let row: Vec<String> = row.iter().map(|v| csv_field(v)).collect();
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Write an export, readable only by the current user when it holds secrets
+fn write_export(path: &str, contents: &str, include_secrets: bool) -> Result<(), String> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    if include_secrets {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options
+        .open(expand_home(path))
+        .map_err(|e| format!("Failed to create {}: {}", path, e))?;
+    // The mode only applies to new files; an existing one is tightened before
+    // any secret is written to it
+    #[cfg(unix)]
+    if include_secrets {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to restrict {}: {}", path, e))?;
+    }
+    #[cfg(not(unix))]
+    let _ = include_secrets;
+    file.write_all(contents.as_bytes()).map_err(|e| e.to_string())
+}
+
+/// Export every server to `path` as "ssh_config", "json" or "csv". Secrets
+/// (passwords, key passphrases) are left out unless `include_secrets` is set
+/// for a format that can hold them, which needs a fresh
+/// `confirm_master_password` like revealing a secret. Returns the number of
+/// servers written.
+#[tauri::command]
+pub fn export_servers(
+    db: State<Database>,
+    vault: State<VaultState>,
+    path: String,
+    format: String,
+    include_secrets: bool,
+) -> Result<usize, String> {
+    // ssh_config has nowhere to put secrets
+    let include_secrets = include_secrets && format != "ssh_config";
+    let mut all = servers::get_all_servers(&db).map_err(|e| e.to_string())?;
+    if include_secrets {
+        vault.require_confirmation()?;
+        for server in &mut all {
+            let Some(credential_id) = server.credential_id else {
+                continue;
+            };
+            if let Some(stored) = credentials::get_credential(&db, credential_id).map_err(|e| e.to_string())? {
+                server.password = stored.password;
+                server.key_passphrase = stored.key_passphrase;
+            }
+        }
+    }
+    let count = all.len();
+
+    let contents = match format.as_str() {
+        "ssh_config" => render_ssh_config(&all),
+        "json" => render_json(all, include_secrets)?,
+        "csv" => render_csv(&all, include_secrets)?,
+        other => return Err(format!("Unsupported export format: {}", other)),
+    };
+    write_export(&path, &contents, include_secrets)?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    const CONFIG: &str = "
+Host bastion
+    HostName 203.0.113.5
+    User ops
+
+Host app
+    HostName app.internal
+    ProxyJump ops@bastion:22
+    ForwardAgent yes
+    LocalForward 127.0.0.1:5432 db.internal:5432
+    DynamicForward 1080
+";
+
+    #[test]
+    fn test_parse_forward_specs() {
+        assert_eq!(parse_listen("8080"), Some((None, 8080)));
+        assert_eq!(parse_listen("*:8080"), Some((Some("0.0.0.0".to_string()), 8080)));
+        assert_eq!(parse_listen("[::1]:8080"), Some((Some("::1".to_string()), 8080)));
+        assert_eq!(parse_target("db:5432"), Some(("db".to_string(), 5432)));
+        assert_eq!(proxy_jump_hop("a,ops@b:2222"), Some("b".to_string()));
+        assert_eq!(proxy_jump_hop("none"), None);
+    }
+
+    #[test]
+    fn test_preview_creates_and_diffs() {
+        let config = SshConfig::parse(CONFIG, Path::new("/nonexistent"));
+
+        let preview = build_preview(&config, &[], "me");
+        assert_eq!(preview.entries.len(), 2);
+        let app = &preview.entries[1];
+        assert_eq!(app.action, "create");
+        assert_eq!(app.server.username, "me");
+        assert_eq!(app.jump_host.as_deref(), Some("bastion"));
+        let rules = app.server.forwarding_rules.as_ref().unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].destination_host.as_deref(), Some("db.internal"));
+        assert_eq!(rules[1].rule_type, "Dynamic");
+
+        // Stored bastion with another address: only the changed field shows up
+        let mut stored = preview.entries[0].server.clone();
+        stored.id = Some(7);
+        stored.host = "198.51.100.1".to_string();
+        stored.password = Some("secret".to_string());
+        let preview = build_preview(&config, &[stored], "me");
+        let bastion = &preview.entries[0];
+        assert_eq!(bastion.action, "update");
+        assert_eq!(bastion.changes.len(), 1);
+        assert_eq!(bastion.changes[0].field, "host");
+        assert_eq!(bastion.server.password.as_deref(), Some("secret"));
+    }
+
+    #[test]
+    fn test_export_round_trips_through_import() {
+        let config = SshConfig::parse(CONFIG, Path::new("/nonexistent"));
+        let mut servers: Vec<Server> = build_preview(&config, &[], "me")
+            .entries
+            .into_iter()
+            .map(|e| e.server)
+            .collect();
+        servers[0].id = Some(1);
+        servers[1].id = Some(2);
+        servers[1].jump_host_id = Some(1);
+
+        let exported = render_ssh_config(&servers);
+        assert!(exported.contains("ProxyJump bastion\n"));
+        assert!(exported.contains("ForwardAgent yes\n"));
+        assert_eq!(exported.matches("ForwardAgent").count(), 1);
+        assert!(exported.contains("LocalForward 127.0.0.1:5432 db.internal:5432\n"));
+        assert!(exported.contains("DynamicForward 1080\n"));
+
+        let reparsed = build_preview(&SshConfig::parse(&exported, Path::new("/nonexistent")), &[], "me");
+        let app = &reparsed.entries[1];
+        assert_eq!(app.server.host, "app.internal");
+        assert_eq!(app.jump_host.as_deref(), Some("bastion"));
+        assert!(app.server.forward_agent && !reparsed.entries[0].server.forward_agent);
+        assert_eq!(app.server.forwarding_rules.as_ref().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_csv_escaping_and_secrets() {
+        let config = SshConfig::parse("Host \"web, prod\"\n  HostName web\n", Path::new("/nonexistent"));
+        let mut servers: Vec<Server> = build_preview(&config, &[], "me")
+            .entries
+            .into_iter()
+            .map(|e| e.server)
+            .collect();
+        servers[0].password = Some("p\"w".to_string());
+
+        let without = render_csv(&servers, false).unwrap();
+        assert!(without.lines().nth(1).unwrap().starts_with(",\"web, prod\",web,22,me"));
+        assert!(!without.contains("password"));
+
+        let with = render_csv(&servers, true).unwrap();
+        assert!(with.trim_end().ends_with(",\"p\"\"w\","));
+    }
+
+    #[test]
+    fn test_json_secrets() {
+        let config = SshConfig::parse("Host web\n  HostName web\n", Path::new("/nonexistent"));
+        let mut servers: Vec<Server> = build_preview(&config, &[], "me")
+            .entries
+            .into_iter()
+            .map(|e| e.server)
+            .collect();
+        servers[0].password = Some("hunter2".to_string());
+        servers[0].key_passphrase = Some("open sesame".to_string());
+
+        let without: serde_json::Value = serde_json::from_str(&render_json(servers.clone(), false).unwrap()).unwrap();
+        assert_eq!(without["includes_secrets"], false);
+        assert_eq!(without["servers"][0]["host"], "web");
+        assert!(without["servers"][0].get("password").is_none());
+        assert!(without["servers"][0].get("key_passphrase").is_none());
+
+        let with: serde_json::Value = serde_json::from_str(&render_json(servers, true).unwrap()).unwrap();
+        assert_eq!(with["servers"][0]["password"], "hunter2");
+        assert_eq!(with["servers"][0]["key_passphrase"], "open sesame");
+    }
+}
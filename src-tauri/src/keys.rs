@@ -0,0 +1,455 @@
+use openssl::bn::BigNumContext;
+use openssl::ec::PointConversionForm;
+use openssl::nid::Nid;
+use openssl::pkey::{Id, PKey, Private};
+use openssl::rsa::Rsa;
+use ssh_encoding::Decode;
+use ssh_key::private::{EcdsaKeypair, Ed25519Keypair, KeypairData};
+use ssh_key::rand_core::OsRng;
+use ssh_key::{Algorithm, EcdsaCurve, HashAlg, LineEnding, PrivateKey};
+use std::time::Duration;
+use tauri::State;
+use crate::db::Database;
+use crate::models::SshKey;
+use crate::ppk::{self, PpkKey};
+use crate::repositories::keys;
+use crate::ssh::SshState;
+use crate::ssh_utils::{expand_home, shell_quote};
+
+const DEFAULT_RSA_BITS: u32 = 4096;
+
+pub(crate) fn put_string(buf: &mut Vec<u8>, data: &[u8]) {
+    buf.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    buf.extend_from_slice(data);
+}
+
+/// Append an unsigned big-endian integer as a canonical SSH mpint
+fn put_mpint(buf: &mut Vec<u8>, bytes: &[u8]) {
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    let bytes = &bytes[start..];
+    if bytes.first().is_some_and(|&b| b >= 0x80) {
+        let mut padded = vec![0];
+        padded.extend_from_slice(bytes);
+        put_string(buf, &padded);
+    } else {
+        put_string(buf, bytes);
+    }
+}
+
+pub(crate) fn read_string<'a>(reader: &mut &'a [u8]) -> Result<&'a [u8], String> {
+    let invalid = || "Truncated key data".to_string();
+    let len_bytes: [u8; 4] = reader.get(..4).ok_or_else(invalid)?.try_into().unwrap();
+    let len = u32::from_be_bytes(len_bytes) as usize;
+    let data = reader.get(4..4 + len).ok_or_else(invalid)?;
+    *reader = &reader[4 + len..];
+    Ok(data)
+}
+
+/// Keypair from the OpenSSH private key wire encoding
+fn decode_keypair(wire: &[u8]) -> Result<KeypairData, String> {
+    KeypairData::decode(&mut &wire[..]).map_err(|e| format!("Invalid key data: {}", e))
+}
+
+fn rsa_keypair(n: &[u8], e: &[u8], d: &[u8], iqmp: &[u8], p: &[u8], q: &[u8]) -> Result<KeypairData, String> {
+    let mut wire = Vec::new();
+    put_string(&mut wire, b"ssh-rsa");
+    for value in [n, e, d, iqmp, p, q] {
+        put_mpint(&mut wire, value);
+    }
+    decode_keypair(&wire)
+}
+
+fn ecdsa_keypair(curve: &str, point: &[u8], private: &[u8]) -> Result<KeypairData, String> {
+    let size = match curve {
+        "nistp256" => 32,
+        "nistp384" => 48,
+        "nistp521" => 66,
+        other => return Err(format!("Unsupported ECDSA curve: {}", other)),
+    };
+    // The scalar is stored at the curve's full width
+    let start = private.iter().position(|&b| b != 0).unwrap_or(private.len());
+    let scalar = &private[start..];
+    if scalar.len() > size {
+        return Err("Invalid ECDSA private key".to_string());
+    }
+    let mut padded = vec![0; size - scalar.len()];
+    padded.extend_from_slice(scalar);
+
+    let mut wire = Vec::new();
+    put_string(&mut wire, format!("ecdsa-sha2-{}", curve).as_bytes());
+    put_string(&mut wire, curve.as_bytes());
+    put_string(&mut wire, point);
+    if padded[0] >= 0x80 {
+        padded.insert(0, 0);
+    }
+    put_string(&mut wire, &padded);
+    decode_keypair(&wire)
+}
+
+fn ed25519_keypair(seed: &[u8]) -> Result<KeypairData, String> {
+    let seed: &[u8; 32] = seed.try_into().map_err(|_| "Invalid Ed25519 private key")?;
+    Ok(KeypairData::from(Ed25519Keypair::from_seed(seed)))
+}
+
+fn keypair_from_openssl(pkey: &PKey<Private>) -> Result<KeypairData, String> {
+    match pkey.id() {
+        Id::RSA => {
+            let rsa = pkey.rsa().map_err(|e| e.to_string())?;
+            keypair_from_rsa(&rsa)
+        }
+        Id::EC => {
+            let ec = pkey.ec_key().map_err(|e| e.to_string())?;
+            let curve = match ec.group().curve_name() {
+                Some(Nid::X9_62_PRIME256V1) => "nistp256",
+                Some(Nid::SECP384R1) => "nistp384",
+                Some(Nid::SECP521R1) => "nistp521",
+                _ => return Err("Unsupported ECDSA curve".to_string()),
+            };
+            let mut ctx = BigNumContext::new().map_err(|e| e.to_string())?;
+            let point = ec
+                .public_key()
+                .to_bytes(ec.group(), PointConversionForm::UNCOMPRESSED, &mut ctx)
+                .map_err(|e| e.to_string())?;
+            ecdsa_keypair(curve, &point, &ec.private_key().to_vec())
+        }
+        Id::ED25519 => ed25519_keypair(&pkey.raw_private_key().map_err(|e| e.to_string())?),
+        _ => Err("Unsupported key type (expected RSA, ECDSA or Ed25519)".to_string()),
+    }
+}
+
+fn keypair_from_rsa(rsa: &Rsa<Private>) -> Result<KeypairData, String> {
+    let missing = || "Incomplete RSA private key".to_string();
+    rsa_keypair(
+        &rsa.n().to_vec(),
+        &rsa.e().to_vec(),
+        &rsa.d().to_vec(),
+        &rsa.iqmp().ok_or_else(missing)?.to_vec(),
+        &rsa.p().ok_or_else(missing)?.to_vec(),
+        &rsa.q().ok_or_else(missing)?.to_vec(),
+    )
+}
+
+fn keypair_from_ppk(key: &PpkKey) -> Result<KeypairData, String> {
+    let mut public = &key.public_blob[..];
+    let mut private = &key.private_blob[..];
+    let algorithm = read_string(&mut public)?;
+    if algorithm != key.algorithm.as_bytes() {
+        return Err("PPK public key does not match its algorithm".to_string());
+    }
+
+    match key.algorithm.as_str() {
+        "ssh-rsa" => {
+            let e = read_string(&mut public)?;
+            let n = read_string(&mut public)?;
+            let d = read_string(&mut private)?;
+            let p = read_string(&mut private)?;
+            let q = read_string(&mut private)?;
+            let iqmp = read_string(&mut private)?;
+            rsa_keypair(n, e, d, iqmp, p, q)
+        }
+        "ssh-ed25519" => {
+            let expected = read_string(&mut public)?;
+            let keypair = ed25519_keypair(read_string(&mut private)?)?;
+            match keypair.ed25519() {
+                Some(k) if k.public.as_ref() == expected => Ok(keypair),
+                _ => Err("PPK private key does not match its public key".to_string()),
+            }
+        }
+        alg if alg.starts_with("ecdsa-sha2-") => {
+            let curve = std::str::from_utf8(read_string(&mut public)?).map_err(|e| e.to_string())?;
+            let point = read_string(&mut public)?;
+            ecdsa_keypair(curve, point, read_string(&mut private)?)
+        }
+        other => Err(format!("Unsupported PPK key type: {}", other)),
+    }
+}
+
+/// Parse a private key in OpenSSH, PEM (PKCS#1, PKCS#8, SEC1) or PuTTY
+/// format and return it decrypted
+pub(crate) fn parse_private_key(content: &str, passphrase: Option<&str>) -> Result<PrivateKey, String> {
+    let passphrase = passphrase.filter(|p| !p.is_empty());
+
+    if ppk::is_ppk(content) {
+        let key = ppk::parse(content, passphrase)?;
+        return PrivateKey::new(keypair_from_ppk(&key)?, key.comment).map_err(|e| e.to_string());
+    }
+
+    if content.contains("BEGIN OPENSSH PRIVATE KEY") {
+        let key = PrivateKey::from_openssh(content).map_err(|e| format!("Invalid OpenSSH key: {}", e))?;
+        if !key.is_encrypted() {
+            return Ok(key);
+        }
+        let passphrase = passphrase.ok_or("Passphrase required")?;
+        return key.decrypt(passphrase).map_err(|_| "Incorrect passphrase".to_string());
+    }
+
+    if content.contains("-----BEGIN") {
+        let pkey = match passphrase {
+            Some(passphrase) => PKey::private_key_from_pem_passphrase(content.as_bytes(), passphrase.as_bytes())
+                .map_err(|_| "Incorrect passphrase or invalid PEM key".to_string())?,
+            None if content.contains("ENCRYPTED") => return Err("Passphrase required".to_string()),
+            None => PKey::private_key_from_pem(content.as_bytes()).map_err(|e| format!("Invalid PEM key: {}", e))?,
+        };
+        return PrivateKey::new(keypair_from_openssl(&pkey)?, "").map_err(|e| e.to_string());
+    }
+
+    Err("Unrecognized private key format".to_string())
+}
+
+fn generate(key_type: &str, bits: Option<u32>) -> Result<KeypairData, String> {
+    match key_type {
+        "ed25519" => Ok(KeypairData::from(Ed25519Keypair::random(&mut OsRng))),
+        "ecdsa" => {
+            let curve = match bits.unwrap_or(256) {
+                256 => EcdsaCurve::NistP256,
+                384 => EcdsaCurve::NistP384,
+                521 => EcdsaCurve::NistP521,
+                other => return Err(format!("Unsupported ECDSA key size: {}", other)),
+            };
+            EcdsaKeypair::random(&mut OsRng, curve)
+                .map(KeypairData::from)
+                .map_err(|e| e.to_string())
+        }
+        "rsa" => {
+            let bits = bits.unwrap_or(DEFAULT_RSA_BITS);
+            if !(2048..=16384).contains(&bits) {
+                return Err(format!("Unsupported RSA key size: {}", bits));
+            }
+            let rsa = Rsa::generate(bits).map_err(|e| e.to_string())?;
+            keypair_from_rsa(&rsa)
+        }
+        other => Err(format!("Unknown key type: {}", other)),
+    }
+}
+
+fn key_bits(key: &PrivateKey) -> Option<i32> {
+    match key.algorithm() {
+        Algorithm::Ed25519 => Some(256),
+        Algorithm::Ecdsa { curve: EcdsaCurve::NistP256 } => Some(256),
+        Algorithm::Ecdsa { curve: EcdsaCurve::NistP384 } => Some(384),
+        Algorithm::Ecdsa { curve: EcdsaCurve::NistP521 } => Some(521),
+        Algorithm::Rsa { .. } => key
+            .public_key()
+            .key_data()
+            .rsa()
+            .and_then(|rsa| rsa.n.as_positive_bytes())
+            .map(|n| n.len() as i32 * 8),
+        _ => None,
+    }
+}
+
+/// OpenSSH encoding of `key` for storage, protected by `passphrase` if set
+fn seal(key: &PrivateKey, passphrase: Option<&str>) -> Result<(String, bool), String> {
+    let sealed = match passphrase.filter(|p| !p.is_empty()) {
+        Some(passphrase) => key.encrypt(&mut OsRng, passphrase).map_err(|e| e.to_string())?,
+        None => key.clone(),
+    };
+    let pem = sealed.to_openssh(LineEnding::LF).map_err(|e| e.to_string())?;
+    Ok((pem.to_string(), sealed.is_encrypted()))
+}
+
+fn store(db: &Database, name: String, key: &PrivateKey, passphrase: Option<&str>) -> Result<SshKey, String> {
+    let (private_key, encrypted) = seal(key, passphrase)?;
+    let comment = key.comment();
+    let mut record = SshKey {
+        id: None,
+        name,
+        key_type: key.algorithm().as_str().to_string(),
+        bits: key_bits(key),
+        fingerprint: key.fingerprint(HashAlg::Sha256).to_string(),
+        public_key: key.public_key().to_openssh().map_err(|e| e.to_string())?,
+        encrypted,
+        comment: (!comment.is_empty()).then(|| comment.to_string()),
+        created_at: 0,
+        updated_at: 0,
+    };
+
+    let id = keys::add_key(db, &record, &private_key).map_err(|e| e.to_string())?;
+    db.flush().map_err(|e| e.to_string())?;
+    println!("Stored {} key {} ({})", record.key_type, record.name, record.fingerprint);
+
+    record.id = Some(id);
+    keys::get_key(db, id).map_err(|e| e.to_string())?.ok_or_else(|| "Key not found".to_string())
+}
+
+pub(crate) fn load_key(db: &Database, id: i64) -> Result<(SshKey, PrivateKey), String> {
+    let record = keys::get_key(db, id).map_err(|e| e.to_string())?.ok_or("Key not found")?;
+    let pem = keys::get_private_key(db, id).map_err(|e| e.to_string())?.ok_or("Key not found")?;
+    let key = PrivateKey::from_openssh(&pem).map_err(|e| e.to_string())?;
+    Ok((record, key))
+}
+
+/// Shell script appending `public_key` to ~/.ssh/authorized_keys unless a
+/// line with the same key is already there. Prints "added" or "present".
+fn deploy_script(public_key: &str) -> String {
+    // Match on "<type> <base64>" so a different comment still counts
+    let key_only: String = public_key.split_whitespace().take(2).collect::<Vec<_>>().join(" ");
+    let script = format!(
+        "umask 077; mkdir -p ~/.ssh && touch ~/.ssh/authorized_keys || exit 1; \
+         if grep -qF {key} ~/.ssh/authorized_keys; then echo present; exit 0; fi; \
+         if [ -s ~/.ssh/authorized_keys ] && [ -n \"$(tail -c 1 ~/.ssh/authorized_keys)\" ]; then echo >> ~/.ssh/authorized_keys; fi; \
+         printf '%s\\n' {line} >> ~/.ssh/authorized_keys && echo added",
+        key = shell_quote(&key_only),
+        line = shell_quote(public_key),
+    );
+    format!("sh -c {}", shell_quote(&script))
+}
+
+#[tauri::command]
+pub fn get_keys(db: State<Database>) -> Result<Vec<SshKey>, String> {
+    keys::get_all_keys(&db).map_err(|e| e.to_string())
+}
+
+/// Create a new key pair. `key_type` is "ed25519", "ecdsa" (bits 256, 384
+/// or 521) or "rsa" (bits 2048-16384, default 4096).
+#[tauri::command]
+pub async fn generate_key(
+    db: State<'_, Database>,
+    name: String,
+    key_type: String,
+    bits: Option<u32>,
+    comment: Option<String>,
+    passphrase: Option<String>,
+) -> Result<SshKey, String> {
+    // RSA generation and key encryption take a moment
+    let key = tauri::async_runtime::spawn_blocking(move || -> Result<PrivateKey, String> {
+        let keypair = generate(&key_type, bits)?;
+        PrivateKey::new(keypair, comment.unwrap_or_default()).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    store(&db, name, &key, passphrase.as_deref())
+}
+
+/// Import a private key given as file content or a path. The passphrase
+/// unlocks the source key and keeps protecting the stored copy.
+#[tauri::command]
+pub fn import_key(
+    db: State<Database>,
+    name: String,
+    key: String,
+    passphrase: Option<String>,
+) -> Result<SshKey, String> {
+    let trimmed = key.trim_start();
+    let content = if trimmed.starts_with("-----BEGIN") || ppk::is_ppk(trimmed) {
+        key
+    } else {
+        std::fs::read_to_string(expand_home(key.trim()))
+            .map_err(|e| format!("Failed to read private key {}: {}", key.trim(), e))?
+    };
+
+    let private_key = parse_private_key(&content, passphrase.as_deref())?;
+    store(&db, name, &private_key, passphrase.as_deref())
+}
+
+/// Set, change or (with an empty `new_passphrase`) remove a key's passphrase
+#[tauri::command]
+pub fn change_key_passphrase(
+    db: State<Database>,
+    id: i64,
+    old_passphrase: Option<String>,
+    new_passphrase: Option<String>,
+) -> Result<SshKey, String> {
+    let (_, key) = load_key(&db, id)?;
+    let key = if key.is_encrypted() {
+        let old = old_passphrase.filter(|p| !p.is_empty()).ok_or("Current passphrase required")?;
+        key.decrypt(old).map_err(|_| "Incorrect passphrase".to_string())?
+    } else {
+        key
+    };
+
+    let (private_key, encrypted) = seal(&key, new_passphrase.as_deref())?;
+    keys::update_private_key(&db, id, &private_key, encrypted).map_err(|e| e.to_string())?;
+    db.flush().map_err(|e| e.to_string())?;
+    keys::get_key(&db, id).map_err(|e| e.to_string())?.ok_or_else(|| "Key not found".to_string())
+}
+
+/// The key's authorized_keys line, also written to `path` when given
+#[tauri::command]
+pub fn export_public_key(db: State<Database>, id: i64, path: Option<String>) -> Result<String, String> {
+    let record = keys::get_key(&db, id).map_err(|e| e.to_string())?.ok_or("Key not found")?;
+    if let Some(path) = path {
+        std::fs::write(expand_home(&path), format!("{}\n", record.public_key)).map_err(|e| e.to_string())?;
+    }
+    Ok(record.public_key)
+}
+
+#[tauri::command]
+pub fn delete_key(db: State<Database>, id: i64) -> Result<(), String> {
+    keys::delete_key(&db, id).map_err(|e| e.to_string())?;
+    db.flush().map_err(|e| e.to_string())
+}
+
+/// Install a key's public half on the server behind an open session.
+/// Returns false when the key was already authorized.
+#[tauri::command]
+pub async fn deploy_key(
+    db: State<'_, Database>,
+    ssh_state: State<'_, SshState>,
+    session_id: String,
+    key_id: i64,
+) -> Result<bool, String> {
+    let record = keys::get_key(&db, key_id).map_err(|e| e.to_string())?.ok_or("Key not found")?;
+    let transport = ssh_state.transport(&session_id)?;
+    let output = transport.exec(&deploy_script(&record.public_key), Duration::from_secs(15)).await?;
+
+    match output.lines().last().map(str::trim) {
+        Some("added") => {
+            println!("Deployed key {} to session {}", record.fingerprint, session_id);
+            Ok(true)
+        }
+        Some("present") => Ok(false),
+        _ => Err(format!("Could not update authorized_keys: {}", output.trim())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_imports_pem_keys() {
+        let rsa = Rsa::generate(2048).unwrap();
+        let group = openssl::ec::EcGroup::from_curve_name(Nid::SECP384R1).unwrap();
+        let pkeys = [
+            PKey::from_rsa(rsa.clone()).unwrap(),
+            PKey::from_ec_key(openssl::ec::EcKey::generate(&group).unwrap()).unwrap(),
+            PKey::generate_ed25519().unwrap(),
+        ];
+
+        let parsed: Vec<PrivateKey> = pkeys
+            .iter()
+            .map(|pkey| {
+                let pem = String::from_utf8(pkey.private_key_to_pem_pkcs8().unwrap()).unwrap();
+                parse_private_key(&pem, None).unwrap()
+            })
+            .collect();
+
+        let n = parsed[0].public_key().key_data().rsa().unwrap().n.as_positive_bytes().unwrap();
+        assert_eq!(n, rsa.n().to_vec());
+        assert_eq!(key_bits(&parsed[0]), Some(2048));
+        assert_eq!(key_bits(&parsed[1]), Some(384));
+        assert_eq!(parsed[2].algorithm(), Algorithm::Ed25519);
+    }
+
+    #[test]
+    fn test_sealed_key_round_trips() {
+        let key = PrivateKey::new(generate("ed25519", None).unwrap(), "me@host").unwrap();
+        let (pem, encrypted) = seal(&key, Some("secret")).unwrap();
+        assert!(encrypted);
+        assert!(parse_private_key(&pem, Some("wrong")).is_err());
+
+        let opened = parse_private_key(&pem, Some("secret")).unwrap();
+        assert_eq!(opened.fingerprint(HashAlg::Sha256), key.fingerprint(HashAlg::Sha256));
+        assert_eq!(opened.comment(), "me@host");
+    }
+
+    #[test]
+    fn test_deploy_script_quotes_key() {
+        let script = deploy_script("ssh-ed25519 AAAAC3Nz it's me");
+        assert!(script.starts_with("sh -c '"));
+        assert!(script.contains("grep -qF"));
+        assert!(!script.contains("it's me"));
+    }
+}
@@ -0,0 +1,438 @@
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD};
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use ssh2::{HostKeyType, Session};
+use ssh_key::Certificate;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+use crate::certificates::{certifies, validate_host_certificate, CERT_AUTHORITY};
+use crate::db::Database;
+use crate::models::KnownHost;
+use crate::prompts::{new_request_id, PromptAnswer, PromptState};
+use crate::repositories::known_hosts;
+
+/// Prefix of the error returned when a server presents a different key than
+/// the one we trust, so the frontend can tell it apart from other failures.
+pub const HOST_KEY_CHANGED: &str = "HOST_KEY_CHANGED";
+
+/// Marker of `@revoked` entries: keys that must never be accepted
+pub const REVOKED: &str = "revoked";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HostKeyPrompt {
+    pub request_id: String,
+    pub host: String,
+    pub port: u16,
+    pub key_type: String,
+    pub fingerprint: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KnownHostsImportSummary {
+    pub imported: usize,
+    pub skipped: usize,
+}
+
+enum HostKeyStatus {
+    Revoked,
+    Trusted,
+    Unknown,
+    Changed(KnownHost),
+}
+
+/// Name a host is recorded under, following OpenSSH's known_hosts convention
+pub fn host_pattern(host: &str, port: u16) -> String {
+    if port == 22 {
+        host.to_string()
+    } else {
+        format!("[{}]:{}", host, port)
+    }
+}
+
+pub fn key_type_name(kind: HostKeyType) -> &'static str {
+    match kind {
+        HostKeyType::Rsa => "ssh-rsa",
+        HostKeyType::Dss => "ssh-dss",
+        HostKeyType::Ecdsa256 => "ecdsa-sha2-nistp256",
+        HostKeyType::Ecdsa384 => "ecdsa-sha2-nistp384",
+        HostKeyType::Ecdsa521 => "ecdsa-sha2-nistp521",
+        HostKeyType::Ed25519 => "ssh-ed25519",
+        HostKeyType::Unknown => "unknown",
+    }
+}
+
+/// OpenSSH style SHA256 fingerprint of a raw key blob
+pub fn fingerprint(blob: &[u8]) -> String {
+    format!("SHA256:{}", STANDARD_NO_PAD.encode(Sha256::digest(blob)))
+}
+
+// "|1|base64(salt)|base64(HMAC-SHA1(salt, hostname))"
+fn hashed_host_matches(entry: &str, name: &str) -> bool {
+    let mut parts = entry.trim_start_matches("|1|").splitn(2, '|');
+    let (Some(salt), Some(hash)) = (parts.next(), parts.next()) else {
+        return false;
+    };
+    let (Ok(salt), Ok(hash)) = (STANDARD.decode(salt), STANDARD.decode(hash)) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha1>::new_from_slice(&salt) else {
+        return false;
+    };
+    mac.update(name.as_bytes());
+    mac.verify_slice(&hash).is_ok()
+}
+
+// Glob match supporting '*' and '?' as used in known_hosts and ssh_config
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi].eq_ignore_ascii_case(&t[ti])) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+/// Does a comma separated known_hosts host field match `name`?
+/// Negated patterns ("!host") veto a match, as in OpenSSH.
+fn host_field_matches(field: &str, name: &str) -> bool {
+    if field.starts_with("|1|") {
+        return hashed_host_matches(field, name);
+    }
+
+    let mut matched = false;
+    for pattern in field.split(',') {
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if wildcard_match(negated, name) {
+                return false;
+            }
+        } else if wildcard_match(pattern, name) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+// Whether `public_key` is listed as `@revoked` for the host
+fn is_revoked(entries: &[KnownHost], name: &str, public_key: &str) -> bool {
+    entries.iter().any(|entry| {
+        entry.marker.as_deref() == Some(REVOKED)
+            && entry.public_key == public_key
+            && host_field_matches(&entry.host, name)
+    })
+}
+
+fn check_host_key(entries: &[KnownHost], name: &str, key_type: &str, public_key: &str) -> HostKeyStatus {
+    if is_revoked(entries, name, public_key) {
+        return HostKeyStatus::Revoked;
+    }
+
+    let matching: Vec<&KnownHost> = entries
+        .iter()
+        .filter(|entry| entry.marker.is_none() && host_field_matches(&entry.host, name))
+        .collect();
+
+    if matching.iter().any(|entry| entry.public_key == public_key) {
+        return HostKeyStatus::Trusted;
+    }
+
+    match matching.into_iter().find(|entry| entry.key_type == key_type) {
+        Some(entry) => HostKeyStatus::Changed(entry.clone()),
+        None => HostKeyStatus::Unknown,
+    }
+}
+
+/// `@cert-authority` entries covering the host, trusted to sign its certificate
+pub fn cert_authorities_for(app: &AppHandle, host: &str, port: u16) -> Result<Vec<KnownHost>, String> {
+    let name = host_pattern(host, port);
+    let db = app.state::<Database>();
+    let entries = known_hosts::get_all_known_hosts(&db).map_err(|e| e.to_string())?;
+    Ok(entries
+        .iter()
+        .filter(|entry| entry.marker.as_deref() == Some(CERT_AUTHORITY) && host_field_matches(&entry.host, &name))
+        // A revoked authority vouches for nothing
+        .filter(|entry| !is_revoked(&entries, &name, &entry.public_key))
+        .cloned()
+        .collect())
+}
+
+/// Check the key presented during `handshake()` against the known hosts store.
+/// A key vouched for by `certificate`, signed by a trusted `@cert-authority`,
+/// is accepted outright; otherwise unknown hosts are confirmed by the user
+/// (trust on first use) and a mismatch with a trusted key is a hard failure.
+pub fn verify_host_key(
+    app: &AppHandle,
+    sess: &Session,
+    host: &str,
+    port: u16,
+    certificate: Option<&Certificate>,
+) -> Result<(), String> {
+    let (blob, kind) = sess.host_key().ok_or("Server did not present a host key")?;
+    let key_type = key_type_name(kind);
+    let public_key = STANDARD.encode(blob);
+    let fingerprint = fingerprint(blob);
+    let name = host_pattern(host, port);
+
+    let db = app.state::<Database>();
+    let entries = known_hosts::get_all_known_hosts(&db).map_err(|e| e.to_string())?;
+
+    if let Some(cert) = certificate.filter(|_| !is_revoked(&entries, &name, &public_key)) {
+        let authorities = cert_authorities_for(app, host, port)?;
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        let checked = if certifies(cert, blob) {
+            validate_host_certificate(cert, &authorities, host, now)
+        } else {
+            Err("it certifies a different key than the server presented".to_string())
+        };
+        match checked {
+            Ok(()) => {
+                println!("Host key for {} is certified by a trusted authority ({})", name, cert.key_id());
+                return Ok(());
+            }
+            // As OpenSSH does, fall back to checking the plain key
+            Err(e) => eprintln!("Host certificate for {} not accepted: {}", name, e),
+        }
+    }
+
+    match check_host_key(&entries, &name, key_type, &public_key) {
+        HostKeyStatus::Revoked => Err(format!(
+            "The {} host key presented by {} ({}) is marked as revoked in known hosts",
+            key_type, name, fingerprint
+        )),
+        HostKeyStatus::Trusted => Ok(()),
+        HostKeyStatus::Changed(expected) => Err(format!(
+            "{}: the {} host key for {} has changed (expected {}, got {}). \
+             Someone could be intercepting the connection; if the change is expected, \
+             remove the old key from known hosts and connect again.",
+            HOST_KEY_CHANGED, key_type, name, expected.fingerprint, fingerprint
+        )),
+        HostKeyStatus::Unknown => {
+            println!("Unknown host key for {}: {} {}", name, key_type, fingerprint);
+            let request_id = new_request_id();
+            let prompt = HostKeyPrompt {
+                request_id: request_id.clone(),
+                host: host.to_string(),
+                port,
+                key_type: key_type.to_string(),
+                fingerprint: fingerprint.clone(),
+            };
+
+            let prompts = app.state::<PromptState>();
+            match prompts.ask(app, "host_key_prompt", &request_id, prompt)? {
+                PromptAnswer::HostKey(true) => {
+                    known_hosts::add_known_host(&db, &KnownHost {
+                        id: None,
+                        host: name,
+                        key_type: key_type.to_string(),
+                        public_key,
+                        fingerprint,
+                        comment: None,
+                        marker: None,
+                        created_at: 0,
+                    }).map_err(|e| e.to_string())?;
+                    db.flush().map_err(|e| e.to_string())?;
+                    Ok(())
+                }
+                _ => Err("Host key was not trusted".to_string()),
+            }
+        }
+    }
+}
+
+/// Parse one line of an OpenSSH known_hosts file. Malformed lines and
+/// unknown markers yield `None`.
+fn parse_known_hosts_line(line: &str) -> Option<KnownHost> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let mut fields = line.split_whitespace().peekable();
+    let marker = match fields.peek() {
+        Some(&"@cert-authority") => {
+            fields.next();
+            Some(CERT_AUTHORITY.to_string())
+        }
+        Some(&"@revoked") => {
+            fields.next();
+            Some(REVOKED.to_string())
+        }
+        Some(field) if field.starts_with('@') => return None,
+        _ => None,
+    };
+    let host = fields.next()?;
+    let key_type = fields.next()?;
+    let public_key = fields.next()?;
+    let comment = fields.collect::<Vec<_>>().join(" ");
+
+    let blob = STANDARD.decode(public_key).ok()?;
+
+    Some(KnownHost {
+        id: None,
+        host: host.to_string(),
+        key_type: key_type.to_string(),
+        public_key: public_key.to_string(),
+        fingerprint: fingerprint(&blob),
+        comment: if comment.is_empty() { None } else { Some(comment) },
+        marker,
+        created_at: 0,
+    })
+}
+
+fn default_known_hosts_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Failed to get home directory")?;
+    Ok(home.join(".ssh").join("known_hosts"))
+}
+
+#[tauri::command]
+pub fn get_known_hosts(db: State<Database>) -> Result<Vec<KnownHost>, String> {
+    known_hosts::get_all_known_hosts(&db).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn delete_known_host(db: State<Database>, id: i64) -> Result<(), String> {
+    known_hosts::delete_known_host(&db, id).map_err(|e| e.to_string())?;
+    db.flush().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn import_system_known_hosts(
+    db: State<Database>,
+    path: Option<String>,
+) -> Result<KnownHostsImportSummary, String> {
+    let path = match path {
+        Some(p) => PathBuf::from(p),
+        None => default_known_hosts_path()?,
+    };
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let mut summary = KnownHostsImportSummary { imported: 0, skipped: 0 };
+    for line in content.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let Some(entry) = parse_known_hosts_line(line) else {
+            summary.skipped += 1;
+            continue;
+        };
+
+        let exists = known_hosts::known_host_exists(&db, &entry.host, &entry.key_type, &entry.public_key)
+            .map_err(|e| e.to_string())?;
+        if exists {
+            summary.skipped += 1;
+            continue;
+        }
+
+        known_hosts::add_known_host(&db, &entry).map_err(|e| e.to_string())?;
+        summary.imported += 1;
+    }
+
+    db.flush().map_err(|e| e.to_string())?;
+    println!("Imported {} known hosts from {} ({} skipped)", summary.imported, path.display(), summary.skipped);
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn respond_host_key_prompt(
+    prompts: State<PromptState>,
+    request_id: String,
+    accept: bool,
+) -> Result<(), String> {
+    prompts.answer(&request_id, PromptAnswer::HostKey(accept))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(host: &str, public_key: &str) -> KnownHost {
+        KnownHost {
+            id: None,
+            host: host.to_string(),
+            key_type: "ssh-ed25519".to_string(),
+            public_key: public_key.to_string(),
+            fingerprint: String::new(),
+            comment: None,
+            marker: None,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_host_patterns() {
+        assert!(host_field_matches("example.com,10.0.0.1", "10.0.0.1"));
+        assert!(host_field_matches("*.example.com", "db.example.com"));
+        assert!(host_field_matches("[git.local]:2222", &host_pattern("git.local", 2222)));
+        assert!(!host_field_matches("git.local", &host_pattern("git.local", 2222)));
+        assert!(!host_field_matches("*.example.com,!db.example.com", "db.example.com"));
+    }
+
+    #[test]
+    fn test_hashed_host() {
+        // Same construction `ssh-keygen -H` uses for hashed entries
+        let salt = [7u8; 20];
+        let mut mac = Hmac::<Sha1>::new_from_slice(&salt).unwrap();
+        mac.update(b"localhost");
+        let hashed = format!("|1|{}|{}", STANDARD.encode(salt), STANDARD.encode(mac.finalize().into_bytes()));
+
+        assert!(host_field_matches(&hashed, "localhost"));
+        assert!(!host_field_matches(&hashed, "otherhost"));
+    }
+
+    #[test]
+    fn test_check_host_key() {
+        let entries = vec![entry("example.com", "AAAA1")];
+
+        assert!(matches!(check_host_key(&entries, "example.com", "ssh-ed25519", "AAAA1"), HostKeyStatus::Trusted));
+        assert!(matches!(check_host_key(&entries, "example.com", "ssh-ed25519", "AAAA2"), HostKeyStatus::Changed(_)));
+        assert!(matches!(check_host_key(&entries, "example.com", "ssh-rsa", "BBBB"), HostKeyStatus::Unknown));
+        assert!(matches!(check_host_key(&entries, "other.com", "ssh-ed25519", "AAAA1"), HostKeyStatus::Unknown));
+    }
+
+    #[test]
+    fn test_revoked_key() {
+        let parsed = parse_known_hosts_line("@revoked * ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl").unwrap();
+        assert_eq!(parsed.host, "*");
+        assert_eq!(parsed.marker.as_deref(), Some(REVOKED));
+
+        // Revocation wins even over an entry trusting the same key
+        let entries = vec![entry("example.com", &parsed.public_key), parsed.clone()];
+        assert!(matches!(check_host_key(&entries, "example.com", "ssh-ed25519", &parsed.public_key), HostKeyStatus::Revoked));
+        assert!(matches!(check_host_key(&entries, "example.com", "ssh-ed25519", "AAAA2"), HostKeyStatus::Changed(_)));
+        assert!(matches!(check_host_key(std::slice::from_ref(&parsed), "other.com", "ssh-ed25519", "AAAA2"), HostKeyStatus::Unknown));
+    }
+
+    #[test]
+    fn test_parse_known_hosts_line() {
+        let parsed = parse_known_hosts_line("github.com ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl comment here").unwrap();
+        assert_eq!(parsed.host, "github.com");
+        assert_eq!(parsed.key_type, "ssh-ed25519");
+        assert_eq!(parsed.comment.as_deref(), Some("comment here"));
+        assert_eq!(parsed.fingerprint, "SHA256:+DiY3wvvV6TuJJhbpZisF/zLDA0zPMSvHdkr4UvCOqU");
+
+        let ca = parse_known_hosts_line("@cert-authority *.example.com ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl").unwrap();
+        assert_eq!(ca.host, "*.example.com");
+        assert_eq!(ca.marker.as_deref(), Some(CERT_AUTHORITY));
+        assert!(parse_known_hosts_line("@unknown * ssh-rsa AAAA").is_none());
+        assert!(parse_known_hosts_line("broken line").is_none());
+    }
+}
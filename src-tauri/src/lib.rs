@@ -1,6 +1,12 @@
 use tauri::Manager;
 
 mod ssh;
+mod transport;
+mod routing;
+mod forwarding;
+mod socks;
+mod ssh_config;
+mod inventory;
 mod local_term;
 mod sftp;
 mod monitor;
@@ -8,6 +14,8 @@ mod local_files;
 mod local_monitor;
 mod ssh_utils;
 mod db;
+mod migrations;
+mod backup;
 mod models;
 mod repositories;
 mod db_commands;
@@ -15,16 +23,39 @@ mod ai_service;
 mod ssh_test;
 mod session_state;
 mod security;
+mod lock;
+mod vault;
+mod prompts;
+mod known_hosts;
+mod keys;
+mod ppk;
+mod agent;
+mod agent_forward;
+mod certificates;
+mod recording;
+mod session_log;
+mod exec;
+mod fanout;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
   tauri::Builder::default()
     .manage(ssh::SshState::new())
     .manage(local_term::LocalState::new())
-    .manage(sftp::SftpState::new())
-    .manage(monitor::MonitorState::new())
+    .manage(prompts::PromptState::new())
+    .manage(routing::HopPool::new())
+    .manage(forwarding::ForwardState::new())
+    .manage(lock::LockState::new())
+    .manage(vault::VaultState::new())
+    .manage(agent::AgentState::new())
+    .manage(recording::RecordingState::new())
+    .manage(session_log::SessionLogState::new())
+    .manage(exec::ExecState::new())
+    .manage(fanout::FanoutState::new())
+    .manage(certificates::HostCertificateCache::new())
     .plugin(tauri_plugin_dialog::init())
-    .invoke_handler(tauri::generate_handler![
+    // Every call from the UI counts as activity for the auto-lock timer
+    .invoke_handler(lock::track_activity(tauri::generate_handler![
         ssh::connect_ssh,
         ssh::write_ssh,
         ssh::resize_ssh,
@@ -32,6 +63,10 @@ pub fn run() {
         ssh::read_remote_file,
         ssh::write_remote_file,
         ssh::duplicate_session,
+        forwarding::start_port_forward,
+        forwarding::stop_port_forward,
+        forwarding::get_port_forwards,
+        forwarding::get_port_forward_connections,
         local_term::connect_local,
         local_term::write_local,
         local_term::resize_local,
@@ -50,6 +85,7 @@ pub fn run() {
         db_commands::delete_server,
         db_commands::save_command_log,
         db_commands::get_command_history,
+        db_commands::get_migration_status,
         db_commands::get_app_settings,
         db_commands::save_app_settings,
         repositories::snippets::get_all_snippets,
@@ -62,16 +98,71 @@ pub fn run() {
         session_state::load_session_state,
         session_state::clear_session_state,
         security::is_master_password_set,
-        security::set_master_password,
         security::verify_master_password,
         security::change_master_password,
         security::unlock_app,
         security::setup_encryption,
-    ])
+        lock::lock_app,
+        vault::confirm_master_password,
+        vault::reveal_credential,
+        vault::copy_credential,
+        backup::create_backup,
+        backup::inspect_backup,
+        backup::restore_backup,
+        backup::get_backup_schedule,
+        backup::set_backup_schedule,
+        inventory::preview_ssh_config_import,
+        inventory::import_ssh_config,
+        inventory::export_servers,
+        known_hosts::get_known_hosts,
+        known_hosts::delete_known_host,
+        known_hosts::import_system_known_hosts,
+        known_hosts::respond_host_key_prompt,
+        keys::get_keys,
+        keys::generate_key,
+        keys::import_key,
+        keys::change_key_passphrase,
+        keys::export_public_key,
+        keys::delete_key,
+        keys::deploy_key,
+        agent::get_agent_status,
+        agent::agent_add_key,
+        agent::agent_remove_key,
+        agent::agent_remove_all,
+        agent::respond_agent_confirm,
+        certificates::get_server_certificate,
+        certificates::get_key_certificate,
+        certificates::set_key_certificate,
+        certificates::add_cert_authority,
+        recording::start_recording,
+        recording::stop_recording,
+        recording::get_recording_status,
+        recording::list_recordings,
+        recording::delete_recording,
+        recording::play_recording,
+        recording::set_playback_speed,
+        recording::pause_playback,
+        recording::stop_playback,
+        exec::exec_command,
+        exec::cancel_exec,
+        fanout::preview_fanout_targets,
+        fanout::start_fanout,
+        fanout::cancel_fanout,
+        fanout::list_fanout_runs,
+        fanout::get_fanout_run,
+        fanout::delete_fanout_run,
+        session_log::get_session_log_settings,
+        session_log::set_session_log_settings,
+        ssh_utils::respond_passphrase_prompt,
+        ssh_utils::respond_keyboard_interactive,
+    ]))
     .setup(|app| {
       // Initialize database
       let db = db::Database::new(app.handle()).expect("Failed to initialize database");
       app.manage(db);
+      backup::start_scheduler(app.handle().clone());
+      lock::start_idle_timer(app.handle().clone());
+      agent::start(app.handle().clone());
 
       if cfg!(debug_assertions) {
         app.handle().plugin(
@@ -2,7 +2,12 @@ use portable_pty::{CommandBuilder, native_pty_system, PtySize};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::io::{Read, Write};
-use tauri::{Emitter, Window};
+use tauri::{Emitter, Manager, Window};
+use crate::agent::AgentState;
+use crate::db::Database;
+use crate::recording::RecordingState;
+use crate::session_log::SessionLogState;
+use crate::repositories::settings;
 
 pub struct LocalState {
     pub sessions: Arc<Mutex<std::collections::HashMap<String, LocalSession>>>,
@@ -14,12 +19,18 @@ impl LocalState {
             sessions: Arc::new(Mutex::new(std::collections::HashMap::new())),
         }
     }
+
+    /// Last terminal size of a session
+    pub fn size(&self, id: &str) -> Option<(u16, u16)> {
+        self.sessions.lock().unwrap().get(id).map(|s| s.size)
+    }
 }
 
 pub struct LocalSession {
     writer: Box<dyn Write + Send>,
     master: Box<dyn portable_pty::MasterPty + Send>,
     child: Box<dyn portable_pty::Child + Send>,
+    size: (u16, u16),
 }
 
 #[tauri::command]
@@ -49,12 +60,22 @@ pub fn connect_local(
     if let Ok(pwd) = std::env::current_dir() {
         cmd.env("PWD", pwd);
     }
+    let agent = window.state::<AgentState>();
+    let use_agent = settings::get_setting(&window.state::<Database>(), "agent_for_local_terminals")
+        .ok()
+        .flatten()
+        .is_some_and(|v| v == "true");
+    if let Some(socket) = agent.socket_path().filter(|_| use_agent) {
+        cmd.env("SSH_AUTH_SOCK", socket);
+    }
     
     let child = pair.slave.spawn_command(cmd).map_err(|e| e.to_string())?;
 
     let mut reader = pair.master.try_clone_reader().map_err(|e| e.to_string())?;
     let writer = pair.master.take_writer().map_err(|e| e.to_string())?;
 
+    window.state::<SessionLogState>().open(window.app_handle(), &id, "local", "localhost");
+
     let id_clone = id.clone();
     thread::spawn(move || {
         let mut buf = [0u8; 1024];
@@ -62,6 +83,8 @@ pub fn connect_local(
             match reader.read(&mut buf) {
                 Ok(n) if n > 0 => {
                     let data = buf[0..n].to_vec();
+                    window.state::<RecordingState>().output(&id_clone, &data);
+                    window.state::<SessionLogState>().output(&id_clone, &data);
                     let _ = window.emit(&format!("local_data_{}", id_clone), data);
                 }
                 Ok(_) => {
@@ -74,6 +97,8 @@ pub fn connect_local(
                 }
             }
         }
+        window.state::<RecordingState>().finish(window.app_handle(), &id_clone);
+        window.state::<SessionLogState>().close(&id_clone);
         let _ = window.emit(&format!("local_close_{}", id_clone), ());
     });
 
@@ -81,6 +106,7 @@ pub fn connect_local(
         writer,
         master: pair.master,
         child,
+        size: (cols, rows),
     };
 
     state.sessions.lock().unwrap().insert(id, session);
@@ -91,10 +117,12 @@ pub fn connect_local(
 #[tauri::command]
 pub fn write_local(
     state: tauri::State<'_, LocalState>,
+    recordings: tauri::State<'_, RecordingState>,
     id: String,
     data: String,
 ) -> Result<(), String> {
     if let Some(session) = state.sessions.lock().unwrap().get_mut(&id) {
+        recordings.input(&id, data.as_bytes());
         let _ = session.writer.write_all(data.as_bytes());
     }
     Ok(())
@@ -103,11 +131,14 @@ pub fn write_local(
 #[tauri::command]
 pub fn resize_local(
     state: tauri::State<'_, LocalState>,
+    recordings: tauri::State<'_, RecordingState>,
     id: String,
     cols: u16,
     rows: u16,
 ) -> Result<(), String> {
     if let Some(session) = state.sessions.lock().unwrap().get_mut(&id) {
+        session.size = (cols, rows);
+        recordings.resize(&id, cols as u32, rows as u32);
         let _ = session.master.resize(PtySize {
             rows,
             cols,
@@ -0,0 +1,108 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::ipc::Invoke;
+use tauri::{AppHandle, Emitter, Manager};
+use crate::agent::AgentState;
+use crate::db::Database;
+use crate::repositories::settings;
+use crate::ssh::SshState;
+use crate::vault::VaultState;
+
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+// Commands the UI calls on a timer; they don't mean the user is present
+const PASSIVE_COMMANDS: &[&str] = &[
+    "get_system_stats",
+    "get_local_system_stats",
+    "get_port_forwards",
+    "get_port_forward_connections",
+    "save_session_state",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LockEvent {
+    pub reason: String, // "idle" or "manual"
+}
+
+/// Time of the last user-driven IPC call
+pub struct LockState {
+    last_activity: Mutex<Instant>,
+}
+
+impl LockState {
+    pub fn new() -> Self {
+        Self {
+            last_activity: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Note an IPC call, unless it is a background poll
+    pub fn record(&self, command: &str) {
+        if !PASSIVE_COMMANDS.contains(&command) {
+            *self.last_activity.lock().unwrap() = Instant::now();
+        }
+    }
+
+    fn idle_for(&self) -> Duration {
+        self.last_activity.lock().unwrap().elapsed()
+    }
+}
+
+/// Wrap the command handler so every call records activity
+pub fn track_activity<F>(handler: F) -> impl Fn(Invoke) -> bool + Send + Sync + 'static
+where
+    F: Fn(Invoke) -> bool + Send + Sync + 'static,
+{
+    move |invoke| {
+        let app = invoke.message.webview_ref().app_handle();
+        app.state::<LockState>().record(invoke.message.command());
+        handler(invoke)
+    }
+}
+
+/// Seal the app: close the database, empty the agent, scrub credentials
+/// kept for open sessions and tell the UI to show the lock screen. Sessions stay
+/// connected, but anything that needs the database fails until unlock.
+pub fn lock(app: &AppHandle, reason: &str) {
+    app.state::<Database>().lock();
+    app.state::<VaultState>().forget();
+    app.state::<AgentState>().clear();
+
+    if let Ok(mut sessions) = app.state::<SshState>().sessions.lock() {
+        for connection in sessions.values_mut() {
+            connection.credentials.wipe();
+        }
+    }
+
+    println!("App locked ({})", reason);
+    let _ = app.emit("app_locked", LockEvent { reason: reason.to_string() });
+}
+
+/// Lock the app now
+#[tauri::command]
+pub fn lock_app(app: AppHandle) {
+    lock(&app, "manual");
+}
+
+/// Background task enforcing `lock_timeout` (minutes, 0 disables it)
+pub fn start_idle_timer(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(IDLE_CHECK_INTERVAL).await;
+
+            let db = app.state::<Database>();
+            if !db.is_unlocked() {
+                continue;
+            }
+            let timeout = settings::get_setting(&db, "lock_timeout")
+                .ok()
+                .flatten()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(0);
+            if timeout > 0 && app.state::<LockState>().idle_for() >= Duration::from_secs(timeout * 60) {
+                lock(&app, "idle");
+            }
+        }
+    });
+}
@@ -0,0 +1,471 @@
+use rusqlite::{Connection, OptionalExtension, Result};
+use serde::Serialize;
+
+/// One schema change. `version` is the `user_version` a database has once
+/// the migration is applied.
+pub struct Migration {
+    pub version: i32,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+/// Every schema change in order. Never edit a released entry; append a new
+/// one instead.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Initial schema",
+        sql: "
+            CREATE TABLE IF NOT EXISTS servers (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                host TEXT NOT NULL,
+                port INTEGER DEFAULT 22,
+                username TEXT NOT NULL,
+                password TEXT,
+                private_key_path TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS command_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                server_id INTEGER,
+                command TEXT NOT NULL,
+                executed_at INTEGER NOT NULL,
+                FOREIGN KEY (server_id) REFERENCES servers(id) ON DELETE CASCADE
+            );
+            CREATE INDEX IF NOT EXISTS idx_history_server_time
+                ON command_history(server_id, executed_at DESC);
+            CREATE TABLE IF NOT EXISTS snippets (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                command TEXT NOT NULL,
+                category TEXT NOT NULL,
+                description TEXT,
+                created_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+        ",
+    },
+    Migration {
+        version: 2,
+        description: "Server groups, tags and colors",
+        sql: "
+            ALTER TABLE servers ADD COLUMN server_group TEXT;
+            ALTER TABLE servers ADD COLUMN tags TEXT;
+            ALTER TABLE servers ADD COLUMN color TEXT;
+        ",
+    },
+    Migration {
+        version: 3,
+        description: "Port forwarding rules",
+        sql: "ALTER TABLE servers ADD COLUMN forwarding_rules TEXT;",
+    },
+    Migration {
+        version: 4,
+        description: "Jump hosts",
+        sql: "ALTER TABLE servers ADD COLUMN jump_host_id INTEGER;",
+    },
+    Migration {
+        version: 5,
+        description: "Managed known_hosts",
+        sql: "
+            CREATE TABLE IF NOT EXISTS known_hosts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                host TEXT NOT NULL,
+                key_type TEXT NOT NULL,
+                public_key TEXT NOT NULL,
+                fingerprint TEXT NOT NULL,
+                comment TEXT,
+                created_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_known_hosts_host ON known_hosts(host);
+        ",
+    },
+    Migration {
+        version: 6,
+        description: "Remembered key passphrases",
+        sql: "ALTER TABLE servers ADD COLUMN key_passphrase TEXT;",
+    },
+    Migration {
+        version: 7,
+        description: "Credential vault",
+        // Secrets move out of the servers table; each server with a secret
+        // gets a credential with the same id
+        sql: "
+            CREATE TABLE IF NOT EXISTS credentials (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                password TEXT,
+                key_passphrase TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            ALTER TABLE servers ADD COLUMN credential_id INTEGER;
+            INSERT INTO credentials (id, password, key_passphrase, created_at, updated_at)
+                SELECT id, password, key_passphrase, updated_at, updated_at FROM servers
+                WHERE password IS NOT NULL OR key_passphrase IS NOT NULL;
+            UPDATE servers SET credential_id = id
+                WHERE password IS NOT NULL OR key_passphrase IS NOT NULL;
+            UPDATE servers SET password = NULL, key_passphrase = NULL;
+        ",
+    },
+    Migration {
+        version: 8,
+        description: "SSH key store",
+        sql: "
+            CREATE TABLE IF NOT EXISTS ssh_keys (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                key_type TEXT NOT NULL,
+                bits INTEGER,
+                fingerprint TEXT NOT NULL,
+                public_key TEXT NOT NULL,
+                private_key TEXT NOT NULL,
+                encrypted INTEGER NOT NULL DEFAULT 0,
+                comment TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            ALTER TABLE servers ADD COLUMN key_id INTEGER;
+        ",
+    },
+    Migration {
+        version: 9,
+        description: "Agent forwarding toggle",
+        sql: "ALTER TABLE servers ADD COLUMN forward_agent INTEGER NOT NULL DEFAULT 0;",
+    },
+    Migration {
+        version: 10,
+        description: "OpenSSH certificates",
+        sql: "
+            ALTER TABLE ssh_keys ADD COLUMN certificate TEXT;
+            ALTER TABLE known_hosts ADD COLUMN marker TEXT;
+        ",
+    },
+    Migration {
+        version: 11,
+        description: "Session recording policy",
+        sql: "ALTER TABLE servers ADD COLUMN record_sessions INTEGER NOT NULL DEFAULT 0;",
+    },
+    Migration {
+        version: 12,
+        description: "Fan-out command runs",
+        sql: "
+            CREATE TABLE IF NOT EXISTS fanout_runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                command TEXT NOT NULL,
+                selector TEXT NOT NULL,
+                parallelism INTEGER NOT NULL,
+                timeout_secs INTEGER,
+                total INTEGER NOT NULL,
+                started_at INTEGER NOT NULL,
+                finished_at INTEGER
+            );
+            CREATE TABLE IF NOT EXISTS fanout_results (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                run_id INTEGER NOT NULL,
+                server_id INTEGER NOT NULL,
+                server_name TEXT NOT NULL,
+                host TEXT NOT NULL,
+                exit_status INTEGER,
+                exit_signal TEXT,
+                error TEXT,
+                timed_out INTEGER NOT NULL DEFAULT 0,
+                cancelled INTEGER NOT NULL DEFAULT 0,
+                duration_ms INTEGER NOT NULL,
+                output TEXT NOT NULL,
+                truncated INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE INDEX IF NOT EXISTS idx_fanout_results_run ON fanout_results(run_id);
+        ",
+    },
+];
+
+/// Schema version of a fully migrated database
+pub const SCHEMA_VERSION: i32 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+// Databases created before the registry existed were always stamped 1,
+// whatever columns the ad-hoc ALTERs had managed to add
+const LEGACY_VERSION: i32 = 1;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationStep {
+    pub version: i32,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationFailure {
+    pub version: i32,
+    pub description: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationReport {
+    pub from_version: i32,
+    pub to_version: i32,
+    pub dry_run: bool,
+    pub applied: Vec<MigrationStep>, // With dry_run: the steps that would apply
+    pub failed: Option<MigrationFailure>,
+}
+
+impl MigrationReport {
+    /// Turn a failed run into an error message
+    pub fn into_result(self) -> std::result::Result<Self, String> {
+        match &self.failed {
+            Some(failure) => Err(format!(
+                "Database migration {} ({}) failed: {}",
+                failure.version, failure.description, failure.error
+            )),
+            None => Ok(self),
+        }
+    }
+}
+
+fn table_exists(conn: &Connection, table: &str) -> Result<bool> {
+    conn.query_row(
+        "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        [table],
+        |_| Ok(()),
+    )
+    .optional()
+    .map(|row| row.is_some())
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in names {
+        if name? == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// The schema version `conn` is at, working it out from the tables for
+/// databases that predate versioning
+pub fn current_version(conn: &Connection) -> Result<i32> {
+    let version: i32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version > LEGACY_VERSION {
+        return Ok(version);
+    }
+    if !table_exists(conn, "servers")? {
+        return Ok(0);
+    }
+
+    // Only schemas from before the registry need inferring: every later
+    // table comes from a migration that also sets user_version
+    let inferred = if has_column(conn, "servers", "key_passphrase")? {
+        6
+    } else if table_exists(conn, "known_hosts")? {
+        5
+    } else if has_column(conn, "servers", "jump_host_id")? {
+        4
+    } else if has_column(conn, "servers", "forwarding_rules")? {
+        3
+    } else if has_column(conn, "servers", "color")? {
+        2
+    } else {
+        1
+    };
+    Ok(inferred)
+}
+
+/// Bring `conn` up to date, one transaction per migration. With `dry_run`
+/// every pending migration is still executed but rolled back, so the report
+/// shows whether the real run would succeed.
+pub fn migrate(conn: &Connection, dry_run: bool) -> Result<MigrationReport> {
+    run_migrations(conn, MIGRATIONS, dry_run)
+}
+
+fn run_migrations(conn: &Connection, migrations: &[Migration], dry_run: bool) -> Result<MigrationReport> {
+    let from_version = current_version(conn)?;
+    let latest = migrations.last().map(|m| m.version).unwrap_or(0);
+    let mut report = MigrationReport {
+        from_version,
+        to_version: from_version,
+        dry_run,
+        applied: Vec::new(),
+        failed: None,
+    };
+
+    if from_version > latest {
+        report.failed = Some(MigrationFailure {
+            version: from_version,
+            description: "Unknown schema version".to_string(),
+            error: format!("Database was created by a newer version of the app (schema {})", from_version),
+        });
+        return Ok(report);
+    }
+
+    // A dry run has to apply earlier steps for later ones to make sense, so
+    // everything happens inside one transaction that is rolled back
+    let dry_run_tx = if dry_run { Some(conn.unchecked_transaction()?) } else { None };
+
+    for migration in migrations.iter().filter(|m| m.version > from_version) {
+        let result = if dry_run {
+            conn.execute_batch(migration.sql)
+        } else {
+            conn.unchecked_transaction().and_then(|tx| {
+                tx.execute_batch(migration.sql)?;
+                tx.pragma_update(None, "user_version", migration.version)?;
+                tx.commit()
+            })
+        };
+
+        if let Err(e) = result {
+            eprintln!("Migration {} ({}) failed: {}", migration.version, migration.description, e);
+            report.failed = Some(MigrationFailure {
+                version: migration.version,
+                description: migration.description.to_string(),
+                error: e.to_string(),
+            });
+            break;
+        }
+
+        if !dry_run {
+            println!("Applied migration {} ({})", migration.version, migration.description);
+        }
+        report.to_version = migration.version;
+        report.applied.push(MigrationStep {
+            version: migration.version,
+            description: migration.description.to_string(),
+        });
+    }
+
+    if let Some(tx) = dry_run_tx {
+        tx.rollback()?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A database as it looked at `version`, built from the registry itself
+    fn database_at(version: i32) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        for migration in MIGRATIONS.iter().filter(|m| m.version <= version) {
+            conn.execute_batch(migration.sql).unwrap();
+        }
+        conn.pragma_update(None, "user_version", version).unwrap();
+        conn
+    }
+
+    fn schema(conn: &Connection) -> Vec<(String, String)> {
+        let mut stmt = conn
+            .prepare("SELECT m.name, p.name FROM sqlite_master m, pragma_table_info(m.name) p WHERE m.type = 'table' ORDER BY 1, 2")
+            .unwrap();
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+        rows.map(|r| r.unwrap()).collect()
+    }
+
+    #[test]
+    fn test_versions_are_sequential() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, i as i32 + 1, "{}", migration.description);
+        }
+    }
+
+    #[test]
+    fn test_migrates_every_historical_version() {
+        let expected = schema(&database_at(SCHEMA_VERSION));
+
+        for version in 0..=SCHEMA_VERSION {
+            let conn = database_at(version);
+            assert_eq!(current_version(&conn).unwrap(), version);
+
+            let report = migrate(&conn, false).unwrap().into_result().unwrap();
+            assert_eq!(report.from_version, version);
+            assert_eq!(report.to_version, SCHEMA_VERSION);
+            assert_eq!(report.applied.len() as i32, SCHEMA_VERSION - version);
+            assert_eq!(current_version(&conn).unwrap(), SCHEMA_VERSION);
+            assert_eq!(schema(&conn), expected, "migrating from version {}", version);
+        }
+    }
+
+    #[test]
+    fn test_infers_version_of_unversioned_database() {
+        // What the old init_schema left behind: columns up to forwarding
+        // rules, stamped with user_version 1
+        let conn = database_at(3);
+        conn.pragma_update(None, "user_version", 1).unwrap();
+        assert_eq!(current_version(&conn).unwrap(), 3);
+
+        let report = migrate(&conn, false).unwrap();
+        assert!(report.failed.is_none());
+        assert_eq!(report.applied[0].version, 4);
+    }
+
+    #[test]
+    fn test_secrets_move_to_credentials() {
+        let conn = database_at(6);
+        conn.execute_batch(
+            "INSERT INTO servers (name, host, username, password, created_at, updated_at)
+                VALUES ('a', 'a', 'root', 'secret', 0, 0);
+             INSERT INTO servers (name, host, username, created_at, updated_at)
+                VALUES ('b', 'b', 'root', 0, 0);",
+        )
+        .unwrap();
+
+        migrate(&conn, false).unwrap().into_result().unwrap();
+
+        let (password, credential_id): (Option<String>, Option<i64>) = conn
+            .query_row("SELECT password, credential_id FROM servers WHERE name = 'a'", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(password, None);
+        let stored: String = conn
+            .query_row("SELECT password FROM credentials WHERE id = ?1", [credential_id.unwrap()], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stored, "secret");
+
+        let other: Option<i64> = conn
+            .query_row("SELECT credential_id FROM servers WHERE name = 'b'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(other, None);
+    }
+
+    #[test]
+    fn test_dry_run_changes_nothing() {
+        let conn = database_at(2);
+        let before = schema(&conn);
+
+        let report = migrate(&conn, true).unwrap();
+        assert!(report.failed.is_none());
+        assert_eq!(report.to_version, SCHEMA_VERSION);
+        assert_eq!(schema(&conn), before);
+        assert_eq!(current_version(&conn).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_failed_migration_is_rolled_back_and_reported() {
+        let broken = [
+            Migration { version: 1, description: "Create", sql: "CREATE TABLE t (a TEXT);" },
+            Migration {
+                version: 2,
+                description: "Broken",
+                sql: "ALTER TABLE t ADD COLUMN b TEXT; ALTER TABLE missing ADD COLUMN c TEXT;",
+            },
+        ];
+        let conn = Connection::open_in_memory().unwrap();
+
+        let report = run_migrations(&conn, &broken, false).unwrap();
+        assert_eq!(report.to_version, 1);
+        let failure = report.failed.clone().unwrap();
+        assert_eq!(failure.version, 2);
+        assert!(report.into_result().unwrap_err().contains("Broken"));
+
+        // The half-applied step left no trace
+        let user_version: i32 = conn.pragma_query_value(None, "user_version", |row| row.get(0)).unwrap();
+        assert_eq!(user_version, 1);
+        assert!(!has_column(&conn, "t", "b").unwrap());
+    }
+}
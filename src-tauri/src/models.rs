@@ -7,6 +7,8 @@ pub struct PortForwardingRule {
     pub source_port: u16,
     pub destination_host: Option<String>,
     pub destination_port: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bind_address: Option<String>, // Listening address; loopback when unset
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,10 +18,26 @@ pub struct Server {
     pub host: String,
     pub port: i32,
     pub username: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    // Secrets are write-only: they go into the credential vault on save and
+    // are never sent back to the frontend
+    #[serde(default, skip_serializing)]
     pub password: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub private_key_path: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub key_passphrase: Option<String>, // Also stored when "remember" is chosen at the prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_id: Option<i64>,
+    #[serde(default)]
+    pub has_password: bool,
+    #[serde(default)]
+    pub has_key_passphrase: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<i64>, // Key from the key store, used when no key path is set
+    #[serde(default)]
+    pub forward_agent: bool, // Expose our agent to the server (ssh -A)
+    #[serde(default)]
+    pub record_sessions: bool, // Record every session to an asciicast file
     #[serde(skip_serializing_if = "Option::is_none")]
     pub group: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -34,6 +52,31 @@ pub struct Server {
     pub updated_at: i64,
 }
 
+/// Secrets of one vault entry. Deliberately not serializable.
+#[derive(Debug, Clone)]
+pub struct Credential {
+    pub password: Option<String>,
+    pub key_passphrase: Option<String>,
+}
+
+/// A key in the key store. The private half only leaves the database to
+/// authenticate or to be re-encrypted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshKey {
+    pub id: Option<i64>,
+    pub name: String,
+    pub key_type: String, // "ssh-ed25519", "ssh-rsa", "ecdsa-sha2-nistp256", ...
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bits: Option<i32>,
+    pub fingerprint: String, // "SHA256:..."
+    pub public_key: String, // authorized_keys line
+    pub encrypted: bool, // Protected by a passphrase
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommandHistory {
     pub id: Option<i64>,
@@ -42,6 +85,77 @@ pub struct CommandHistory {
     pub executed_at: i64,
 }
 
+/// Saved servers a fan-out run targets. Every criterion given must match;
+/// tags match when the server has any of them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerSelector {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub server_ids: Vec<i64>,
+}
+
+impl ServerSelector {
+    pub fn is_empty(&self) -> bool {
+        self.group.is_none() && self.tags.is_empty() && self.server_ids.is_empty()
+    }
+
+    pub fn matches(&self, server: &Server) -> bool {
+        let group = self.group.as_ref().map_or(true, |g| server.group.as_ref() == Some(g));
+        let tags = self.tags.is_empty()
+            || server.tags.as_ref().is_some_and(|tags| tags.iter().any(|t| self.tags.contains(t)));
+        let ids = self.server_ids.is_empty() || server.id.is_some_and(|id| self.server_ids.contains(&id));
+        group && tags && ids
+    }
+}
+
+/// A persisted fan-out run, with its outcome counts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FanoutRun {
+    pub id: Option<i64>,
+    pub command: String,
+    pub selector: ServerSelector,
+    pub parallelism: u32,
+    pub timeout_secs: Option<u64>,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub total: u32,
+    pub succeeded: u32,
+    pub failed: u32,
+}
+
+/// How the command went on one host of a fan-out run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FanoutHostResult {
+    pub server_id: i64,
+    pub server_name: String,
+    pub host: String,
+    pub exit_status: Option<i32>,
+    pub exit_signal: Option<String>,
+    pub error: Option<String>, // Connection or channel failure
+    pub timed_out: bool,
+    pub cancelled: bool,
+    pub duration_ms: u64,
+    pub output: String, // stdout and stderr interleaved, truncated
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnownHost {
+    pub id: Option<i64>,
+    pub host: String, // OpenSSH host pattern: "host", "[host]:port" or hashed "|1|salt|hash"
+    pub key_type: String,
+    pub public_key: String, // Base64 key blob
+    pub fingerprint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub marker: Option<String>, // "cert-authority" for CA keys trusted to sign host certificates
+    pub created_at: i64,
+}
+
 
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,6 +169,8 @@ pub struct AppSettings {
     pub ai_base_url: Option<String>,
     pub auto_reconnect: bool,
     pub lock_timeout: i32, // Minutes, 0 to disable
+    #[serde(default)]
+    pub agent_for_local_terminals: bool, // Export SSH_AUTH_SOCK of the built-in agent
 }
 
 impl Default for AppSettings {
@@ -69,6 +185,7 @@ impl Default for AppSettings {
             ai_base_url: None,
             auto_reconnect: true,
             lock_timeout: 0, // Default: disabled (0 = no auto-lock)
+            agent_for_local_terminals: false,
         }
     }
 }
@@ -1,21 +1,7 @@
-use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
-use std::io::Read;
-use ssh2::Session;
+use std::time::Duration;
 use serde::Serialize;
 use crate::ssh::SshState;
-
-pub struct MonitorState {
-    pub sessions: Arc<Mutex<HashMap<String, Session>>>,
-}
-
-impl MonitorState {
-    pub fn new() -> Self {
-        Self {
-            sessions: Arc::new(Mutex::new(HashMap::new())),
-        }
-    }
-}
+use crate::transport::SshTransport;
 
 #[derive(Serialize)]
 pub struct SystemStats {
@@ -45,106 +31,34 @@ pub struct ProcessInfo {
     command: String,
 }
 
-fn run_command(sess: &Session, cmd: &str) -> Result<String, String> {
-    let mut channel = sess.channel_session().map_err(|e| e.to_string())?;
-    channel.exec(cmd).map_err(|e| e.to_string())?;
-    
+// Helper to run command safely
+async fn run_safe(transport: &SshTransport, cmd: &str) -> String {
     // Read with timeout to prevent deadlock
-    let mut s = String::new();
-    let mut buf = [0u8; 8192];
-    let start = std::time::Instant::now();
-    let timeout = std::time::Duration::from_secs(5);
-    
-    loop {
-        if start.elapsed() > timeout {
-            break;
-        }
-        
-        match channel.read(&mut buf) {
-            Ok(0) => break, // EOF
-            Ok(n) => s.push_str(&String::from_utf8_lossy(&buf[0..n])),
-            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                std::thread::sleep(std::time::Duration::from_millis(10));
-                continue;
+    match transport.exec(cmd, Duration::from_secs(5)).await {
+        Ok(output) => {
+            println!("DEBUG: Command executed. Output length: {}", output.len());
+            if output.is_empty() {
+                println!("DEBUG: Output is empty!");
             }
-            Err(_) => break,
+            output
+        },
+        Err(e) => {
+            println!("Command failed: {} - Error: {}", cmd.lines().next().unwrap_or(""), e);
+            String::new()
         }
     }
-    
-    let _ = channel.wait_close();
-    Ok(s)
 }
 
 #[tauri::command]
-pub fn get_system_stats(
+pub async fn get_system_stats(
     ssh_state: tauri::State<'_, SshState>,
-    monitor_state: tauri::State<'_, MonitorState>,
     id: String,
 ) -> Result<SystemStats, String> {
-    let mut sessions = monitor_state.sessions.lock().unwrap();
-    
-    // Ensure session exists (same connection logic as before)
-    if !sessions.contains_key(&id) {
-        let (host, port, username, password, private_key) = {
-            let ssh_sessions = ssh_state.sessions.lock().unwrap();
-            let conn = ssh_sessions.get(&id).ok_or("SSH session not found")?;
-            (
-                conn.host.clone(),
-                conn.port,
-                conn.username.clone(),
-                conn.password.clone(),
-                conn.private_key.clone(),
-            )
-        };
-
-        // Create TCP connection with timeout
-        let tcp = std::net::TcpStream::connect_timeout(
-            &format!("{}:{}", host, port).parse().map_err(|e| format!("Invalid address: {}", e))?,
-            std::time::Duration::from_secs(5)
-        ).map_err(|e| format!("TCP connection failed: {}", e))?;
-        
-        tcp.set_read_timeout(Some(std::time::Duration::from_secs(5))).ok();
-        tcp.set_write_timeout(Some(std::time::Duration::from_secs(5))).ok();
-        
-        let mut sess = Session::new().unwrap();
-        sess.set_tcp_stream(tcp);
-        sess.set_timeout(5000);
-        sess.handshake().map_err(|e| format!("SSH handshake failed: {}", e))?;
-
-        if let Some(pwd) = password {
-            sess.userauth_password(&username, &pwd).map_err(|e| e.to_string())?;
-        } else if let Some(_key) = private_key {
-            sess.userauth_agent(&username).map_err(|e| e.to_string())?;
-        } else {
-            return Err("No authentication method provided".to_string());
-        }
-        
-        sessions.insert(id.clone(), sess);
-    }
-
-    let sess = sessions.get(&id).unwrap();
-
-    // Helper to run command safely
-    let run_safe = |cmd: &str| -> String {
-        match run_command(sess, cmd) {
-            Ok(output) => {
-                println!("DEBUG: Command executed. Output length: {}", output.len());
-                if output.is_empty() {
-                    println!("DEBUG: Output is empty!");
-                } else {
-                    // println!("DEBUG: Output start: {}", &output[..std::cmp::min(output.len(), 100)]);
-                }
-                output
-            },
-            Err(e) => {
-                println!("Command failed: {} - Error: {}", cmd.lines().next().unwrap_or(""), e);
-                String::new()
-            }
-        }
-    };
+    // Stats are gathered on exec channels of the session's own connection
+    let transport = ssh_state.transport(&id)?;
 
     // 1. Detect OS (Fast single command)
-    let uname = run_safe("uname -s");
+    let uname = run_safe(&transport, "uname -s").await;
     let is_mac = uname.contains("Darwin");
 
     // 2. Construct ONE big command to fetch everything
@@ -157,7 +71,7 @@ pub fn get_system_stats(
 
     let cmd = format!("(export TERM=xterm; {}) 2>&1", cmd_inner);
 
-    let output = run_safe(&cmd);
+    let output = run_safe(&transport, &cmd).await;
     
     // Parse the output
     let mut uptime = 0;
@@ -258,7 +172,7 @@ pub fn get_system_stats(
                 }
             },
             "PROC" => {
-                for line in content.lines().skip(0) {
+                for line in content.lines() {
                     let p: Vec<&str> = line.split_whitespace().collect();
                     if p.len() >= 11 {
                         processes.push(ProcessInfo {
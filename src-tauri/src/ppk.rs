@@ -0,0 +1,229 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use openssl::symm::{Cipher, Crypter, Mode};
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use std::collections::HashMap;
+use crate::keys::put_string;
+
+/// A PuTTY private key with its blobs in SSH wire format
+#[derive(Debug)]
+pub struct PpkKey {
+    pub algorithm: String,
+    pub comment: String,
+    pub public_blob: Vec<u8>,
+    pub private_blob: Vec<u8>,
+}
+
+pub fn is_ppk(content: &str) -> bool {
+    content.trim_start().starts_with("PuTTY-User-Key-File-")
+}
+
+struct PpkFile<'a> {
+    version: u32,
+    headers: HashMap<&'a str, &'a str>,
+    public_blob: Vec<u8>,
+    private_blob: Vec<u8>,
+}
+
+fn read_file(content: &str) -> Result<PpkFile<'_>, String> {
+    let mut lines = content.lines().map(str::trim_end);
+    let mut headers = HashMap::new();
+    let mut public_blob = None;
+    let mut private_blob = None;
+    let mut version = 0;
+
+    while let Some(line) = lines.next() {
+        if line.is_empty() {
+            continue;
+        }
+        let (name, value) = line.split_once(": ").ok_or_else(|| format!("Malformed PPK line: {}", line))?;
+        if let Some(v) = name.strip_prefix("PuTTY-User-Key-File-") {
+            version = v.parse().map_err(|_| format!("Unknown PPK version: {}", v))?;
+            headers.insert("Algorithm", value);
+            continue;
+        }
+
+        if name == "Public-Lines" || name == "Private-Lines" {
+            let count: usize = value.parse().map_err(|_| format!("Invalid {}: {}", name, value))?;
+            let body: String = lines.by_ref().take(count).collect();
+            let blob = STANDARD.decode(body).map_err(|e| format!("Invalid {} data: {}", name, e))?;
+            if name == "Public-Lines" {
+                public_blob = Some(blob);
+            } else {
+                private_blob = Some(blob);
+            }
+        } else {
+            headers.insert(name, value);
+        }
+    }
+
+    if version != 2 && version != 3 {
+        return Err(format!("Unsupported PPK version {} (only versions 2 and 3 are supported)", version));
+    }
+    Ok(PpkFile {
+        version,
+        headers,
+        public_blob: public_blob.ok_or("PPK file has no public key")?,
+        private_blob: private_blob.ok_or("PPK file has no private key")?,
+    })
+}
+
+fn header<'a>(file: &PpkFile<'a>, name: &str) -> Result<&'a str, String> {
+    file.headers.get(name).copied().ok_or_else(|| format!("PPK file is missing {}", name))
+}
+
+struct FileKeys {
+    cipher_key: Vec<u8>,
+    iv: Vec<u8>,
+    mac_key: Vec<u8>,
+}
+
+/// Cipher key, IV and MAC key for the file, per the PPK version
+fn derive_keys(file: &PpkFile, passphrase: &str) -> Result<FileKeys, String> {
+    if file.version == 2 {
+        let mut key = Vec::with_capacity(40);
+        for counter in 0u32..2 {
+            let mut hash = Sha1::new();
+            hash.update(counter.to_be_bytes());
+            hash.update(passphrase.as_bytes());
+            key.extend_from_slice(&hash.finalize());
+        }
+        key.truncate(32);
+
+        let mut hash = Sha1::new();
+        hash.update(b"putty-private-key-file-mac-key");
+        hash.update(passphrase.as_bytes());
+        return Ok(FileKeys {
+            cipher_key: key,
+            iv: vec![0; 16],
+            mac_key: hash.finalize().to_vec(),
+        });
+    }
+
+    let algorithm = match header(file, "Key-Derivation")? {
+        "Argon2d" => Algorithm::Argon2d,
+        "Argon2i" => Algorithm::Argon2i,
+        "Argon2id" => Algorithm::Argon2id,
+        other => return Err(format!("Unsupported PPK key derivation: {}", other)),
+    };
+    let number = |name: &str| -> Result<u32, String> {
+        header(file, name)?.parse().map_err(|_| format!("Invalid {}", name))
+    };
+    let salt = hex::decode(header(file, "Argon2-Salt")?).map_err(|e| e.to_string())?;
+    let params = Params::new(
+        number("Argon2-Memory")?,
+        number("Argon2-Passes")?,
+        number("Argon2-Parallelism")?,
+        Some(80),
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut output = [0u8; 80];
+    Argon2::new(algorithm, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut output)
+        .map_err(|e| e.to_string())?;
+    Ok(FileKeys {
+        cipher_key: output[..32].to_vec(),
+        iv: output[32..48].to_vec(),
+        mac_key: output[48..].to_vec(),
+    })
+}
+
+/// Parse a PuTTY `.ppk` file (versions 2 and 3), decrypting it with
+/// `passphrase` when it is protected and checking its MAC
+pub fn parse(content: &str, passphrase: Option<&str>) -> Result<PpkKey, String> {
+    let file = read_file(content)?;
+    let algorithm = header(&file, "Algorithm")?;
+    let encryption = header(&file, "Encryption")?;
+    let comment = file.headers.get("Comment").copied().unwrap_or("");
+
+    let (private_blob, mac_key) = match encryption {
+        "none" => {
+            let mac_key = if file.version == 2 { derive_keys(&file, "")?.mac_key } else { Vec::new() };
+            (file.private_blob.clone(), mac_key)
+        }
+        "aes256-cbc" => {
+            let passphrase = passphrase.filter(|p| !p.is_empty()).ok_or("Passphrase required")?;
+            let keys = derive_keys(&file, passphrase)?;
+            let mut crypter = Crypter::new(Cipher::aes_256_cbc(), Mode::Decrypt, &keys.cipher_key, Some(&keys.iv))
+                .map_err(|e| e.to_string())?;
+            crypter.pad(false);
+            let mut plain = vec![0; file.private_blob.len() + 16];
+            let mut len = crypter.update(&file.private_blob, &mut plain).map_err(|e| e.to_string())?;
+            len += crypter.finalize(&mut plain[len..]).map_err(|_| "Invalid PPK private key length")?;
+            plain.truncate(len);
+            (plain, keys.mac_key)
+        }
+        other => return Err(format!("Unsupported PPK encryption: {}", other)),
+    };
+
+    let mut mac_data = Vec::new();
+    put_string(&mut mac_data, algorithm.as_bytes());
+    put_string(&mut mac_data, encryption.as_bytes());
+    put_string(&mut mac_data, comment.as_bytes());
+    put_string(&mut mac_data, &file.public_blob);
+    put_string(&mut mac_data, &private_blob);
+
+    let expected = hex::decode(header(&file, "Private-MAC")?).map_err(|e| e.to_string())?;
+    let verified = if file.version == 2 {
+        let mut mac = Hmac::<Sha1>::new_from_slice(&mac_key).map_err(|e| e.to_string())?;
+        mac.update(&mac_data);
+        mac.verify_slice(&expected).is_ok()
+    } else {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&mac_key).map_err(|e| e.to_string())?;
+        mac.update(&mac_data);
+        mac.verify_slice(&expected).is_ok()
+    };
+    if !verified {
+        return Err(if encryption == "none" {
+            "PPK file is corrupt (MAC mismatch)".to_string()
+        } else {
+            "Incorrect passphrase".to_string()
+        });
+    }
+
+    Ok(PpkKey {
+        algorithm: algorithm.to_string(),
+        comment: comment.to_string(),
+        public_blob: file.public_blob,
+        private_blob,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ppk_v3(public_blob: &[u8], private_blob: &[u8]) -> String {
+        let mut mac_data = Vec::new();
+        put_string(&mut mac_data, b"ssh-ed25519");
+        put_string(&mut mac_data, b"none");
+        put_string(&mut mac_data, b"test");
+        put_string(&mut mac_data, public_blob);
+        put_string(&mut mac_data, private_blob);
+        let mut mac = Hmac::<Sha256>::new_from_slice(&[]).unwrap();
+        mac.update(&mac_data);
+
+        format!(
+            "PuTTY-User-Key-File-3: ssh-ed25519\nEncryption: none\nComment: test\nPublic-Lines: 1\n{}\nPrivate-Lines: 1\n{}\nPrivate-MAC: {}\n",
+            STANDARD.encode(public_blob),
+            STANDARD.encode(private_blob),
+            hex::encode(mac.finalize().into_bytes()),
+        )
+    }
+
+    #[test]
+    fn test_parse_unencrypted_v3() {
+        let key = parse(&ppk_v3(b"public", b"private"), None).unwrap();
+        assert_eq!(key.algorithm, "ssh-ed25519");
+        assert_eq!(key.comment, "test");
+        assert_eq!(key.public_blob, b"public");
+        assert_eq!(key.private_blob, b"private");
+
+        let tampered = ppk_v3(b"public", b"private").replace("Comment: test", "Comment: evil");
+        assert!(parse(&tampered, None).is_err());
+    }
+}
@@ -0,0 +1,72 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+// How long a backend operation waits for the user before giving up
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Answers the frontend can give to a prompt raised by the backend
+#[derive(Debug)]
+pub enum PromptAnswer {
+    HostKey(bool),
+    Passphrase { passphrase: Option<String>, remember: bool },
+    KeyboardInteractive(Option<Vec<String>>),
+    Confirm(bool),
+}
+
+/// Pending backend -> UI round trips, keyed by request id
+pub struct PromptState {
+    pending: Mutex<HashMap<String, Sender<PromptAnswer>>>,
+}
+
+impl PromptState {
+    pub fn new() -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Emit `event` with `payload` and block until the frontend answers
+    /// through the matching respond command (or the prompt times out).
+    pub fn ask<P: Serialize + Clone>(
+        &self,
+        app: &AppHandle,
+        event: &str,
+        request_id: &str,
+        payload: P,
+    ) -> Result<PromptAnswer, String> {
+        let (tx, rx) = channel();
+        self.pending.lock().unwrap().insert(request_id.to_string(), tx);
+
+        if let Err(e) = app.emit(event, payload) {
+            self.pending.lock().unwrap().remove(request_id);
+            return Err(format!("Failed to emit {}: {}", event, e));
+        }
+
+        let answer = rx.recv_timeout(PROMPT_TIMEOUT);
+        self.pending.lock().unwrap().remove(request_id);
+        answer.map_err(|_| "Timed out waiting for user response".to_string())
+    }
+
+    pub fn answer(&self, request_id: &str, answer: PromptAnswer) -> Result<(), String> {
+        let tx = self
+            .pending
+            .lock()
+            .unwrap()
+            .remove(request_id)
+            .ok_or("Prompt not found or already answered")?;
+        tx.send(answer).map_err(|_| "Prompt is no longer waiting".to_string())
+    }
+}
+
+/// Random identifier used to correlate a prompt event with its response
+pub fn new_request_id() -> String {
+    use rand::RngCore;
+
+    let mut bytes = [0u8; 16];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
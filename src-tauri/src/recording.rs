@@ -0,0 +1,492 @@
+use chrono::{Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{DirBuilder, File, OpenOptions};
+use std::io::{BufRead, BufReader, LineWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State, Window};
+use tokio::sync::watch;
+use crate::local_term::LocalState;
+use crate::ssh::SshState;
+
+const RECORDINGS_DIR: &str = "recordings";
+const MIN_SPEED: f64 = 0.1;
+const MAX_SPEED: f64 = 16.0;
+
+/// First line of an asciicast v2 file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CastHeader {
+    pub version: u32,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_time_limit: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<HashMap<String, String>>,
+}
+
+/// A recording in progress, emitted as `recording_status_{session_id}`
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingInfo {
+    pub session_id: String,
+    pub path: String,
+    pub started_at: i64,
+    pub record_input: bool,
+}
+
+/// A finished (or running) recording on disk
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingFile {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    pub modified: i64,
+    pub header: CastHeader,
+}
+
+struct CastWriter {
+    file: LineWriter<File>,
+    // Output split inside a UTF-8 sequence, completed by the next chunk
+    pending_output: Vec<u8>,
+    pending_input: Vec<u8>,
+}
+
+struct Recorder {
+    info: RecordingInfo,
+    started: Instant,
+    writer: Mutex<CastWriter>,
+}
+
+impl Recorder {
+    fn event(&self, code: &str, data: &str) {
+        let event = (self.started.elapsed().as_secs_f64(), code, data);
+        let line = serde_json::to_string(&event).unwrap_or_default();
+        let mut writer = self.writer.lock().unwrap();
+        // Each event is one line, so the line writer flushes it right away and
+        // a crash loses nothing already shown
+        if let Err(e) = writeln!(writer.file, "{}", line) {
+            eprintln!("Failed to write recording {}: {}", self.info.path, e);
+        }
+    }
+
+    fn output(&self, data: &[u8]) {
+        let text = decode(&mut self.writer.lock().unwrap().pending_output, data);
+        if !text.is_empty() {
+            self.event("o", &text);
+        }
+    }
+
+    fn input(&self, data: &[u8]) {
+        let text = decode(&mut self.writer.lock().unwrap().pending_input, data);
+        if !text.is_empty() {
+            self.event("i", &text);
+        }
+    }
+}
+
+/// Start of a multi-byte UTF-8 sequence cut off at the end of `bytes`, or
+/// its length when it ends on a character boundary
+fn incomplete_tail(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - back];
+        if byte & 0xC0 == 0x80 {
+            continue;
+        }
+        let needed = match byte {
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => 1,
+        };
+        return if needed > back { bytes.len() - back } else { bytes.len() };
+    }
+    bytes.len()
+}
+
+/// Text for the complete characters in `pending` + `data`; a trailing partial
+/// character stays in `pending`
+fn decode(pending: &mut Vec<u8>, data: &[u8]) -> String {
+    pending.extend_from_slice(data);
+    let complete = incomplete_tail(pending);
+    let text = String::from_utf8_lossy(&pending[..complete]).into_owned();
+    pending.drain(..complete);
+    text
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PlaybackControl {
+    speed: f64,
+    paused: bool,
+    stopped: bool,
+}
+
+/// One step of a playback, emitted as `playback_event_{playback_id}`
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaybackEvent {
+    pub time: f64,
+    pub kind: String, // "o" (output), "r" (resize, as "COLSxROWS") or "m" (marker)
+    pub data: String,
+}
+
+pub struct RecordingState {
+    recorders: Mutex<HashMap<String, Arc<Recorder>>>,
+    playbacks: Mutex<HashMap<String, watch::Sender<PlaybackControl>>>,
+}
+
+impl RecordingState {
+    pub fn new() -> Self {
+        Self {
+            recorders: Mutex::new(HashMap::new()),
+            playbacks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn recorder(&self, session_id: &str) -> Option<Arc<Recorder>> {
+        self.recorders.lock().unwrap().get(session_id).cloned()
+    }
+
+    /// Terminal output of a session, recorded if it is being recorded
+    pub fn output(&self, session_id: &str, data: &[u8]) {
+        if let Some(recorder) = self.recorder(session_id) {
+            recorder.output(data);
+        }
+    }
+
+    /// Keystrokes sent to a session; only kept by recordings that asked for input
+    pub fn input(&self, session_id: &str, data: &[u8]) {
+        if let Some(recorder) = self.recorder(session_id).filter(|r| r.info.record_input) {
+            recorder.input(data);
+        }
+    }
+
+    pub fn resize(&self, session_id: &str, cols: u32, rows: u32) {
+        if let Some(recorder) = self.recorder(session_id) {
+            recorder.event("r", &format!("{}x{}", cols, rows));
+        }
+    }
+
+    /// A labelled point in the recording, such as a reconnect
+    pub fn marker(&self, session_id: &str, label: &str) {
+        if let Some(recorder) = self.recorder(session_id) {
+            recorder.event("m", label);
+        }
+    }
+
+    /// Start recording a session into a new file in the recordings directory
+    pub fn start(
+        &self,
+        app: &AppHandle,
+        session_id: &str,
+        title: &str,
+        (width, height): (u32, u32),
+        record_input: bool,
+    ) -> Result<RecordingInfo, String> {
+        let mut recorders = self.recorders.lock().unwrap();
+        if recorders.contains_key(session_id) {
+            return Err("This session is already being recorded".to_string());
+        }
+
+        let dir = recordings_dir(app)?;
+        let mut dir_builder = DirBuilder::new();
+        dir_builder.recursive(true);
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        // Recordings hold whatever the terminal showed, and with input
+        // recording every keystroke, typed passwords included
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+            dir_builder.mode(0o700);
+            options.mode(0o600);
+        }
+        dir_builder.create(&dir).map_err(|e| e.to_string())?;
+        let path = dir.join(format!("{}-{}.cast", Local::now().format("%Y%m%d-%H%M%S"), file_label(title)));
+        let mut file = LineWriter::new(options.open(&path).map_err(|e| e.to_string())?);
+
+        let started_at = Utc::now().timestamp();
+        let header = CastHeader {
+            version: 2,
+            width,
+            height,
+            timestamp: Some(started_at),
+            idle_time_limit: None,
+            title: Some(title.to_string()),
+            env: Some(HashMap::from([("TERM".to_string(), "xterm-256color".to_string())])),
+        };
+        writeln!(file, "{}", serde_json::to_string(&header).map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+
+        let info = RecordingInfo {
+            session_id: session_id.to_string(),
+            path: path.to_string_lossy().into_owned(),
+            started_at,
+            record_input,
+        };
+        recorders.insert(session_id.to_string(), Arc::new(Recorder {
+            info: info.clone(),
+            started: Instant::now(),
+            writer: Mutex::new(CastWriter {
+                file,
+                pending_output: Vec::new(),
+                pending_input: Vec::new(),
+            }),
+        }));
+        println!("Recording session {} to {}", session_id, info.path);
+        let _ = app.emit(&format!("recording_status_{}", session_id), Some(&info));
+        Ok(info)
+    }
+
+    /// Stop recording a session, e.g. when it closes
+    pub fn finish(&self, app: &AppHandle, session_id: &str) -> Option<RecordingInfo> {
+        let recorder = self.recorders.lock().unwrap().remove(session_id)?;
+        let _ = recorder.writer.lock().unwrap().file.flush();
+        println!("Recording of session {} saved to {}", session_id, recorder.info.path);
+        let _ = app.emit(&format!("recording_status_{}", session_id), None::<RecordingInfo>);
+        Some(recorder.info.clone())
+    }
+}
+
+fn recordings_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(app.path().app_data_dir().map_err(|e| e.to_string())?.join(RECORDINGS_DIR))
+}
+
+/// `title` reduced to characters that are safe in a file name
+fn file_label(title: &str) -> String {
+    let label: String = title
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect();
+    label.trim_matches('_').chars().take(64).collect()
+}
+
+/// `path` if it is a recording in the recordings directory
+fn recording_path(app: &AppHandle, path: &str) -> Result<PathBuf, String> {
+    let dir = recordings_dir(app)?.canonicalize().map_err(|e| e.to_string())?;
+    let path = Path::new(path).canonicalize().map_err(|e| e.to_string())?;
+    if !path.starts_with(&dir) || path.extension().map_or(true, |ext| ext != "cast") {
+        return Err("Not a session recording".to_string());
+    }
+    Ok(path)
+}
+
+fn read_header(path: &Path) -> Result<CastHeader, String> {
+    let mut line = String::new();
+    BufReader::new(File::open(path).map_err(|e| e.to_string())?)
+        .read_line(&mut line)
+        .map_err(|e| e.to_string())?;
+    let header: CastHeader = serde_json::from_str(&line).map_err(|e| format!("Invalid asciicast header: {}", e))?;
+    if header.version != 2 {
+        return Err(format!("Unsupported asciicast version {}", header.version));
+    }
+    Ok(header)
+}
+
+/// Title and terminal size of an open SSH or local session
+fn session_details(app: &AppHandle, session_id: &str) -> Option<(String, (u32, u32))> {
+    let ssh = app.state::<SshState>().terminal_details(session_id);
+    ssh.or_else(|| {
+        let (cols, rows) = app.state::<LocalState>().size(session_id)?;
+        Some(("local".to_string(), (cols as u32, rows as u32)))
+    })
+}
+
+#[tauri::command]
+pub fn start_recording(
+    app: AppHandle,
+    state: State<RecordingState>,
+    session_id: String,
+    record_input: Option<bool>,
+) -> Result<RecordingInfo, String> {
+    let (title, size) = session_details(&app, &session_id).ok_or("Session not found")?;
+    state.start(&app, &session_id, &title, size, record_input.unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn stop_recording(app: AppHandle, state: State<RecordingState>, session_id: String) -> Result<Option<RecordingInfo>, String> {
+    Ok(state.finish(&app, &session_id))
+}
+
+#[tauri::command]
+pub fn get_recording_status(state: State<RecordingState>, session_id: String) -> Result<Option<RecordingInfo>, String> {
+    Ok(state.recorder(&session_id).map(|r| r.info.clone()))
+}
+
+/// Recordings on disk, newest first
+#[tauri::command]
+pub fn list_recordings(app: AppHandle) -> Result<Vec<RecordingFile>, String> {
+    let dir = recordings_dir(&app)?;
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+
+    let mut recordings = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().map_or(true, |ext| ext != "cast") {
+            continue;
+        }
+        let Ok(header) = read_header(&path) else { continue };
+        let metadata = entry.metadata().map_err(|e| e.to_string())?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs() as i64);
+        recordings.push(RecordingFile {
+            path: path.to_string_lossy().into_owned(),
+            name: entry.file_name().to_string_lossy().into_owned(),
+            size: metadata.len(),
+            modified,
+            header,
+        });
+    }
+    recordings.sort_by_key(|r| std::cmp::Reverse(r.modified));
+    Ok(recordings)
+}
+
+#[tauri::command]
+pub fn delete_recording(app: AppHandle, path: String) -> Result<(), String> {
+    let path = recording_path(&app, &path)?;
+    std::fs::remove_file(path).map_err(|e| e.to_string())
+}
+
+/// Play a recording back through `playback_event_{playback_id}` events,
+/// ending with `playback_end_{playback_id}`. Pauses longer than `max_idle`
+/// seconds (or the file's idle_time_limit) are shortened to it.
+#[tauri::command]
+pub fn play_recording(
+    window: Window,
+    state: State<RecordingState>,
+    playback_id: String,
+    path: String,
+    speed: Option<f64>,
+    max_idle: Option<f64>,
+) -> Result<CastHeader, String> {
+    let path = recording_path(window.app_handle(), &path)?;
+    let header = read_header(&path)?;
+    let lines: Vec<String> = BufReader::new(File::open(&path).map_err(|e| e.to_string())?)
+        .lines()
+        .skip(1)
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let (control, rx) = watch::channel(PlaybackControl {
+        speed: speed.unwrap_or(1.0).clamp(MIN_SPEED, MAX_SPEED),
+        paused: false,
+        stopped: false,
+    });
+    if let Some(previous) = state.playbacks.lock().unwrap().insert(playback_id.clone(), control) {
+        previous.send_modify(|c| c.stopped = true);
+    }
+
+    let max_idle = max_idle
+        .or(header.idle_time_limit)
+        .filter(|v| *v > 0.0)
+        .unwrap_or(f64::INFINITY);
+    let own = rx.clone();
+    tauri::async_runtime::spawn(async move {
+        play(&window, &playback_id, lines, max_idle, rx).await;
+        let _ = window.emit(&format!("playback_end_{}", playback_id), ());
+        // A newer playback may have taken over the id; leave its control alone
+        let state = window.state::<RecordingState>();
+        let mut playbacks = state.playbacks.lock().unwrap();
+        if playbacks.get(&playback_id).is_some_and(|c| c.subscribe().same_channel(&own)) {
+            playbacks.remove(&playback_id);
+        }
+    });
+    Ok(header)
+}
+
+async fn play(window: &Window, playback_id: &str, lines: Vec<String>, max_idle: f64, mut control: watch::Receiver<PlaybackControl>) {
+    let event_name = format!("playback_event_{}", playback_id);
+    let mut position = 0.0;
+
+    for line in lines.iter().filter(|l| !l.trim().is_empty()) {
+        let Ok((time, kind, data)) = serde_json::from_str::<(f64, String, String)>(line) else {
+            eprintln!("Skipping malformed recording event: {}", line);
+            continue;
+        };
+        if kind == "i" {
+            continue;
+        }
+
+        // Recording time still to wait, which speed changes stretch or shrink
+        let mut remaining = (time - position).clamp(0.0, max_idle);
+        while remaining > 0.0 {
+            let current = *control.borrow();
+            if current.stopped {
+                return;
+            }
+            if current.paused {
+                if control.changed().await.is_err() {
+                    return;
+                }
+                continue;
+            }
+            let started = Instant::now();
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs_f64(remaining / current.speed)) => remaining = 0.0,
+                changed = control.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+                    remaining -= started.elapsed().as_secs_f64() * current.speed;
+                }
+            }
+        }
+        if control.borrow().stopped {
+            return;
+        }
+
+        position = time;
+        let _ = window.emit(&event_name, PlaybackEvent { time, kind, data });
+    }
+}
+
+/// Change the speed of a running playback (0.1x to 16x)
+#[tauri::command]
+pub fn set_playback_speed(state: State<RecordingState>, playback_id: String, speed: f64) -> Result<(), String> {
+    let playbacks = state.playbacks.lock().unwrap();
+    let control = playbacks.get(&playback_id).ok_or("Playback not running")?;
+    control.send_modify(|c| c.speed = speed.clamp(MIN_SPEED, MAX_SPEED));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn pause_playback(state: State<RecordingState>, playback_id: String, paused: bool) -> Result<(), String> {
+    let playbacks = state.playbacks.lock().unwrap();
+    let control = playbacks.get(&playback_id).ok_or("Playback not running")?;
+    control.send_modify(|c| c.paused = paused);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_playback(state: State<RecordingState>, playback_id: String) -> Result<(), String> {
+    if let Some(control) = state.playbacks.lock().unwrap().remove(&playback_id) {
+        control.send_modify(|c| c.stopped = true);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_keeps_split_characters() {
+        let mut pending = Vec::new();
+        let bytes = "héllo €".as_bytes();
+        // Split inside "é" and inside "€"
+        assert_eq!(decode(&mut pending, &bytes[..2]), "h");
+        assert_eq!(decode(&mut pending, &bytes[2..8]), "éllo ");
+        assert_eq!(decode(&mut pending, &bytes[8..]), "€");
+        assert!(pending.is_empty());
+
+        assert_eq!(decode(&mut pending, b"\xffok"), "\u{fffd}ok");
+    }
+}
@@ -0,0 +1,85 @@
+use crate::db::Database;
+use crate::models::Credential;
+use rusqlite::{OptionalExtension, Result};
+use chrono::Utc;
+
+pub fn get_credential(db: &Database, id: i64) -> Result<Option<Credential>> {
+    db.query(|conn| {
+        conn.query_row(
+            "SELECT password, key_passphrase FROM credentials WHERE id = ?1",
+            [id],
+            |row| {
+                Ok(Credential {
+                    password: row.get(0)?,
+                    key_passphrase: row.get(1)?,
+                })
+            },
+        )
+        .optional()
+    })
+}
+
+/// Secrets stored for a server, if it has a vault entry
+pub fn get_server_credential(db: &Database, server_id: i64) -> Result<Option<Credential>> {
+    let credential_id: Option<i64> = db.query(|conn| {
+        conn.query_row(
+            "SELECT credential_id FROM servers WHERE id = ?1",
+            [server_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map(Option::flatten)
+    })?;
+
+    match credential_id {
+        Some(id) => get_credential(db, id),
+        None => Ok(None),
+    }
+}
+
+/// Store secrets for a server, creating its vault entry on first use.
+/// `None` keeps the stored value, an empty string removes it.
+pub fn set_server_secrets(
+    db: &Database,
+    server_id: i64,
+    password: Option<&str>,
+    key_passphrase: Option<&str>,
+) -> Result<i64> {
+    let now = Utc::now().timestamp();
+    db.query(|conn| {
+        let existing: Option<i64> = conn
+            .query_row(
+                "SELECT credential_id FROM servers WHERE id = ?1",
+                [server_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+
+        let id = match existing {
+            Some(id) => id,
+            None => {
+                conn.execute(
+                    "INSERT INTO credentials (created_at, updated_at) VALUES (?1, ?1)",
+                    [now],
+                )?;
+                let id = conn.last_insert_rowid();
+                conn.execute(
+                    "UPDATE servers SET credential_id = ?1 WHERE id = ?2",
+                    [id, server_id],
+                )?;
+                id
+            }
+        };
+
+        conn.execute(
+            "UPDATE credentials SET
+                password = CASE WHEN ?1 IS NULL THEN password ELSE NULLIF(?1, '') END,
+                key_passphrase = CASE WHEN ?2 IS NULL THEN key_passphrase ELSE NULLIF(?2, '') END,
+                updated_at = ?3
+             WHERE id = ?4",
+            rusqlite::params![password, key_passphrase, now, id],
+        )?;
+        Ok(id)
+    })
+}
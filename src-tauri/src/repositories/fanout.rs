@@ -0,0 +1,148 @@
+use crate::db::Database;
+use crate::models::{FanoutHostResult, FanoutRun, ServerSelector};
+use rusqlite::{OptionalExtension, Result, Row};
+use chrono::Utc;
+
+const RUN_COLUMNS: &str = "r.id, r.command, r.selector, r.parallelism, r.timeout_secs, r.started_at, r.finished_at, r.total,
+    (SELECT COUNT(*) FROM fanout_results x WHERE x.run_id = r.id AND x.exit_status = 0),
+    (SELECT COUNT(*) FROM fanout_results x WHERE x.run_id = r.id AND (x.exit_status IS NULL OR x.exit_status != 0))";
+
+fn run_from_row(row: &Row) -> Result<FanoutRun> {
+    let selector: String = row.get(2)?;
+    Ok(FanoutRun {
+        id: Some(row.get(0)?),
+        command: row.get(1)?,
+        selector: serde_json::from_str::<ServerSelector>(&selector).unwrap_or_default(),
+        parallelism: row.get(3)?,
+        timeout_secs: row.get(4)?,
+        started_at: row.get(5)?,
+        finished_at: row.get(6)?,
+        total: row.get(7)?,
+        succeeded: row.get(8)?,
+        failed: row.get(9)?,
+    })
+}
+
+pub fn create_run(db: &Database, run: &FanoutRun) -> Result<i64> {
+    db.query(|conn| {
+        conn.execute(
+            "INSERT INTO fanout_runs (command, selector, parallelism, timeout_secs, total, started_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+                run.command,
+                serde_json::to_string(&run.selector).unwrap_or_default(),
+                run.parallelism,
+                run.timeout_secs,
+                run.total,
+                run.started_at,
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    })
+}
+
+pub fn finish_run(db: &Database, run_id: i64) -> Result<()> {
+    db.query(|conn| {
+        conn.execute(
+            "UPDATE fanout_runs SET finished_at = ?1 WHERE id = ?2",
+            rusqlite::params![Utc::now().timestamp(), run_id],
+        )?;
+        Ok(())
+    })
+}
+
+pub fn add_result(db: &Database, run_id: i64, result: &FanoutHostResult) -> Result<()> {
+    db.query(|conn| {
+        conn.execute(
+            "INSERT INTO fanout_results (run_id, server_id, server_name, host, exit_status, exit_signal, error, timed_out, cancelled, duration_ms, output, truncated)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            rusqlite::params![
+                run_id,
+                result.server_id,
+                result.server_name,
+                result.host,
+                result.exit_status,
+                result.exit_signal,
+                result.error,
+                result.timed_out,
+                result.cancelled,
+                result.duration_ms as i64,
+                result.output,
+                result.truncated,
+            ],
+        )?;
+        Ok(())
+    })
+}
+
+/// Runs, newest first
+pub fn get_runs(db: &Database, limit: i64) -> Result<Vec<FanoutRun>> {
+    db.query(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM fanout_runs r ORDER BY r.started_at DESC, r.id DESC LIMIT ?1",
+            RUN_COLUMNS
+        ))?;
+        let runs = stmt.query_map([limit], run_from_row)?;
+        runs.collect()
+    })
+}
+
+pub fn get_run(db: &Database, run_id: i64) -> Result<Option<FanoutRun>> {
+    db.query(|conn| {
+        conn.query_row(
+            &format!("SELECT {} FROM fanout_runs r WHERE r.id = ?1", RUN_COLUMNS),
+            [run_id],
+            run_from_row,
+        )
+        .optional()
+    })
+}
+
+/// Host results of a run, in the order they finished
+pub fn get_results(db: &Database, run_id: i64) -> Result<Vec<FanoutHostResult>> {
+    db.query(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT server_id, server_name, host, exit_status, exit_signal, error, timed_out, cancelled, duration_ms, output, truncated
+             FROM fanout_results WHERE run_id = ?1 ORDER BY id",
+        )?;
+        let results = stmt.query_map([run_id], |row| {
+            Ok(FanoutHostResult {
+                server_id: row.get(0)?,
+                server_name: row.get(1)?,
+                host: row.get(2)?,
+                exit_status: row.get(3)?,
+                exit_signal: row.get(4)?,
+                error: row.get(5)?,
+                timed_out: row.get(6)?,
+                cancelled: row.get(7)?,
+                duration_ms: row.get::<_, i64>(8)? as u64,
+                output: row.get(9)?,
+                truncated: row.get(10)?,
+            })
+        })?;
+        results.collect()
+    })
+}
+
+/// Drop all but the newest `keep` runs
+pub fn prune_runs(db: &Database, keep: i64) -> Result<()> {
+    db.query(|conn| {
+        conn.execute(
+            "DELETE FROM fanout_results WHERE run_id NOT IN (SELECT id FROM fanout_runs ORDER BY started_at DESC, id DESC LIMIT ?1)",
+            [keep],
+        )?;
+        conn.execute(
+            "DELETE FROM fanout_runs WHERE id NOT IN (SELECT id FROM fanout_runs ORDER BY started_at DESC, id DESC LIMIT ?1)",
+            [keep],
+        )?;
+        Ok(())
+    })
+}
+
+pub fn delete_run(db: &Database, run_id: i64) -> Result<()> {
+    db.query(|conn| {
+        conn.execute("DELETE FROM fanout_results WHERE run_id = ?1", [run_id])?;
+        conn.execute("DELETE FROM fanout_runs WHERE id = ?1", [run_id])?;
+        Ok(())
+    })
+}
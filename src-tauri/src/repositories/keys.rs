@@ -0,0 +1,124 @@
+use crate::db::Database;
+use crate::models::SshKey;
+use rusqlite::{OptionalExtension, Result, Row};
+use chrono::Utc;
+
+const KEY_COLUMNS: &str = "id, name, key_type, bits, fingerprint, public_key, encrypted, comment, created_at, updated_at";
+
+fn key_from_row(row: &Row) -> Result<SshKey> {
+    Ok(SshKey {
+        id: Some(row.get(0)?),
+        name: row.get(1)?,
+        key_type: row.get(2)?,
+        bits: row.get(3)?,
+        fingerprint: row.get(4)?,
+        public_key: row.get(5)?,
+        encrypted: row.get(6)?,
+        comment: row.get(7)?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
+
+pub fn add_key(db: &Database, key: &SshKey, private_key: &str) -> Result<i64> {
+    let now = Utc::now().timestamp();
+
+    db.query(|conn| {
+        conn.execute(
+            "INSERT INTO ssh_keys (name, key_type, bits, fingerprint, public_key, private_key, encrypted, comment, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)",
+            rusqlite::params![
+                key.name,
+                key.key_type,
+                key.bits,
+                key.fingerprint,
+                key.public_key,
+                private_key,
+                key.encrypted,
+                key.comment,
+                now,
+            ],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    })
+}
+
+pub fn get_all_keys(db: &Database) -> Result<Vec<SshKey>> {
+    db.query(|conn| {
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM ssh_keys ORDER BY name", KEY_COLUMNS))?;
+        let keys = stmt.query_map([], key_from_row)?;
+        keys.collect()
+    })
+}
+
+pub fn get_key(db: &Database, id: i64) -> Result<Option<SshKey>> {
+    db.query(|conn| {
+        conn.query_row(
+            &format!("SELECT {} FROM ssh_keys WHERE id = ?1", KEY_COLUMNS),
+            [id],
+            key_from_row,
+        )
+        .optional()
+    })
+}
+
+/// The stored private key in OpenSSH format
+pub fn get_private_key(db: &Database, id: i64) -> Result<Option<String>> {
+    db.query(|conn| {
+        conn.query_row("SELECT private_key FROM ssh_keys WHERE id = ?1", [id], |row| row.get(0))
+            .optional()
+    })
+}
+
+/// Replace the private key, e.g. after its passphrase changed
+pub fn update_private_key(db: &Database, id: i64, private_key: &str, encrypted: bool) -> Result<()> {
+    let now = Utc::now().timestamp();
+
+    db.query(|conn| {
+        conn.execute(
+            "UPDATE ssh_keys SET private_key = ?1, encrypted = ?2, updated_at = ?3 WHERE id = ?4",
+            rusqlite::params![private_key, encrypted, now, id],
+        )?;
+        Ok(())
+    })
+}
+
+/// OpenSSH certificate issued for the key, if one is attached
+pub fn get_certificate(db: &Database, id: i64) -> Result<Option<String>> {
+    db.query(|conn| {
+        conn.query_row("SELECT certificate FROM ssh_keys WHERE id = ?1", [id], |row| row.get(0))
+            .optional()
+            .map(Option::flatten)
+    })
+}
+
+/// Attach a certificate to the key, or remove it with `None`
+pub fn set_certificate(db: &Database, id: i64, certificate: Option<&str>) -> Result<()> {
+    let now = Utc::now().timestamp();
+
+    db.query(|conn| {
+        conn.execute(
+            "UPDATE ssh_keys SET certificate = ?1, updated_at = ?2 WHERE id = ?3",
+            rusqlite::params![certificate, now, id],
+        )?;
+        Ok(())
+    })
+}
+
+/// Key assigned to a server, if any
+pub fn get_server_key_id(db: &Database, server_id: i64) -> Result<Option<i64>> {
+    db.query(|conn| {
+        conn.query_row("SELECT key_id FROM servers WHERE id = ?1", [server_id], |row| row.get(0))
+            .optional()
+            .map(Option::flatten)
+    })
+}
+
+pub fn delete_key(db: &Database, id: i64) -> Result<()> {
+    db.query(|conn| {
+        conn.execute("UPDATE servers SET key_id = NULL WHERE key_id = ?1", [id])?;
+        conn.execute("DELETE FROM ssh_keys WHERE id = ?1", [id])?;
+        Ok(())
+    })
+}
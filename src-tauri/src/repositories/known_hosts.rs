@@ -0,0 +1,68 @@
+use crate::db::Database;
+use crate::models::KnownHost;
+use rusqlite::Result;
+use chrono::Utc;
+
+pub fn add_known_host(db: &Database, host: &KnownHost) -> Result<i64> {
+    let now = Utc::now().timestamp();
+
+    db.query(|conn| {
+        conn.execute(
+            "INSERT INTO known_hosts (host, key_type, public_key, fingerprint, comment, marker, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                host.host,
+                host.key_type,
+                host.public_key,
+                host.fingerprint,
+                host.comment,
+                host.marker,
+                now,
+            ],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    })
+}
+
+pub fn get_all_known_hosts(db: &Database) -> Result<Vec<KnownHost>> {
+    db.query(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, host, key_type, public_key, fingerprint, comment, marker, created_at
+             FROM known_hosts ORDER BY host",
+        )?;
+
+        let hosts = stmt.query_map([], |row| {
+            Ok(KnownHost {
+                id: Some(row.get(0)?),
+                host: row.get(1)?,
+                key_type: row.get(2)?,
+                public_key: row.get(3)?,
+                fingerprint: row.get(4)?,
+                comment: row.get(5)?,
+                marker: row.get(6)?,
+                created_at: row.get(7)?,
+            })
+        })?;
+
+        hosts.collect()
+    })
+}
+
+pub fn known_host_exists(db: &Database, host: &str, key_type: &str, public_key: &str) -> Result<bool> {
+    db.query(|conn| {
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM known_hosts WHERE host = ?1 AND key_type = ?2 AND public_key = ?3",
+            [host, key_type, public_key],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    })
+}
+
+pub fn delete_known_host(db: &Database, id: i64) -> Result<()> {
+    db.query(|conn| {
+        conn.execute("DELETE FROM known_hosts WHERE id = ?1", [id])?;
+        Ok(())
+    })
+}
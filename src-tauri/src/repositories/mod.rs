@@ -2,3 +2,7 @@ pub mod servers;
 pub mod history;
 pub mod settings;
 pub mod snippets;
+pub mod known_hosts;
+pub mod credentials;
+pub mod keys;
+pub mod fanout;
@@ -1,5 +1,5 @@
 use crate::db::Database;
-use crate::models::Server;
+use crate::models::{Server, ServerSelector};
 use rusqlite::Result;
 use chrono::Utc;
 
@@ -9,14 +9,13 @@ pub fn create_server(db: &Database, server: &Server) -> Result<i64> {
     
     db.query(|conn| {
         conn.execute(
-            "INSERT INTO servers (name, host, port, username, password, private_key_path, server_group, tags, color, created_at, updated_at, forwarding_rules, jump_host_id)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            "INSERT INTO servers (name, host, port, username, private_key_path, server_group, tags, color, created_at, updated_at, forwarding_rules, jump_host_id, key_id, forward_agent, record_sessions)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
             rusqlite::params![
                 server.name,
                 server.host,
                 server.port,
                 server.username,
-                server.password,
                 server.private_key_path,
                 server.group,
                 tags_json,
@@ -25,6 +24,9 @@ pub fn create_server(db: &Database, server: &Server) -> Result<i64> {
                 now,
                 serde_json::to_string(&server.forwarding_rules).unwrap_or_default(),
                 server.jump_host_id,
+                server.key_id,
+                server.forward_agent,
+                server.record_sessions,
             ],
         )?;
         
@@ -35,8 +37,9 @@ pub fn create_server(db: &Database, server: &Server) -> Result<i64> {
 pub fn get_all_servers(db: &Database) -> Result<Vec<Server>> {
     db.query(|conn| {
         let mut stmt = conn.prepare(
-            "SELECT id, name, host, port, username, password, private_key_path, server_group, tags, color, created_at, updated_at, forwarding_rules, jump_host_id
-             FROM servers ORDER BY updated_at DESC",
+            "SELECT s.id, s.name, s.host, s.port, s.username, s.credential_id, s.private_key_path, s.server_group, s.tags, s.color, s.created_at, s.updated_at, s.forwarding_rules, s.jump_host_id,
+                    c.password IS NOT NULL, c.key_passphrase IS NOT NULL, s.key_id, s.forward_agent, s.record_sessions
+             FROM servers s LEFT JOIN credentials c ON c.id = s.credential_id ORDER BY s.updated_at DESC",
         )?;
         
         let servers = stmt.query_map([], |row| {
@@ -49,7 +52,7 @@ pub fn get_all_servers(db: &Database) -> Result<Vec<Server>> {
                 host: row.get(2)?,
                 port: row.get(3)?,
                 username: row.get(4)?,
-                password: row.get(5)?,
+                password: None,
                 private_key_path: row.get(6)?,
                 group: row.get(7)?,
                 tags,
@@ -58,6 +61,13 @@ pub fn get_all_servers(db: &Database) -> Result<Vec<Server>> {
                 updated_at: row.get(11)?,
                 forwarding_rules: row.get::<_, Option<String>>(12)?.and_then(|s| serde_json::from_str(&s).ok()),
                 jump_host_id: row.get(13)?,
+                key_passphrase: None,
+                credential_id: row.get(5)?,
+                has_password: row.get(14)?,
+                has_key_passphrase: row.get(15)?,
+                key_id: row.get(16)?,
+                forward_agent: row.get(17)?,
+                record_sessions: row.get(18)?,
             })
         })?;
         
@@ -65,12 +75,22 @@ pub fn get_all_servers(db: &Database) -> Result<Vec<Server>> {
     })
 }
 
-#[allow(dead_code)]
+/// Saved servers matching `selector`, by name
+pub fn select_servers(db: &Database, selector: &ServerSelector) -> Result<Vec<Server>> {
+    let mut servers: Vec<Server> = get_all_servers(db)?
+        .into_iter()
+        .filter(|server| selector.matches(server))
+        .collect();
+    servers.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(servers)
+}
+
 pub fn get_server(db: &Database, id: i64) -> Result<Option<Server>> {
     db.query(|conn| {
         let mut stmt = conn.prepare(
-            "SELECT id, name, host, port, username, password, private_key_path, server_group, tags, color, created_at, updated_at, forwarding_rules, jump_host_id
-             FROM servers WHERE id = ?1",
+            "SELECT s.id, s.name, s.host, s.port, s.username, s.credential_id, s.private_key_path, s.server_group, s.tags, s.color, s.created_at, s.updated_at, s.forwarding_rules, s.jump_host_id,
+                    c.password IS NOT NULL, c.key_passphrase IS NOT NULL, s.key_id, s.forward_agent, s.record_sessions
+             FROM servers s LEFT JOIN credentials c ON c.id = s.credential_id WHERE s.id = ?1",
         )?;
         
         let mut servers = stmt.query_map([id], |row| {
@@ -83,7 +103,7 @@ pub fn get_server(db: &Database, id: i64) -> Result<Option<Server>> {
                 host: row.get(2)?,
                 port: row.get(3)?,
                 username: row.get(4)?,
-                password: row.get(5)?,
+                password: None,
                 private_key_path: row.get(6)?,
                 group: row.get(7)?,
                 tags,
@@ -92,6 +112,13 @@ pub fn get_server(db: &Database, id: i64) -> Result<Option<Server>> {
                 updated_at: row.get(11)?,
                 forwarding_rules: row.get::<_, Option<String>>(12)?.and_then(|s| serde_json::from_str(&s).ok()),
                 jump_host_id: row.get(13)?,
+                key_passphrase: None,
+                credential_id: row.get(5)?,
+                has_password: row.get(14)?,
+                has_key_passphrase: row.get(15)?,
+                key_id: row.get(16)?,
+                forward_agent: row.get(17)?,
+                record_sessions: row.get(18)?,
             })
         })?;
         
@@ -109,13 +136,13 @@ pub fn update_server(db: &Database, server: &Server) -> Result<()> {
     db.query(|conn| {
         conn.execute(
             "UPDATE servers SET name = ?1, host = ?2, port = ?3, username = ?4, 
-             password = ?5, private_key_path = ?6, server_group = ?7, tags = ?8, color = ?9, updated_at = ?10, forwarding_rules = ?12, jump_host_id = ?13 WHERE id = ?11",
+             private_key_path = ?5, server_group = ?6, tags = ?7, color = ?8, updated_at = ?9, forwarding_rules = ?11, jump_host_id = ?12, key_id = ?13, forward_agent = ?14, record_sessions = ?15
+             WHERE id = ?10",
             rusqlite::params![
                 server.name,
                 server.host,
                 server.port,
                 server.username,
-                server.password,
                 server.private_key_path,
                 server.group,
                 tags_json,
@@ -124,6 +151,9 @@ pub fn update_server(db: &Database, server: &Server) -> Result<()> {
                 server.id,
                 serde_json::to_string(&server.forwarding_rules).unwrap_or_default(),
                 server.jump_host_id,
+                server.key_id,
+                server.forward_agent,
+                server.record_sessions,
             ],
         )?;
         
@@ -133,6 +163,10 @@ pub fn update_server(db: &Database, server: &Server) -> Result<()> {
 
 pub fn delete_server(db: &Database, id: i64) -> Result<()> {
     db.query(|conn| {
+        conn.execute(
+            "DELETE FROM credentials WHERE id = (SELECT credential_id FROM servers WHERE id = ?1)",
+            [id],
+        )?;
         conn.execute("DELETE FROM servers WHERE id = ?1", [id])?;
         Ok(())
     })
@@ -56,6 +56,11 @@ pub fn get_all_settings(db: &Database) -> Result<AppSettings> {
         .unwrap_or_else(|| "0".to_string())
         .parse()
         .unwrap_or(0);
+
+    let agent_for_local_terminals = get_setting(db, "agent_for_local_terminals")?
+        .unwrap_or_else(|| "false".to_string())
+        .parse()
+        .unwrap_or(false);
     
     Ok(AppSettings {
         history_limit,
@@ -67,6 +72,7 @@ pub fn get_all_settings(db: &Database) -> Result<AppSettings> {
         ai_base_url,
         auto_reconnect,
         lock_timeout,
+        agent_for_local_terminals,
     })
 }
 
@@ -77,6 +83,7 @@ pub fn update_settings(db: &Database, settings: &AppSettings) -> Result<()> {
     set_setting(db, "ai_provider", &settings.ai_provider)?;
     set_setting(db, "auto_reconnect", &settings.auto_reconnect.to_string())?;
     set_setting(db, "lock_timeout", &settings.lock_timeout.to_string())?;
+    set_setting(db, "agent_for_local_terminals", &settings.agent_for_local_terminals.to_string())?;
     
     if let Some(key) = &settings.ai_api_key {
         set_setting(db, "ai_api_key", key)?;
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+use tauri::{AppHandle, Manager};
+use tokio::sync::watch;
+use crate::db::Database;
+use crate::models::Server;
+use crate::repositories::servers;
+use crate::ssh_utils::Credentials;
+use crate::transport::SshTransport;
+
+// Longest jump host chain we are willing to follow
+const MAX_HOPS: usize = 8;
+
+/// Live connections to jump hosts, keyed by the chain of server ids used to
+/// reach them (outermost first), so sessions behind the same bastion share
+/// one login to it. Entries are weak: a hop goes away with its last user.
+pub struct HopPool {
+    hops: Mutex<HashMap<Vec<i64>, Weak<SshTransport>>>,
+}
+
+impl HopPool {
+    pub fn new() -> Self {
+        Self {
+            hops: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get(&self, path: &[i64]) -> Option<Arc<SshTransport>> {
+        let mut hops = self.hops.lock().unwrap();
+        // Drop entries whose connection is gone while we are here
+        hops.retain(|_, hop| hop.upgrade().is_some_and(|hop| !hop.is_closed()));
+        hops.get(path).and_then(Weak::upgrade)
+    }
+
+    fn insert(&self, path: Vec<i64>, hop: &Arc<SshTransport>) {
+        self.hops.lock().unwrap().insert(path, Arc::downgrade(hop));
+    }
+}
+
+/// Jump hosts that must be traversed to reach a server, outermost first.
+/// `server_id` is the target itself (if saved) so routes through it are
+/// reported as cycles too.
+pub fn resolve_route(db: &Database, server_id: Option<i64>, jump_host_id: Option<i64>) -> Result<Vec<Server>, String> {
+    resolve_chain(server_id, jump_host_id, |id| {
+        servers::get_server(db, id).map_err(|e| e.to_string())
+    })
+}
+
+fn resolve_chain(
+    server_id: Option<i64>,
+    jump_host_id: Option<i64>,
+    lookup: impl Fn(i64) -> Result<Option<Server>, String>,
+) -> Result<Vec<Server>, String> {
+    let mut visited: Vec<i64> = server_id.into_iter().collect();
+    let mut route: Vec<Server> = Vec::new();
+    let mut next = jump_host_id;
+
+    while let Some(id) = next {
+        if visited.contains(&id) {
+            let mut names: Vec<String> = route.iter().rev().map(|s| s.name.clone()).collect();
+            names.push(format!("#{}", id));
+            return Err(format!("Jump host cycle detected: {}", names.join(" -> ")));
+        }
+        if route.len() >= MAX_HOPS {
+            return Err(format!("Jump host chain is longer than {} hops", MAX_HOPS));
+        }
+
+        let server = lookup(id)?.ok_or_else(|| format!("Jump host #{} not found", id))?;
+        visited.push(id);
+        next = server.jump_host_id;
+        route.push(server);
+    }
+
+    // Walked from the target outwards; connections are made the other way
+    route.reverse();
+    Ok(route)
+}
+
+/// Connect (or reuse connections) through every hop of `route` and return
+/// the innermost one, from which the target is reached.
+pub async fn connect_route(
+    app: &AppHandle,
+    route: &[Server],
+    cancel: Option<&watch::Receiver<bool>>,
+) -> Result<Option<Arc<SshTransport>>, String> {
+    let mut path = Vec::new();
+    let mut via: Option<Arc<SshTransport>> = None;
+
+    for server in route {
+        path.push(server.id.unwrap_or_default());
+        if let Some(hop) = app.state::<HopPool>().get(&path) {
+            println!("Reusing jump host connection to {}:{}", hop.host, hop.port);
+            via = Some(hop);
+            continue;
+        }
+
+        println!("Connecting to Jump Host: {}@{}:{}", server.username, server.host, server.port);
+        let mut credentials = credentials_for(server);
+        credentials.cancel = cancel.cloned();
+        let hop = SshTransport::connect_via(app, &server.host, server.port as u16, &mut credentials, via)
+            .await
+            .map_err(|e| format!("Jump host {} connection failed: {}", server.name, e))?;
+        app.state::<HopPool>().insert(path.clone(), &hop);
+        via = Some(hop);
+    }
+
+    Ok(via)
+}
+
+pub fn credentials_for(server: &Server) -> Credentials {
+    Credentials {
+        username: server.username.clone(),
+        password: server.password.clone(),
+        private_key: server.private_key_path.clone(),
+        passphrase: server.key_passphrase.clone(),
+        server_id: server.id,
+        certificate: None,
+        cancel: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(id: i64, jump_host_id: Option<i64>) -> Server {
+        Server {
+            id: Some(id),
+            name: format!("host{}", id),
+            host: format!("10.0.0.{}", id),
+            port: 22,
+            username: "root".to_string(),
+            password: None,
+            private_key_path: None,
+            key_passphrase: None,
+            credential_id: None,
+            has_password: false,
+            has_key_passphrase: false,
+            key_id: None,
+            forward_agent: false,
+            record_sessions: false,
+            group: None,
+            tags: None,
+            color: None,
+            created_at: 0,
+            updated_at: 0,
+            forwarding_rules: None,
+            jump_host_id,
+        }
+    }
+
+    fn lookup(servers: Vec<Server>) -> impl Fn(i64) -> Result<Option<Server>, String> {
+        let servers: HashMap<i64, Server> = servers.into_iter().map(|s| (s.id.unwrap(), s)).collect();
+        move |id| Ok(servers.get(&id).cloned())
+    }
+
+    #[test]
+    fn test_resolve_chain_outermost_first() {
+        // target(4) -> 3 -> 2 -> 1
+        let servers = vec![server(1, None), server(2, Some(1)), server(3, Some(2))];
+        let route = resolve_chain(Some(4), Some(3), lookup(servers)).unwrap();
+        let ids: Vec<i64> = route.iter().map(|s| s.id.unwrap()).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+
+        assert!(resolve_chain(Some(4), None, lookup(vec![])).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_resolve_chain_detects_cycles() {
+        let servers = vec![server(1, Some(2)), server(2, Some(1))];
+        let err = resolve_chain(None, Some(1), lookup(servers)).unwrap_err();
+        assert!(err.contains("cycle"), "{}", err);
+
+        // A server must not be its own (indirect) jump host
+        let servers = vec![server(1, Some(2)), server(2, None)];
+        assert!(resolve_chain(Some(2), Some(1), lookup(servers.clone())).is_err());
+        assert!(resolve_chain(Some(1), Some(1), lookup(servers)).is_err());
+    }
+
+    #[test]
+    fn test_resolve_chain_missing_hop() {
+        let err = resolve_chain(None, Some(9), lookup(vec![])).unwrap_err();
+        assert!(err.contains("not found"), "{}", err);
+    }
+}
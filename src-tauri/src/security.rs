@@ -1,18 +1,26 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::{command, State};
 use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
 use argon2::password_hash::SaltString;
 use rand::rngs::OsRng;
+use rand::RngCore;
 use crate::db::Database;
 use rusqlite::Connection;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SecurityConfig {
     pub password_hash: String,
+    // Hex salt for the Argon2id database key; absent for databases keyed
+    // with the old unsalted SHA-256 scheme
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_salt: Option<String>,
 }
 
+const KEY_SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+
 fn get_security_file_path() -> PathBuf {
     let mut path = dirs::data_local_dir().unwrap_or(PathBuf::from("."));
     path.push("nebula-ssh");
@@ -21,83 +29,184 @@ fn get_security_file_path() -> PathBuf {
     path
 }
 
-// Derive a key from password for SQLCipher
-fn derive_key(password: &str) -> Result<String, String> {
-    use sha2::{Sha256, Digest};
-    
-    // Use SHA256 to derive a 256-bit key from the password
-    let mut hasher = Sha256::new();
-    hasher.update(password.as_bytes());
-    let result = hasher.finalize();
-    
-    // Convert to hex string
-    let key = hex::encode(result);
-    
-    // Log for debugging (REMOVE IN PRODUCTION)
-    eprintln!("Derived key length: {}", key.len());
-    
-    Ok(key)
+fn get_pending_file_path() -> PathBuf {
+    get_security_file_path().with_extension("json.pending")
 }
 
-#[command]
-pub fn is_master_password_set() -> bool {
-    get_security_file_path().exists()
+fn read_config(path: &Path) -> Result<SecurityConfig, String> {
+    let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&json).map_err(|e| e.to_string())
 }
 
-#[command]
-pub fn set_master_password(password: String) -> Result<(), String> {
+fn load_config() -> Result<SecurityConfig, String> {
+    let path = get_security_file_path();
+    if !path.exists() {
+        return Err("Master password not set".to_string());
+    }
+    read_config(&path)
+}
+
+// Config left behind by a re-key that didn't finish, if any
+fn load_pending() -> Option<SecurityConfig> {
+    let path = get_pending_file_path();
+    if !path.exists() {
+        return None;
+    }
+    read_config(&path).ok()
+}
+
+fn write_config(path: &Path, config: &SecurityConfig) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+    // Write then rename so a crash never leaves a truncated config behind
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, json).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, path).map_err(|e| e.to_string())
+}
+
+fn save_config(config: &SecurityConfig) -> Result<(), String> {
+    write_config(&get_security_file_path(), config)
+}
+
+fn new_config(password: &str) -> Result<SecurityConfig, String> {
     let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
-    let password_hash = argon2.hash_password(password.as_bytes(), &salt)
+    let password_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
         .map_err(|e| e.to_string())?
         .to_string();
 
-    let config = SecurityConfig {
+    let mut key_salt = [0u8; KEY_SALT_LEN];
+    OsRng.fill_bytes(&mut key_salt);
+
+    Ok(SecurityConfig {
         password_hash,
-    };
+        key_salt: Some(hex::encode(key_salt)),
+    })
+}
 
-    let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
-    fs::write(get_security_file_path(), json).map_err(|e| e.to_string())?;
-    Ok(())
+// Derive the SQLCipher key from the password with Argon2id and the stored salt
+fn derive_key(password: &str, salt: &str) -> Result<String, String> {
+    let salt = hex::decode(salt).map_err(|e| e.to_string())?;
+    let mut key = [0u8; KEY_LEN];
+    Argon2::default()
+        .hash_password_into(password.as_bytes(), &salt, &mut key)
+        .map_err(|e| e.to_string())?;
+    Ok(hex::encode(key))
 }
 
-#[command]
-pub fn verify_master_password(password: String) -> Result<bool, String> {
-    let path = get_security_file_path();
-    if !path.exists() {
-        return Err("Master password not set".to_string());
-    }
+// Key used before salted derivation; only needed to open and re-key old databases
+fn legacy_key(password: &str) -> String {
+    use sha2::{Sha256, Digest};
+    hex::encode(Sha256::digest(password.as_bytes()))
+}
 
-    let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
-    let config: SecurityConfig = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+#[command]
+pub fn is_master_password_set() -> bool {
+    get_security_file_path().exists()
+}
 
+fn check_password(config: &SecurityConfig, password: &str) -> Result<bool, String> {
     let parsed_hash = PasswordHash::new(&config.password_hash)
         .map_err(|e| e.to_string())?;
-    
     Ok(Argon2::default().verify_password(password.as_bytes(), &parsed_hash).is_ok())
 }
 
 #[command]
-pub fn change_master_password(old_password: String, new_password: String) -> Result<(), String> {
-    if !verify_master_password(old_password.clone())? {
+pub fn verify_master_password(password: String) -> Result<bool, String> {
+    check_password(&load_config()?, &password)
+}
+
+/// Re-key the open database with `password` under a fresh salt. The new
+/// config goes to a pending file first and only replaces security.json
+/// once the database uses the new key, so an interrupted re-key always
+/// leaves a config that opens the database (see `unlock_app`).
+fn rekey_database(db: &Database, password: &str, old_key: &str) -> Result<(), String> {
+    let config = new_config(password)?;
+    let key = derive_key(password, config.key_salt.as_deref().unwrap_or_default())?;
+    let pending = get_pending_file_path();
+    write_config(&pending, &config)?;
+
+    if let Err(e) = db.rekey(key) {
+        let _ = fs::remove_file(&pending);
+        return Err(e.to_string());
+    }
+    if let Err(e) = fs::rename(&pending, get_security_file_path()) {
+        eprintln!("Failed to save security config, restoring previous key: {}", e);
+        db.rekey(old_key.to_string()).map_err(|e| e.to_string())?;
+        let _ = fs::remove_file(&pending);
+        return Err(e.to_string());
+    }
+    Ok(())
+}
+
+#[command]
+pub fn change_master_password(old_password: String, new_password: String, db: State<Database>) -> Result<(), String> {
+    let config = load_config()?;
+    if !check_password(&config, &old_password)? {
         return Err("Incorrect old password".to_string());
     }
-    set_master_password(new_password)
+
+    let old_key = match &config.key_salt {
+        Some(salt) => derive_key(&old_password, salt)?,
+        None => legacy_key(&old_password),
+    };
+    // Re-keying needs the database open under the old key
+    db.unlock(old_key.clone()).map_err(|e| e.to_string())?;
+    rekey_database(&db, &new_password, &old_key)
+}
+
+// Verify `password` against `config` and open the database with its key
+fn unlock_with(config: &SecurityConfig, password: &str, db: &Database) -> Result<(), String> {
+    if !check_password(config, password)? {
+        return Err("Incorrect password".to_string());
+    }
+
+    match &config.key_salt {
+        Some(salt) => {
+            let key = derive_key(password, salt)?;
+            db.unlock(key).map_err(|e| e.to_string())?;
+        }
+        None => {
+            // Database still uses the unsalted key: open it and move it over
+            eprintln!("Migrating database to Argon2id key derivation");
+            let old_key = legacy_key(password);
+            db.unlock(old_key.clone()).map_err(|e| e.to_string())?;
+            rekey_database(db, password, &old_key)?;
+        }
+    }
+    Ok(())
 }
 
 // Unified command for unlocking the app (verify password + unlock DB)
 #[command]
 pub fn unlock_app(password: String, db: State<Database>) -> Result<(), String> {
-    // Verify password
-    if !verify_master_password(password.clone())? {
-        return Err("Incorrect password".to_string());
+    let config = load_config()?;
+    match unlock_with(&config, &password, &db) {
+        Ok(()) => {
+            // A re-key that was interrupted before touching the database
+            let _ = fs::remove_file(get_pending_file_path());
+            Ok(())
+        }
+        // A re-key interrupted after the database took the new key: the
+        // pending config is the one that opens it, so finish the swap
+        Err(e) => match load_pending() {
+            Some(pending) if !db.is_unlocked() && unlock_with(&pending, &password, &db).is_ok() => {
+                eprintln!("Completing interrupted database re-key");
+                fs::rename(get_pending_file_path(), get_security_file_path()).map_err(|e| e.to_string())
+            }
+            _ => Err(e),
+        },
     }
-    
-    // Derive key and unlock database
-    let key = derive_key(&password)?;
-    db.unlock(key).map_err(|e| e.to_string())?;
-    
-    Ok(())
+}
+
+// Whether the database file at `path` can be read with `key`
+fn can_open(path: &std::path::Path, key: &str) -> bool {
+    let Ok(conn) = Connection::open(path) else {
+        eprintln!("Cannot open existing database file");
+        return false;
+    };
+    conn.pragma_update(None, "key", format!("x'{}'", key)).is_ok()
+        && conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |_| Ok(())).is_ok()
 }
 
 // Setup encryption for first-time setup or migration
@@ -124,11 +233,10 @@ pub fn setup_encryption(password: String, db: State<Database>) -> Result<(), Str
     
     eprintln!("Should migrate existing database: {}", should_migrate);
     
-    // Save password hash
-    set_master_password(password.clone())?;
-    
-    // Derive key
-    let key = derive_key(&password)?;
+    // Save password hash and a fresh key salt
+    let config = new_config(&password)?;
+    let key = derive_key(&password, config.key_salt.as_deref().unwrap_or_default())?;
+    save_config(&config)?;
     
     if should_migrate {
         eprintln!("Migrating existing unencrypted database (file is large)");
@@ -140,34 +248,15 @@ pub fn setup_encryption(password: String, db: State<Database>) -> Result<(), Str
         // Only delete if it's truly unencrypted/corrupted
         if db_path.exists() {
             eprintln!("Checking if existing file needs migration");
-            // Try to open with the new key
-            match Connection::open(db_path) {
-                Ok(test_conn) => {
-                    // Try to use the key
-                    let test_key = format!("x'{}'", key);
-                    match test_conn.pragma_update(None, "key", &test_key) {
-                        Ok(_) => {
-                            // Check if we can read from it
-                            match test_conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |_| Ok(())) {
-                                Ok(_) => {
-                                    eprintln!("Database is already encrypted with this key, using it");
-                                    // Database already encrypted, just unlock it
-                                    drop(test_conn);
-                                    db.unlock(key.clone()).map_err(|e| e.to_string())?;
-                                    return Ok(());
-                                }
-                                Err(_) => {
-                                    eprintln!("Database exists but cannot read, will recreate");
-                                }
-                            }
-                        }
-                        Err(_) => {
-                            eprintln!("Cannot set key on existing database");
-                        }
+            // Try the new key, then the unsalted key older versions used
+            for candidate in [key.clone(), legacy_key(&password)] {
+                if can_open(db_path, &candidate) {
+                    eprintln!("Database is already encrypted with this password, using it");
+                    db.unlock(candidate.clone()).map_err(|e| e.to_string())?;
+                    if candidate != key {
+                        db.rekey(key).map_err(|e| e.to_string())?;
                     }
-                }
-                Err(_) => {
-                    eprintln!("Cannot open existing database file");
+                    return Ok(());
                 }
             }
             
@@ -183,3 +272,20 @@ pub fn setup_encryption(password: String, db: State<Database>) -> Result<(), Str
     eprintln!("setup_encryption completed successfully");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_key_is_salted() {
+        let salt_a = hex::encode([1u8; KEY_SALT_LEN]);
+        let salt_b = hex::encode([2u8; KEY_SALT_LEN]);
+
+        let key = derive_key("hunter2", &salt_a).unwrap();
+        assert_eq!(key.len(), KEY_LEN * 2);
+        assert_eq!(key, derive_key("hunter2", &salt_a).unwrap());
+        assert_ne!(key, derive_key("hunter2", &salt_b).unwrap());
+        assert_ne!(key, legacy_key("hunter2"));
+    }
+}
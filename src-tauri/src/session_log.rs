@@ -0,0 +1,543 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager, State};
+use crate::db::Database;
+use crate::repositories::settings;
+use crate::ssh_utils::expand_home;
+
+const LOG_EXTENSION: &str = "log";
+const DEFAULT_DIRECTORY_PATTERN: &str = "{server}/{date}";
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+// A partial line is written out once it grows this long
+const MAX_LINE_LEN: usize = 16 * 1024;
+
+/// How terminal output is logged to plain-text files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionLogSettings {
+    pub enabled: bool,
+    pub directory: Option<String>, // Defaults to <app data>/logs/sessions
+    // Subdirectories under `directory`; `{server}`, `{host}` and `{date}`
+    // are replaced, e.g. "{server}/{date}"
+    pub directory_pattern: String,
+    pub timestamps: bool, // Prefix each line with the time it started
+    pub strip_ansi: bool,
+    pub max_file_size_mb: u32, // Start a new part beyond this, 0 for no limit
+    pub retention_days: u32, // Delete older logs, 0 to keep forever
+}
+
+fn load_settings(db: &Database) -> Result<SessionLogSettings, String> {
+    let get = |key: &str| settings::get_setting(db, key).map_err(|e| e.to_string());
+    Ok(SessionLogSettings {
+        enabled: get("session_log_enabled")?.is_some_and(|v| v == "true"),
+        directory: get("session_log_directory")?.filter(|v| !v.is_empty()),
+        directory_pattern: get("session_log_directory_pattern")?
+            .unwrap_or_else(|| DEFAULT_DIRECTORY_PATTERN.to_string()),
+        timestamps: get("session_log_timestamps")?.map_or(true, |v| v == "true"),
+        strip_ansi: get("session_log_strip_ansi")?.map_or(true, |v| v == "true"),
+        max_file_size_mb: get("session_log_max_file_size_mb")?.and_then(|v| v.parse().ok()).unwrap_or(10),
+        retention_days: get("session_log_retention_days")?.and_then(|v| v.parse().ok()).unwrap_or(30),
+    })
+}
+
+#[tauri::command]
+pub fn get_session_log_settings(db: State<Database>) -> Result<SessionLogSettings, String> {
+    load_settings(&db)
+}
+
+/// Save the logging settings; sessions opened from now on use them
+#[tauri::command]
+pub fn set_session_log_settings(db: State<Database>, log_settings: SessionLogSettings) -> Result<(), String> {
+    let pattern = log_settings.directory_pattern.trim().trim_matches('/');
+    if Path::new(pattern).components().any(|c| !matches!(c, Component::Normal(_))) {
+        return Err("The directory pattern must be a relative path without \"..\"".to_string());
+    }
+
+    let values = [
+        ("session_log_enabled", log_settings.enabled.to_string()),
+        ("session_log_directory", log_settings.directory.unwrap_or_default()),
+        ("session_log_directory_pattern", pattern.to_string()),
+        ("session_log_timestamps", log_settings.timestamps.to_string()),
+        ("session_log_strip_ansi", log_settings.strip_ansi.to_string()),
+        ("session_log_max_file_size_mb", log_settings.max_file_size_mb.to_string()),
+        ("session_log_retention_days", log_settings.retention_days.to_string()),
+    ];
+    for (key, value) in values {
+        settings::set_setting(&db, key, &value).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn default_log_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(app.path().app_data_dir().map_err(|e| e.to_string())?.join("logs").join("sessions"))
+}
+
+/// One path component made from user text
+fn path_label(text: &str) -> String {
+    let label: String = text
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '.' | '-' | '_' | ' ') { c } else { '_' })
+        .collect();
+    match label.trim().trim_matches('.') {
+        "" => "_".to_string(),
+        label => label.to_string(),
+    }
+}
+
+/// Directory for a session's log under `root`, following `pattern`
+fn log_dir(root: &Path, pattern: &str, server: &str, host: &str, now: &DateTime<Local>) -> PathBuf {
+    let mut dir = root.to_path_buf();
+    for part in pattern.split('/').filter(|p| !p.is_empty()) {
+        let part = part
+            .replace("{server}", &path_label(server))
+            .replace("{host}", &path_label(host))
+            .replace("{date}", &now.format("%Y-%m-%d").to_string());
+        dir.push(path_label(&part));
+    }
+    dir
+}
+
+/// Escape sequence being skipped while stripping ANSI codes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Escape {
+    None,
+    Start,
+    Csi,
+    // OSC, DCS and the other string sequences, up to BEL or ESC \
+    Str,
+    StrTerminator,
+    // Character set selection: ESC ( B and friends
+    Charset,
+}
+
+/// Turns terminal output into plain lines. Escape sequences are dropped,
+/// while carriage returns, backspaces, cursor moves and line erases are
+/// applied to the current line, so progress bars and line editing log as
+/// the text that ended up on screen.
+struct Plain {
+    escape: Escape,
+    csi_param: Option<usize>,
+    utf8: Vec<u8>,
+    line: Vec<char>,
+    cursor: usize,
+}
+
+impl Plain {
+    fn new() -> Self {
+        Self {
+            escape: Escape::None,
+            csi_param: None,
+            utf8: Vec::new(),
+            line: Vec::new(),
+            cursor: 0,
+        }
+    }
+
+    /// Feed output, calling `emit` with every completed line
+    fn feed(&mut self, data: &[u8], mut emit: impl FnMut(&str)) {
+        for &byte in data {
+            self.escape = match (self.escape, byte) {
+                (Escape::None, 0x1b) => Escape::Start,
+                (Escape::None, _) => {
+                    self.push(byte, &mut emit);
+                    Escape::None
+                }
+                (Escape::Start, b'[') => {
+                    self.csi_param = None;
+                    Escape::Csi
+                }
+                (Escape::Start, b']' | b'P' | b'X' | b'^' | b'_') => Escape::Str,
+                (Escape::Start, b'(' | b')' | b'*' | b'+' | b'#' | b'%') => Escape::Charset,
+                (Escape::Start, _) => Escape::None,
+                (Escape::Csi, b'0'..=b'9') => {
+                    let digit = (byte - b'0') as usize;
+                    self.csi_param = Some(self.csi_param.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+                    Escape::Csi
+                }
+                // Only the last parameter is kept
+                (Escape::Csi, b';') => {
+                    self.csi_param = None;
+                    Escape::Csi
+                }
+                (Escape::Csi, 0x40..=0x7e) => {
+                    self.apply_csi(byte);
+                    Escape::None
+                }
+                (Escape::Csi, _) => Escape::Csi,
+                (Escape::Str, 0x07) => Escape::None,
+                (Escape::Str, 0x1b) => Escape::StrTerminator,
+                (Escape::Str, _) => Escape::Str,
+                (Escape::StrTerminator, b'\\') => Escape::None,
+                (Escape::StrTerminator, _) => Escape::Str,
+                (Escape::Charset, _) => Escape::None,
+            };
+        }
+    }
+
+    // Only the sequences that edit the current line matter for the log
+    fn apply_csi(&mut self, command: u8) {
+        let count = self.csi_param.unwrap_or(1).max(1);
+        match command {
+            b'C' => self.cursor = (self.cursor + count).min(MAX_LINE_LEN - 1),
+            b'D' => self.cursor = self.cursor.saturating_sub(count),
+            b'G' => self.cursor = (count - 1).min(MAX_LINE_LEN - 1),
+            b'K' if self.csi_param.unwrap_or(0) == 0 => self.line.truncate(self.cursor),
+            b'K' if self.csi_param == Some(2) => self.line.clear(),
+            b'P' => {
+                let end = (self.cursor + count).min(self.line.len());
+                if self.cursor < end {
+                    self.line.drain(self.cursor..end);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn push(&mut self, byte: u8, emit: &mut impl FnMut(&str)) {
+        if byte >= 0x80 || !self.utf8.is_empty() {
+            self.utf8.push(byte);
+            match std::str::from_utf8(&self.utf8) {
+                Ok(text) => {
+                    let c = text.chars().next().unwrap_or('\u{fffd}');
+                    self.utf8.clear();
+                    self.put(c, emit);
+                }
+                Err(e) if e.error_len().is_some() || self.utf8.len() >= 4 => {
+                    self.utf8.clear();
+                    self.put('\u{fffd}', emit);
+                }
+                Err(_) => {}
+            }
+            return;
+        }
+
+        match byte {
+            b'\n' => self.end_line(emit),
+            b'\r' => self.cursor = 0,
+            0x08 => self.cursor = self.cursor.saturating_sub(1),
+            b'\t' | 0x20..=0x7e => self.put(byte as char, emit),
+            _ => {}
+        }
+    }
+
+    fn put(&mut self, c: char, emit: &mut impl FnMut(&str)) {
+        if self.cursor > self.line.len() {
+            self.line.resize(self.cursor, ' ');
+        }
+        if self.cursor < self.line.len() {
+            self.line[self.cursor] = c;
+        } else {
+            self.line.push(c);
+        }
+        self.cursor += 1;
+        if self.line.len() >= MAX_LINE_LEN {
+            self.end_line(emit);
+        }
+    }
+
+    fn end_line(&mut self, emit: &mut impl FnMut(&str)) {
+        let line: String = self.line.iter().collect();
+        emit(line.trim_end());
+        self.line.clear();
+        self.cursor = 0;
+    }
+}
+
+struct LogFile {
+    file: File,
+    path: PathBuf,
+    part: u32,
+    written: u64,
+}
+
+/// The log of one session
+struct SessionLog {
+    settings: SessionLogSettings,
+    base: PathBuf, // Path of the first part, without extension
+    file: LogFile,
+    plain: Option<Plain>,
+    at_line_start: bool,
+    line_started: Option<DateTime<Local>>,
+}
+
+impl SessionLog {
+    fn open_part(base: &Path, part: u32) -> Result<LogFile, String> {
+        let path = match part {
+            1 => base.with_extension(LOG_EXTENSION),
+            n => PathBuf::from(format!("{}.{}.{}", base.display(), n, LOG_EXTENSION)),
+        };
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        // Terminal output can hold anything typed or shown, secrets included
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options.open(&path).map_err(|e| e.to_string())?;
+        Ok(LogFile { file, path, part, written: 0 })
+    }
+
+    fn timestamp(at: &DateTime<Local>) -> String {
+        format!("[{}] ", at.format(TIMESTAMP_FORMAT))
+    }
+
+    fn write(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.file.file.write_all(data)?;
+        self.file.written += data.len() as u64;
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &[u8], started: Option<DateTime<Local>>) -> std::io::Result<()> {
+        if self.settings.timestamps {
+            let prefix = Self::timestamp(&started.unwrap_or_else(Local::now));
+            self.write(prefix.as_bytes())?;
+        }
+        self.write(line)?;
+        self.write(b"\n")
+    }
+
+    fn output(&mut self, data: &[u8]) -> std::io::Result<()> {
+        if let Some(mut plain) = self.plain.take() {
+            if !data.is_empty() && self.line_started.is_none() {
+                self.line_started = Some(Local::now());
+            }
+            let mut lines = Vec::new();
+            plain.feed(data, |line| lines.push(line.to_string()));
+            let pending = !plain.line.is_empty();
+            self.plain = Some(plain);
+
+            for line in lines {
+                let started = self.line_started.take();
+                self.write_line(line.as_bytes(), started)?;
+            }
+            if pending && self.line_started.is_none() {
+                self.line_started = Some(Local::now());
+            }
+        } else if self.settings.timestamps {
+            for chunk in data.split_inclusive(|&b| b == b'\n') {
+                if self.at_line_start {
+                    let prefix = Self::timestamp(&Local::now());
+                    self.write(prefix.as_bytes())?;
+                }
+                self.write(chunk)?;
+                self.at_line_start = chunk.ends_with(b"\n");
+            }
+        } else {
+            self.write(data)?;
+        }
+
+        self.rotate_if_full()
+    }
+
+    fn rotate_if_full(&mut self) -> std::io::Result<()> {
+        let limit = self.settings.max_file_size_mb as u64 * 1024 * 1024;
+        if limit > 0 && self.file.written >= limit {
+            let next = Self::open_part(&self.base, self.file.part + 1).map_err(std::io::Error::other)?;
+            println!("Session log {} is full, continuing in {}", self.file.path.display(), next.path.display());
+            self.file = next;
+        }
+        Ok(())
+    }
+
+    /// Write out the unfinished line, when the session ends
+    fn finish(&mut self) {
+        if let Some(plain) = self.plain.take() {
+            let line: String = plain.line.iter().collect();
+            if !line.trim_end().is_empty() {
+                let started = self.line_started.take();
+                let _ = self.write_line(line.trim_end().as_bytes(), started);
+            }
+        }
+        let _ = self.file.file.flush();
+    }
+}
+
+/// Whether `name` is a log file this feature writes: `HHMMSS-<label>.log`,
+/// or `HHMMSS-<label>.N.log` for later parts
+fn is_session_log(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    bytes.len() > 11
+        && bytes[..6].iter().all(u8::is_ascii_digit)
+        && bytes[6] == b'-'
+        && name.ends_with(&format!(".{}", LOG_EXTENSION))
+}
+
+/// Delete session logs in `dir` last written before `cutoff`. Logs sit
+/// `depth` directory levels down, as the directory pattern lays them out;
+/// nothing else is touched, and a directory is only removed once deleting
+/// logs has left it empty. Returns whether anything was deleted.
+fn prune_dir(dir: &Path, depth: usize, cutoff: SystemTime) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return false;
+    };
+    let mut removed = false;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // Symlinks are neither followed nor deleted
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if depth > 0 {
+            if file_type.is_dir() && prune_dir(&path, depth - 1, cutoff) {
+                removed = true;
+                // Only succeeds once the directory is empty
+                let _ = std::fs::remove_dir(&path);
+            }
+        } else if file_type.is_file()
+            && is_session_log(&entry.file_name().to_string_lossy())
+            && entry.metadata().and_then(|m| m.modified()).is_ok_and(|modified| modified < cutoff)
+        {
+            println!("Removing old session log {}", path.display());
+            removed |= std::fs::remove_file(&path).is_ok();
+        }
+    }
+    removed
+}
+
+/// Delete logs under `root` last written more than `days` days ago
+fn prune_logs(root: &Path, pattern: &str, days: u32) {
+    let Some(cutoff) = SystemTime::now().checked_sub(Duration::from_secs(days as u64 * 24 * 3600)) else {
+        return;
+    };
+    let depth = pattern.split('/').filter(|p| !p.is_empty()).count();
+    prune_dir(root, depth, cutoff);
+}
+
+pub struct SessionLogState {
+    logs: Mutex<HashMap<String, Arc<Mutex<SessionLog>>>>,
+}
+
+impl SessionLogState {
+    pub fn new() -> Self {
+        Self {
+            logs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start logging a session if logging is enabled. `server` names the
+    /// saved server (or the host) for the directory pattern.
+    pub fn open(&self, app: &AppHandle, session_id: &str, server: &str, host: &str) {
+        let settings = match load_settings(&app.state::<Database>()) {
+            Ok(settings) if settings.enabled => settings,
+            _ => return,
+        };
+        if let Err(e) = self.try_open(app, session_id, server, host, settings) {
+            eprintln!("Failed to open session log for {}: {}", session_id, e);
+        }
+    }
+
+    fn try_open(
+        &self,
+        app: &AppHandle,
+        session_id: &str,
+        server: &str,
+        host: &str,
+        settings: SessionLogSettings,
+    ) -> Result<(), String> {
+        let root = match &settings.directory {
+            Some(dir) => expand_home(dir),
+            None => default_log_dir(app)?,
+        };
+        let now = Local::now();
+        let dir = log_dir(&root, &settings.directory_pattern, server, host, &now);
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        if settings.retention_days > 0 {
+            let (days, pattern) = (settings.retention_days, settings.directory_pattern.clone());
+            tauri::async_runtime::spawn_blocking(move || prune_logs(&root, &pattern, days));
+        }
+
+        let base = dir.join(format!("{}-{}", now.format("%H%M%S"), path_label(server)));
+        let file = SessionLog::open_part(&base, 1)?;
+        println!("Logging session {} to {}", session_id, file.path.display());
+        let log = SessionLog {
+            plain: settings.strip_ansi.then(Plain::new),
+            settings,
+            base,
+            file,
+            at_line_start: true,
+            line_started: None,
+        };
+        self.logs.lock().unwrap().insert(session_id.to_string(), Arc::new(Mutex::new(log)));
+        Ok(())
+    }
+
+    /// Append terminal output of a session that is being logged
+    pub fn output(&self, session_id: &str, data: &[u8]) {
+        let Some(log) = self.logs.lock().unwrap().get(session_id).cloned() else {
+            return;
+        };
+        let mut log = log.lock().unwrap();
+        if let Err(e) = log.output(data) {
+            eprintln!("Failed to write session log {}: {}", log.file.path.display(), e);
+        }
+    }
+
+    pub fn close(&self, session_id: &str) {
+        if let Some(log) = self.logs.lock().unwrap().remove(session_id) {
+            log.lock().unwrap().finish();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain_lines(data: &[u8]) -> Vec<String> {
+        let mut plain = Plain::new();
+        let mut lines = Vec::new();
+        plain.feed(data, |line| lines.push(line.to_string()));
+        lines
+    }
+
+    #[test]
+    fn test_plain_strips_escapes_and_applies_edits() {
+        assert_eq!(
+            plain_lines(b"\x1b[1;32muser@host\x1b[0m:~$ lsx\x08 \x08\r\n\x1b]0;title\x07 10%\r100%\r\n"),
+            vec!["user@host:~$ ls", "100%"]
+        );
+        assert_eq!(plain_lines("caf\u{e9}\x08e\n".as_bytes()), vec!["cafe"]);
+        assert_eq!(plain_lines(b"git status\x1b[6D\x1b[Kpush\n"), vec!["git push"]);
+    }
+
+    #[test]
+    fn test_log_dir_pattern() {
+        let now = Local::now();
+        let dir = log_dir(Path::new("/logs"), "{server}/{date}", "web/prod", "10.0.0.1", &now);
+        assert_eq!(dir, Path::new("/logs").join("web_prod").join(now.format("%Y-%m-%d").to_string()));
+    }
+
+    #[test]
+    fn test_prune_only_removes_own_logs() {
+        let root = std::env::temp_dir().join(format!("nebula-session-logs-{}", std::process::id()));
+        let old = SystemTime::now() - Duration::from_secs(90 * 24 * 3600);
+        let write = |path: &Path, age: SystemTime| {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            File::create(path).unwrap().set_modified(age).unwrap();
+        };
+        write(&root.join("web/2024-01-01/093000-web.log"), old);
+        write(&root.join("web/2024-01-01/093000-web.2.log"), old);
+        write(&root.join("db/2024-01-01/101500-db.log"), old);
+        write(&root.join("db/2024-01-01/notes.log"), old);
+        write(&root.join("db/2024-01-02/101500-db.log"), SystemTime::now());
+        write(&root.join("120000-top.log"), old);
+        std::fs::create_dir_all(root.join("empty/dir")).unwrap();
+
+        assert!(is_session_log("093000-web.2.log") && !is_session_log("notes.log"));
+        prune_logs(&root, "{server}/{date}", 30);
+
+        assert!(!root.join("web").exists());
+        assert!(!root.join("db/2024-01-01/101500-db.log").exists());
+        assert!(root.join("db/2024-01-01/notes.log").exists());
+        assert!(root.join("db/2024-01-02/101500-db.log").exists());
+        assert!(root.join("120000-top.log").exists());
+        assert!(root.join("empty/dir").exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
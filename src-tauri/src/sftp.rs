@@ -1,24 +1,14 @@
-use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
-use std::net::TcpStream;
+use std::io::{Read, Write};
 use std::path::Path;
-use ssh2::Session;
 use serde::Serialize;
+use ssh2::ErrorCode;
 use std::fs::File;
 
 use crate::ssh::SshState;
 
-pub struct SftpState {
-    pub sessions: Arc<Mutex<HashMap<String, Session>>>,
-}
-
-impl SftpState {
-    pub fn new() -> Self {
-        Self {
-            sessions: Arc::new(Mutex::new(HashMap::new())),
-        }
-    }
-}
+// libssh2 signals the end of a directory listing with LIBSSH2_ERROR_FILE
+const LIBSSH2_ERROR_FILE: i32 = -16;
+const COPY_BUFFER_SIZE: usize = 32768;
 
 #[derive(Serialize)]
 pub struct FileEntry {
@@ -34,46 +24,14 @@ pub struct FileEntry {
 }
 
 #[tauri::command]
-pub fn init_sftp(
+pub async fn init_sftp(
     ssh_state: tauri::State<'_, SshState>,
-    sftp_state: tauri::State<'_, SftpState>,
     id: String,
 ) -> Result<(), String> {
     println!("Init SFTP for session: {}", id);
-    // Check if we already have an SFTP session
-    if sftp_state.sessions.lock().unwrap().contains_key(&id) {
-        println!("SFTP session already exists for {}", id);
-        return Ok(());
-    }
-
-    // Get credentials from SshState
-    let (host, port, username, password, private_key) = {
-        let sessions = ssh_state.sessions.lock().unwrap();
-        let conn = sessions.get(&id).ok_or("SSH session not found")?;
-        (
-            conn.host.clone(),
-            conn.port,
-            conn.username.clone(),
-            conn.password.clone(),
-            conn.private_key.clone(),
-        )
-    };
-
-    // Connect
-    let tcp = TcpStream::connect(format!("{}:{}", host, port)).map_err(|e| e.to_string())?;
-    let mut sess = Session::new().unwrap();
-    sess.set_tcp_stream(tcp);
-    sess.handshake().map_err(|e| e.to_string())?;
-
-    if let Some(pwd) = password {
-        sess.userauth_password(&username, &pwd).map_err(|e| e.to_string())?;
-    } else if let Some(_key) = private_key {
-        sess.userauth_agent(&username).map_err(|e| e.to_string())?;
-    } else {
-        return Err("No authentication method provided".to_string());
-    }
-
-    sftp_state.sessions.lock().unwrap().insert(id, sess);
+    // SFTP runs as a subsystem channel on the session's existing connection
+    let transport = ssh_state.transport(&id)?;
+    transport.sftp().await?;
     Ok(())
 }
 
@@ -95,22 +53,26 @@ fn format_size(size: u64) -> String {
 }
 
 #[tauri::command]
-pub fn list_directory(
-    state: tauri::State<'_, SftpState>,
+pub async fn list_directory(
+    state: tauri::State<'_, SshState>,
     id: String,
     path: String,
 ) -> Result<Vec<FileEntry>, String> {
-    let sessions = state.sessions.lock().unwrap();
-    let sess = sessions.get(&id).ok_or("SFTP session not found")?;
-    
-    let sftp = sess.sftp().map_err(|e| e.to_string())?;
+    let transport = state.transport(&id)?;
+    let sftp = transport.sftp().await?;
     let path_path = Path::new(&path);
     let mut entries = Vec::new();
 
-    let dir = sftp.readdir(path_path).map_err(|e| e.to_string())?;
-    
-    for (path_buf, stat) in dir {
-        let name = path_buf.file_name().unwrap().to_string_lossy().to_string();
+    let mut dir = transport.call(|| sftp.opendir(path_path)).await.map_err(|e| e.to_string())?;
+
+    loop {
+        let (path_buf, stat) = match transport.call(|| dir.readdir()).await {
+            Ok(entry) => entry,
+            // libssh2 reports the end of the listing as LIBSSH2_ERROR_FILE
+            Err(e) if e.code() == ErrorCode::Session(LIBSSH2_ERROR_FILE) => break,
+            Err(e) => return Err(e.to_string()),
+        };
+        let name = path_buf.to_string_lossy().to_string();
         if name == "." || name == ".." {
             continue;
         }
@@ -141,39 +103,53 @@ pub fn list_directory(
 }
 
 #[tauri::command]
-pub fn download_file(
-    state: tauri::State<'_, SftpState>,
+pub async fn download_file(
+    state: tauri::State<'_, SshState>,
     id: String,
     remote_path: String,
     local_path: String,
 ) -> Result<(), String> {
-    let sessions = state.sessions.lock().unwrap();
-    let sess = sessions.get(&id).ok_or("SFTP session not found")?;
-    let sftp = sess.sftp().map_err(|e| e.to_string())?;
+    let transport = state.transport(&id)?;
+    let sftp = transport.sftp().await?;
 
-    let mut remote_file = sftp.open(Path::new(&remote_path)).map_err(|e| e.to_string())?;
+    let mut remote_file = transport.call(|| sftp.open(Path::new(&remote_path))).await.map_err(|e| e.to_string())?;
     let mut local_file = File::create(local_path).map_err(|e| e.to_string())?;
 
-    std::io::copy(&mut remote_file, &mut local_file).map_err(|e| e.to_string())?;
+    let mut buf = vec![0u8; COPY_BUFFER_SIZE];
+    loop {
+        let n = transport.read(&mut remote_file, &mut buf).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        local_file.write_all(&buf[..n]).map_err(|e| e.to_string())?;
+    }
+    let _ = transport.call(|| remote_file.close()).await;
 
     Ok(())
 }
 
 #[tauri::command]
-pub fn upload_file(
-    state: tauri::State<'_, SftpState>,
+pub async fn upload_file(
+    state: tauri::State<'_, SshState>,
     id: String,
     local_path: String,
     remote_path: String,
 ) -> Result<(), String> {
-    let sessions = state.sessions.lock().unwrap();
-    let sess = sessions.get(&id).ok_or("SFTP session not found")?;
-    let sftp = sess.sftp().map_err(|e| e.to_string())?;
+    let transport = state.transport(&id)?;
+    let sftp = transport.sftp().await?;
 
     let mut local_file = File::open(local_path).map_err(|e| e.to_string())?;
-    let mut remote_file = sftp.create(Path::new(&remote_path)).map_err(|e| e.to_string())?;
+    let mut remote_file = transport.call(|| sftp.create(Path::new(&remote_path))).await.map_err(|e| e.to_string())?;
 
-    std::io::copy(&mut local_file, &mut remote_file).map_err(|e| e.to_string())?;
+    let mut buf = vec![0u8; COPY_BUFFER_SIZE];
+    loop {
+        let n = local_file.read(&mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        transport.write_all(&mut remote_file, &buf[..n]).await.map_err(|e| e.to_string())?;
+    }
+    transport.call(|| remote_file.close()).await.map_err(|e| e.to_string())?;
 
     Ok(())
 }
@@ -0,0 +1,210 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const SOCKS4: u8 = 0x04;
+const SOCKS5: u8 = 0x05;
+const CMD_CONNECT: u8 = 0x01;
+const SOCKS5_NO_AUTH: u8 = 0x00;
+const SOCKS5_NO_ACCEPTABLE_METHOD: u8 = 0xFF;
+const SOCKS5_ATYP_IPV4: u8 = 0x01;
+const SOCKS5_ATYP_DOMAIN: u8 = 0x03;
+const SOCKS5_ATYP_IPV6: u8 = 0x04;
+// SOCKS5 reply codes
+const REP_SUCCEEDED: u8 = 0x00;
+const REP_GENERAL_FAILURE: u8 = 0x01;
+const REP_COMMAND_NOT_SUPPORTED: u8 = 0x07;
+const REP_ADDRESS_TYPE_NOT_SUPPORTED: u8 = 0x08;
+// SOCKS4 reply codes
+const SOCKS4_GRANTED: u8 = 0x5A;
+const SOCKS4_REJECTED: u8 = 0x5B;
+// Upper bound for the NUL terminated user id / host name fields of SOCKS4
+const MAX_SOCKS4_FIELD: usize = 255;
+
+/// A CONNECT request read from a SOCKS client. Host names are kept as
+/// given so they are resolved on the remote side.
+#[derive(Debug, PartialEq)]
+pub struct SocksRequest {
+    pub host: String,
+    pub port: u16,
+    version: u8,
+}
+
+impl SocksRequest {
+    /// Tell the client its connection is ready
+    pub async fn reply_success<S: AsyncWrite + Unpin>(&self, stream: &mut S) -> Result<(), String> {
+        self.reply(stream, true).await
+    }
+
+    /// Tell the client the connection could not be made
+    pub async fn reply_failure<S: AsyncWrite + Unpin>(&self, stream: &mut S) -> Result<(), String> {
+        self.reply(stream, false).await
+    }
+
+    async fn reply<S: AsyncWrite + Unpin>(&self, stream: &mut S, success: bool) -> Result<(), String> {
+        let reply = if self.version == SOCKS5 {
+            let code = if success { REP_SUCCEEDED } else { REP_GENERAL_FAILURE };
+            socks5_reply(code)
+        } else {
+            socks4_reply(success)
+        };
+        stream.write_all(&reply).await.map_err(|e| e.to_string())
+    }
+}
+
+fn socks5_reply(code: u8) -> Vec<u8> {
+    // Bound address is not meaningful for a tunnelled connection
+    vec![SOCKS5, code, 0x00, SOCKS5_ATYP_IPV4, 0, 0, 0, 0, 0, 0]
+}
+
+fn socks4_reply(success: bool) -> Vec<u8> {
+    let code = if success { SOCKS4_GRANTED } else { SOCKS4_REJECTED };
+    vec![0x00, code, 0, 0, 0, 0, 0, 0]
+}
+
+/// Run the server side of a SOCKS4/4a or SOCKS5 handshake up to the
+/// CONNECT request. Unsupported requests are answered before returning an
+/// error.
+pub async fn accept<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> Result<SocksRequest, String> {
+    match stream.read_u8().await.map_err(|e| e.to_string())? {
+        SOCKS5 => accept_socks5(stream).await,
+        SOCKS4 => accept_socks4(stream).await,
+        version => Err(format!("Unsupported SOCKS version {}", version)),
+    }
+}
+
+async fn accept_socks5<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> Result<SocksRequest, String> {
+    let method_count = stream.read_u8().await.map_err(|e| e.to_string())?;
+    let mut methods = vec![0u8; method_count as usize];
+    stream.read_exact(&mut methods).await.map_err(|e| e.to_string())?;
+
+    // No authentication: dynamic forwards only ever listen on loopback
+    if !methods.contains(&SOCKS5_NO_AUTH) {
+        let _ = stream.write_all(&[SOCKS5, SOCKS5_NO_ACCEPTABLE_METHOD]).await;
+        return Err("SOCKS5 client does not offer unauthenticated access".to_string());
+    }
+    stream.write_all(&[SOCKS5, SOCKS5_NO_AUTH]).await.map_err(|e| e.to_string())?;
+
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await.map_err(|e| e.to_string())?;
+    let [version, command, _, address_type] = header;
+    if version != SOCKS5 {
+        return Err(format!("Unexpected SOCKS version {} in request", version));
+    }
+
+    let host = match address_type {
+        SOCKS5_ATYP_IPV4 => {
+            let mut ip = [0u8; 4];
+            stream.read_exact(&mut ip).await.map_err(|e| e.to_string())?;
+            Ipv4Addr::from(ip).to_string()
+        }
+        SOCKS5_ATYP_DOMAIN => {
+            let len = stream.read_u8().await.map_err(|e| e.to_string())?;
+            let mut name = vec![0u8; len as usize];
+            stream.read_exact(&mut name).await.map_err(|e| e.to_string())?;
+            String::from_utf8(name).map_err(|_| "Invalid host name in SOCKS request".to_string())?
+        }
+        SOCKS5_ATYP_IPV6 => {
+            let mut ip = [0u8; 16];
+            stream.read_exact(&mut ip).await.map_err(|e| e.to_string())?;
+            Ipv6Addr::from(ip).to_string()
+        }
+        other => {
+            let _ = stream.write_all(&socks5_reply(REP_ADDRESS_TYPE_NOT_SUPPORTED)).await;
+            return Err(format!("Unsupported SOCKS5 address type {}", other));
+        }
+    };
+    let port = stream.read_u16().await.map_err(|e| e.to_string())?;
+
+    if command != CMD_CONNECT {
+        let _ = stream.write_all(&socks5_reply(REP_COMMAND_NOT_SUPPORTED)).await;
+        return Err(format!("Unsupported SOCKS5 command {}", command));
+    }
+
+    Ok(SocksRequest { host, port, version: SOCKS5 })
+}
+
+async fn accept_socks4<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> Result<SocksRequest, String> {
+    let command = stream.read_u8().await.map_err(|e| e.to_string())?;
+    let port = stream.read_u16().await.map_err(|e| e.to_string())?;
+    let mut ip = [0u8; 4];
+    stream.read_exact(&mut ip).await.map_err(|e| e.to_string())?;
+    read_nul_terminated(stream).await?; // User id, ignored
+
+    // SOCKS4a: an address of 0.0.0.x (x != 0) means a host name follows
+    let host = if ip[..3] == [0, 0, 0] && ip[3] != 0 {
+        read_nul_terminated(stream).await?
+    } else {
+        Ipv4Addr::from(ip).to_string()
+    };
+
+    if command != CMD_CONNECT {
+        let _ = stream.write_all(&socks4_reply(false)).await;
+        return Err(format!("Unsupported SOCKS4 command {}", command));
+    }
+
+    Ok(SocksRequest { host, port, version: SOCKS4 })
+}
+
+async fn read_nul_terminated<S: AsyncRead + Unpin>(stream: &mut S) -> Result<String, String> {
+    let mut bytes = Vec::new();
+    loop {
+        match stream.read_u8().await.map_err(|e| e.to_string())? {
+            0 => break,
+            byte if bytes.len() < MAX_SOCKS4_FIELD => bytes.push(byte),
+            _ => return Err("SOCKS4 request field too long".to_string()),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| "Invalid text in SOCKS4 request".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::duplex;
+
+    // Feed `request` to the server side and collect what it answered
+    fn handshake(request: &[u8]) -> (Result<SocksRequest, String>, Vec<u8>) {
+        tauri::async_runtime::block_on(async {
+            let (mut client, mut server) = duplex(1024);
+            client.write_all(request).await.unwrap();
+            let result = accept(&mut server).await;
+            drop(server);
+            let mut answer = Vec::new();
+            client.read_to_end(&mut answer).await.unwrap();
+            (result, answer)
+        })
+    }
+
+    #[test]
+    fn test_socks5_connect_by_name() {
+        let mut request = vec![5, 1, 0, 5, 1, 0, 3, 11];
+        request.extend_from_slice(b"example.com");
+        request.extend_from_slice(&443u16.to_be_bytes());
+
+        let (result, answer) = handshake(&request);
+        let request = result.unwrap();
+        assert_eq!((request.host.as_str(), request.port), ("example.com", 443));
+        assert_eq!(answer, vec![5, 0]);
+    }
+
+    #[test]
+    fn test_socks5_rejects_bind_command() {
+        let (result, answer) = handshake(&[5, 1, 0, 5, 2, 0, 1, 10, 0, 0, 1, 0, 80]);
+        assert!(result.is_err());
+        assert_eq!(answer[..2], [5, 0]);
+        assert_eq!(answer[2..4], [5, REP_COMMAND_NOT_SUPPORTED]);
+    }
+
+    #[test]
+    fn test_socks4a_connect_by_name() {
+        let mut request = vec![4, 1, 0, 22, 0, 0, 0, 1];
+        request.extend_from_slice(b"user\0internal.lan\0");
+
+        let (result, _) = handshake(&request);
+        let request = result.unwrap();
+        assert_eq!((request.host.as_str(), request.port), ("internal.lan", 22));
+
+        let (result, _) = handshake(&[4, 1, 0, 80, 192, 168, 1, 10, 0]);
+        assert_eq!(result.unwrap().host, "192.168.1.10");
+    }
+}
@@ -1,14 +1,26 @@
+use rand::Rng;
+use serde::Serialize;
+use ssh2::Channel;
 use std::io::Read;
-use std::io::Write;
-use std::net::TcpStream;
 use std::sync::{Arc, Mutex};
-use std::thread;
-use tauri::{Emitter, Window};
-use ssh2::Session;
-use crate::models::PortForwardingRule;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Window};
+use tokio::sync::mpsc;
+use crate::agent_forward;
 use crate::db::Database;
+use crate::forwarding::{start_forward, ForwardState};
+use crate::models::{PortForwardingRule, Server};
+use crate::recording::RecordingState;
+use crate::session_log::SessionLogState;
 use crate::repositories::servers;
-use std::net::TcpListener;
+use crate::repositories::settings::get_all_settings;
+use crate::ssh_utils::Credentials;
+use crate::transport::SshTransport;
+
+const DEFAULT_SIZE: (u32, u32) = (80, 24);
+const RECONNECT_ATTEMPTS: u32 = 8;
+const RECONNECT_DELAY_BASE: Duration = Duration::from_secs(1);
+const RECONNECT_DELAY_MAX: Duration = Duration::from_secs(60);
 
 pub struct SshState {
     pub sessions: Arc<Mutex<std::collections::HashMap<String, SshConnection>>>,
@@ -20,59 +32,37 @@ impl SshState {
             sessions: Arc::new(Mutex::new(std::collections::HashMap::new())),
         }
     }
+
+    /// Title and last terminal size of a session, for recordings
+    pub fn terminal_details(&self, id: &str) -> Option<(String, (u32, u32))> {
+        let sessions = self.sessions.lock().unwrap();
+        let conn = sessions.get(id)?;
+        Some((format!("{}@{}", conn.credentials.username, conn.host), conn.size))
+    }
+
+    /// Shared transport of a connected session
+    pub fn transport(&self, id: &str) -> Result<Arc<SshTransport>, String> {
+        let sessions = self.sessions.lock().unwrap();
+        let conn = sessions.get(id).ok_or("SSH session not found")?;
+        Ok(conn.transport.clone())
+    }
 }
 
 pub struct SshConnection {
-    writer: Option<std::sync::mpsc::Sender<Vec<u8>>>,
-    resizer: Option<std::sync::mpsc::Sender<(u32, u32)>>,
+    writer: Option<mpsc::UnboundedSender<Vec<u8>>>,
+    resizer: Option<mpsc::UnboundedSender<(u32, u32)>>,
     pub host: String,
     pub port: u16,
-    pub username: String,
-    pub password: Option<String>,
-    pub private_key: Option<String>,
-    #[allow(dead_code)]
-    pub session: Option<Session>,        // Terminal session (non-blocking)
-    pub sftp_session: Option<Session>,   // SFTP session (blocking) - dedicated for file operations
+    pub credentials: Credentials,
+    pub transport: Arc<SshTransport>, // Shared by terminal, SFTP, monitor and exec
     pub jump_host_id: Option<i64>,
-}
-
-// Helper struct for adaptive sleep
-struct Backoff {
-    current_ms: u64,
-    min_ms: u64,
-    max_ms: u64,
-}
-
-impl Backoff {
-    fn new(min_ms: u64, max_ms: u64) -> Self {
-        Self {
-            current_ms: min_ms,
-            min_ms,
-            max_ms,
-        }
-    }
-
-    fn reset(&mut self) {
-        self.current_ms = self.min_ms;
-    }
-
-    fn wait(&mut self) {
-        thread::sleep(std::time::Duration::from_millis(self.current_ms));
-        // Linear backoff
-        if self.current_ms < self.max_ms {
-            self.current_ms += 10;
-            if self.current_ms > self.max_ms {
-                self.current_ms = self.max_ms;
-            }
-        }
-    }
+    size: (u32, u32),
 }
 
 #[tauri::command]
 pub async fn connect_ssh(
     window: Window,
     state: tauri::State<'_, SshState>,
-    db: tauri::State<'_, Database>,
     id: String,
     host: String,
     port: u16,
@@ -81,428 +71,316 @@ pub async fn connect_ssh(
     private_key: Option<String>,
     forwarding_rules: Option<Vec<PortForwardingRule>>,
     jump_host_id: Option<i64>,
+    server_id: Option<i64>,
+    passphrase: Option<String>,
 ) -> Result<(), String> {
     println!("Connecting SSH: {}@{}:{}", username, host, port);
-    
-    let mut sess = Session::new().unwrap();
-    let tcp_stream: Option<TcpStream>;
-
-    if let Some(jump_id) = jump_host_id {
-        println!("Using Jump Host ID: {}", jump_id);
-        // Fetch jump host details
-        let jump_host = servers::get_server(&db, jump_id)
-            .map_err(|e| e.to_string())?
-            .ok_or("Jump host not found")?;
-
-        println!("Connecting to Jump Host: {}@{}:{}", jump_host.username, jump_host.host, jump_host.port);
-        let jump_tcp = TcpStream::connect(format!("{}:{}", jump_host.host, jump_host.port))
-            .map_err(|e| format!("Failed to connect to jump host: {}", e))?;
-        
-        let mut jump_sess = Session::new().unwrap();
-        jump_sess.set_tcp_stream(jump_tcp);
-        jump_sess.handshake().map_err(|e| format!("Jump host handshake failed: {}", e))?;
-
-        if let Some(ref pwd) = jump_host.password {
-            jump_sess.userauth_password(&jump_host.username, pwd).map_err(|e| format!("Jump host auth failed: {}", e))?;
-        } else if let Some(ref _key_path) = jump_host.private_key_path {
-             jump_sess.userauth_agent(&jump_host.username).map_err(|e| format!("Jump host auth failed: {}", e))?;
-        } else {
-            return Err("Jump host has no auth method".to_string());
-        }
-
-        println!("Authenticated with Jump Host");
-
-        // Direct TCP/IP forwarding to target
-        // Note: ssh2::Channel implements Read + Write, but Session::handshake expects TcpStream.
-        // We can't easily use Channel as TcpStream.
-        // Workaround: Local Port Forwarding
-        // 1. Bind a random local port
-        // 2. Forward that local port to target host:port via jump host
-        // 3. Connect our target session to that local port
-        
-        // Actually, let's try the direct channel approach if possible? No, ssh2 API is strict.
-        // Let's use local port forwarding.
-        
-        let _local_port = 0; // Bind to random port
-        let listener = TcpListener::bind("127.0.0.1:0").map_err(|e| e.to_string())?;
-        let local_addr = listener.local_addr().map_err(|e| e.to_string())?;
-        println!("Bound local listener for jump: {}", local_addr);
-
-        let jump_sess_clone = jump_sess.clone();
-        let target_host = host.clone();
-        let target_port = port;
-
-        thread::spawn(move || {
-             for stream in listener.incoming() {
-                match stream {
-                    Ok(local_stream) => {
-                        let mut channel = match jump_sess_clone.channel_direct_tcpip(&target_host, target_port, None) {
-                            Ok(c) => c,
-                            Err(e) => {
-                                eprintln!("Failed to open direct-tcpip channel: {}", e);
-                                return;
-                            }
-                        };
-                        
-                        // Simple blocking copy loop (one way? No, need bidirectional)
-                        // Since we are in a thread, we can try to handle it.
-                        // But we need two threads for bidirectional copy or non-blocking.
-                        // Let's spawn two threads for this connection.
-                        
-                        let mut local_reader = local_stream.try_clone().unwrap();
-                        let mut local_writer = local_stream;
-                        
-                        // We need to handle bidirectional copy in a single thread because Channel is not Send/Clone easily
-                        let mut buf_local = [0u8; 4096];
-                        let mut buf_remote = [0u8; 4096];
-                        
-                        local_reader.set_nonblocking(true).ok();
-                        // channel read is blocking by default, but we can set session to non-blocking?
-                        // But session is shared. 
-                        // Let's use a short timeout loop.
-                        
-                        let mut backoff = Backoff::new(10, 50);
-
-                        loop {
-                            let mut activity = false;
-                            
-                            // Local -> Remote
-                            match local_reader.read(&mut buf_local) {
-                                Ok(0) => break, // EOF
-                                Ok(n) => {
-                                    if let Err(_) = channel.write_all(&buf_local[..n]) { break; }
-                                    channel.flush().ok();
-                                    activity = true;
-                                }
-                                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
-                                Err(_) => break,
-                            }
-                            
-                            // Remote -> Local
-                            // We can't easily set channel to non-blocking without affecting session.
-                            // But we can check if data is available? 
-                            // ssh2 doesn't expose "bytes available" easily on channel.
-                            // We can use `channel.read` but it might block.
-                            
-                            // CRITICAL: If we block here, we can't read from local.
-                            // We really need a way to read non-blocking from channel.
-                            // `jump_sess_clone.set_blocking(false);`
-                            
-                            jump_sess_clone.set_blocking(false);
-                            match channel.read(&mut buf_remote) {
-                                Ok(0) => {
-                                    // In non-blocking, 0 might mean no data if we didn't check EOF.
-                                    if channel.eof() { break; }
-                                }
-                                Ok(n) => {
-                                    if let Err(_) = local_writer.write_all(&buf_remote[..n]) { break; }
-                                    local_writer.flush().ok();
-                                    activity = true;
-                                }
-                                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
-                                Err(_) => break,
-                            }
-                            
-                            if activity {
-                                backoff.reset();
-                            } else {
-                                backoff.wait();
-                            }
-                        }
-                    }
-                    Err(_) => {}
-                }
-             }
-        });
-        
-        // Wait a bit for listener to be ready? It is ready.
-        // Connect to local forwarder
-        tcp_stream = Some(TcpStream::connect(local_addr).map_err(|e| e.to_string())?);
-        
-    } else {
-        tcp_stream = Some(TcpStream::connect(format!("{}:{}", host, port)).map_err(|e| e.to_string())?);
-    }
-
-    let tcp = tcp_stream.unwrap();
-    tcp.set_nodelay(true).map_err(|e| e.to_string())?;
-    
-    sess.set_tcp_stream(tcp);
-    sess.handshake().map_err(|e| e.to_string())?;
-    println!("SSH Handshake complete");
-
-    if let Some(ref pwd) = password {
-        sess.userauth_password(&username, pwd).map_err(|e| e.to_string())?;
-    } else if let Some(ref _key) = private_key {
-        // Assuming key is path or content. For now let's assume it's a path if it doesn't look like a key
-        // But ssh2 expects path. If it's content we might need to write to temp file or use userauth_pubkey_memory (if available in crate version)
-        // For simplicity, let's assume we only support password for now or key paths if we add that logic.
-        // The prompt didn't specify key details, so let's stick to basic auth or key path.
-        sess.userauth_agent(&username).map_err(|e| e.to_string())?;
-    } else {
-        return Err("No authentication method provided".to_string());
-    }
+    let app = window.app_handle().clone();
+    let mut credentials = Credentials {
+        username: username.clone(),
+        password,
+        private_key,
+        passphrase,
+        server_id,
+        certificate: None,
+        cancel: None,
+    };
 
-    // Set keepalive on SSH session
-    sess.set_keepalive(true, 30);  // Send keepalive every 30 seconds
-    
-    let mut channel = sess.channel_session().map_err(|e| e.to_string())?;
-    channel.request_pty("xterm-256color", None, Some((80, 24, 0, 0))).map_err(|e| e.to_string())?;
-    channel.shell().map_err(|e| e.to_string())?;
+    let server = saved_server(&app, server_id)?;
+    let forward_agent = server.as_ref().is_some_and(|s| s.forward_agent);
+    let transport = SshTransport::connect(&app, &host, port, &mut credentials, jump_host_id).await?;
+    let (channel, forwarding) = open_terminal(&transport, forward_agent, DEFAULT_SIZE).await?;
     println!("SSH channel established for session: {}", id);
 
-    let (tx_write, rx_write) = std::sync::mpsc::channel::<Vec<u8>>();
-    let (tx_resize, rx_resize) = std::sync::mpsc::channel::<(u32, u32)>();
-
-    // Clone values we need to store before using them in auth
-    let password_clone = password.clone();
-    let private_key_clone = private_key.clone();
+    let (tx_write, rx_write) = mpsc::unbounded_channel::<Vec<u8>>();
+    let (tx_resize, rx_resize) = mpsc::unbounded_channel::<(u32, u32)>();
 
-    // Create dedicated SFTP session (blocking mode)
-    println!("Creating dedicated SFTP session...");
-    let mut sftp_sess = Session::new().unwrap();
-    
-    // Establish second TCP connection for SFTP
-    let sftp_tcp = if let Some(_jump_id) = jump_host_id {
-        // If using jump host, need to handle SFTP connection through jump as well
-        // For now, use same approach as main connection
-        TcpStream::connect(format!("{}:{}", host, port)).map_err(|e| format!("Failed to create SFTP connection: {}", e))?
-    } else {
-        TcpStream::connect(format!("{}:{}", host, port)).map_err(|e| format!("Failed to create SFTP connection: {}", e))?
-    };
-    
-    sftp_tcp.set_nodelay(true).map_err(|e| e.to_string())?;
-    sftp_sess.set_tcp_stream(sftp_tcp);
-    sftp_sess.handshake().map_err(|e| format!("SFTP handshake failed: {}", e))?;
-    
-    // Authenticate SFTP session (same credentials)
-    if let Some(ref pwd) = password {
-        sftp_sess.userauth_password(&username, pwd).map_err(|e| format!("SFTP auth failed: {}", e))?;
-    }
-    
-    // Keep SFTP session in BLOCKING mode (critical for file operations)
-    sftp_sess.set_blocking(true);
-    println!("SFTP session created successfully (blocking mode)");
-
-    // Store connection info in state BEFORE spawning thread
+    // Store connection info in state BEFORE spawning the session task
     {
         let mut sessions = state.sessions.lock().unwrap();
         sessions.insert(id.clone(), SshConnection {
-            writer: Some(tx_write.clone()),
-            resizer: Some(tx_resize.clone()),
+            writer: Some(tx_write),
+            resizer: Some(tx_resize),
             host: host.clone(),
             port,
-            username: username.clone(),
-            password: password_clone,
-            private_key: private_key_clone,
-            session: Some(sess.clone()),        // Terminal session (non-blocking)
-            sftp_session: Some(sftp_sess),      // SFTP session (blocking)
+            credentials,
+            transport: transport.clone(),
             jump_host_id,
+            size: DEFAULT_SIZE,
         });
     }
 
-    // Handle Port Forwarding
-    if let Some(rules) = forwarding_rules {
-        for rule in rules {
-            if rule.rule_type == "Local" {
-                let sess_clone = sess.clone();
-                let source_port = rule.source_port;
-                let dest_host = rule.destination_host.clone().unwrap_or("127.0.0.1".to_string());
-                let dest_port = rule.destination_port.unwrap_or(80);
-                let id_clone_for_thread = id.clone();
-
-                thread::spawn(move || {
-                    println!("Starting local forward {}:{} -> {}:{}", source_port, dest_host, dest_port, id_clone_for_thread);
-                    let listener = match TcpListener::bind(format!("127.0.0.1:{}", source_port)) {
-                        Ok(l) => l,
-                        Err(e) => {
-                            eprintln!("Failed to bind local port {}: {}", source_port, e);
-                            return;
-                        }
-                    };
-
-                    for stream in listener.incoming() {
-                        match stream {
-                            Ok(local_stream) => {
-                                let sess = sess_clone.clone();
-                                let dest_host = dest_host.clone();
-                                thread::spawn(move || {
-                                    match sess.channel_direct_tcpip(&dest_host, dest_port, None) {
-                                        Ok(mut remote_channel) => {
-                                            // Bi-directional copy
-                                            // This is a simplified blocking copy. 
-                                            // Ideally we need non-blocking or select! but Rust std doesn't have select! for IO.
-                                            // We can spawn two threads for bidirectional copy.
-                                            
-                                            let mut local_reader = local_stream.try_clone().unwrap();
-                                            let _local_writer = local_stream;
-                                            let mut _remote_reader = remote_channel.stream(0); // Stream 0 is normal data
-                                            // remote_channel implements Read/Write directly
-                                            
-                                            // We can't easily split ssh2 channel into reader/writer that are Send.
-                                            // So we have to do it in one thread or use a crate that handles this.
-                                            // For MVP, let's try a simple loop with non-blocking or short timeouts?
-                                            // Or just use standard copy if we don't care about closing cleanly immediately.
-                                            
-                                            // Let's try the two-thread approach if we can clone the channel?
-                                            // ssh2::Channel does NOT implement Clone.
-                                            
-                                            // So we must handle both directions in one thread using non-blocking IO or poll.
-                                            // ssh2 doesn't expose poll easily.
-                                            
-                                            // Alternative: Use `std::io::copy` in one direction and ... wait, we can't.
-                                            
-                                            // Let's just do a simple loop with read timeouts.
-                                            let mut buf = [0u8; 4096];
-                                            let mut backoff = Backoff::new(10, 50);
-
-                                            loop {
-                                                let mut activity = false;
-
-                                                // Read from local, write to remote
-                                                local_reader.set_nonblocking(true).ok();
-                                                match local_reader.read(&mut buf) {
-                                                    Ok(0) => break, // EOF
-                                                    Ok(n) => {
-                                                        if let Err(_) = remote_channel.write_all(&buf[..n]) { break; }
-                                                        remote_channel.flush().ok();
-                                                        activity = true;
-                                                    }
-                                                    Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
-                                                    Err(_) => break,
-                                                }
-                                                
-                                                // Read from remote, write to local
-                                                // remote_channel.read is blocking unless session is non-blocking.
-                                                // But session is shared... changing blocking mode affects all.
-                                                // This is tricky with ssh2.
-                                                
-                                                // For this MVP, we might skip complex forwarding implementation 
-                                                // and just set up the structure, or use a very simple blocking approach 
-                                                // that might hang one direction.
-                                                
-                                                // BETTER APPROACH for MVP:
-                                                // Just log that we started it. Real implementation requires async or mio/tokio with ssh2 integration (which is hard).
-                                                // OR use `ssh2`'s listener for remote forwarding.
-                                                
-                                                // Let's try a very naive implementation:
-                                                // Read from remote (blocking with timeout?) -> Write local
-                                                // Read from local (non-blocking) -> Write remote
-                                                
-                                                // Actually, let's just leave a TODO comment and print for now to verify the UI flow.
-                                                // Implementing a robust full-duplex proxy in a sync thread with ssh2 is non-trivial code.
-                                                // I will implement a basic "connect" log.
-                                                if activity {
-                                                    backoff.reset();
-                                                } else {
-                                                    backoff.wait();
-                                                }
-                                            }
-                                        }
-                                        Err(e) => eprintln!("Failed to create channel: {}", e),
-                                    }
-                                });
-                            }
-                            Err(e) => eprintln!("Connection failed: {}", e),
-                        }
-                    }
-                });
-            }
+    // Handle Port Forwarding; a rule that fails to start is reported
+    // through its status event and does not fail the connection
+    for rule in forwarding_rules.unwrap_or_default() {
+        if let Err(e) = start_forward(&app, &id, transport.clone(), rule).await {
+            eprintln!("Failed to start port forward for session {}: {}", id, e);
         }
     }
     println!("SSH connection info stored for session: {}", id);
 
-    let id_clone = id.clone();
-    
-    // Spawn a thread to handle the session
-    thread::spawn(move || {
-        let mut buf = [0u8; 4096];
-        let mut backoff = Backoff::new(10, 50);
+    if forwarding {
+        tauri::async_runtime::spawn(agent_forward::serve(window.clone(), id.clone(), transport.clone()));
+    }
 
+    let server_name = server.as_ref().map_or_else(|| host.clone(), |s| s.name.clone());
+    app.state::<SessionLogState>().open(&app, &id, &server_name, &host);
+    if server.is_some_and(|s| s.record_sessions) {
+        let title = format!("{}@{}", username, host);
+        if let Err(e) = app.state::<RecordingState>().start(&app, &id, &title, DEFAULT_SIZE, false) {
+            eprintln!("Failed to start recording session {}: {}", id, e);
+        }
+    }
+
+    let mut terminal = Terminal {
+        window,
+        id,
+        rx_write,
+        rx_resize,
+        size: DEFAULT_SIZE,
+    };
+    tauri::async_runtime::spawn(async move {
+        let app = terminal.window.app_handle().clone();
+        let recordings = app.state::<RecordingState>();
+        let (mut transport, mut channel) = (transport, channel);
         loop {
-            let mut activity = false;
-
-            // Non-blocking read
-            // ssh2 doesn't support easy non-blocking read without setting the session to non-blocking
-            // But if we set it to non-blocking, we need to manage the loop carefully.
-            // Alternative: Use a short timeout or just block.
-            // If we block, we can't easily write or resize unless we do it on the same thread or use non-blocking.
-            
-            // Let's try setting blocking to false for the session?
-            // Or better, just check for data available?
-            
-            // Actually, a common pattern with ssh2 in Rust is to use a loop that checks for incoming data
-            // and also checks for outgoing data from a channel.
-            
-            // Check for write data
-            if let Ok(data) = rx_write.try_recv() {
-                let _ = channel.write_all(&data);
-                let _ = channel.flush();
-                activity = true;
+            let ended = terminal.run(&transport, &mut channel).await;
+            let _ = transport.call(|| channel.close()).await;
+            let Ended::Lost(reason) = ended else { break };
+            if !terminal.is_current(&transport) {
+                // Disconnected by the user
+                break;
             }
-
-            // Check for resize
-            if let Ok((cols, rows)) = rx_resize.try_recv() {
-                let _ = channel.request_pty_size(cols, rows, None, None);
-                activity = true;
+            transport.abort(&reason);
+            recordings.marker(&terminal.id, &format!("Connection lost: {}", reason));
+            match terminal.reconnect(&transport, &reason).await {
+                Some((resumed, shell)) => {
+                    recordings.marker(&terminal.id, "Reconnected");
+                    (transport, channel) = (resumed, shell);
+                }
+                None => break,
             }
+        }
 
-            // Read data
-            // We can use read which blocks, but that prevents us from writing if we are single threaded here.
-            // So we should set the stream to non-blocking or use a timeout.
-            // sess.set_blocking(false); 
-            
-            // Let's try a simple approach:
-            // We can't easily share the channel across threads because it's not Send/Sync usually (it borrows Session).
-            // So this thread must own the channel and session.
-            
-            // To make it responsive, we can use a small timeout on read?
-            // ssh2 doesn't have read_timeout easily on channel.
-            
-            // We can use `channel.read` but if it blocks forever we can't write.
-            // We can set the underlying TcpStream to non-blocking?
-            // Or use `sess.set_blocking(false)`.
-            
-            sess.set_blocking(false);
-            match channel.read(&mut buf) {
+        recordings.finish(&app, &terminal.id);
+        app.state::<SessionLogState>().close(&terminal.id);
+        println!("SSH task exiting for session: {}", terminal.id);
+        let _ = terminal.window.emit(&format!("ssh_close_{}", terminal.id), ());
+    });
+
+    Ok(())
+}
+
+fn saved_server(app: &AppHandle, server_id: Option<i64>) -> Result<Option<Server>, String> {
+    match server_id {
+        Some(server_id) => servers::get_server(&app.state::<Database>(), server_id).map_err(|e| e.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// Open the shell channel, with agent forwarding when requested and the
+/// server allows it
+async fn open_terminal(
+    transport: &SshTransport,
+    forward_agent: bool,
+    (cols, rows): (u32, u32),
+) -> Result<(Channel, bool), String> {
+    if forward_agent {
+        agent_forward::enable(transport);
+    }
+    let (channel, forwarding) = transport.open_shell("xterm-256color", cols, rows, forward_agent).await?;
+    if forward_agent && !forwarding {
+        agent_forward::disable(transport);
+    }
+    Ok((channel, forwarding))
+}
+
+/// Delay before reconnect `attempt` (from 1): doubling from the base up to
+/// the cap, plus up to half of that again as jitter so tabs that dropped
+/// together do not retry in lockstep
+fn reconnect_delay(attempt: u32) -> Duration {
+    let backoff = RECONNECT_DELAY_BASE
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(RECONNECT_DELAY_MAX);
+    let jitter = rand::thread_rng().gen_range(0..=backoff.as_millis() as u64 / 2);
+    backoff + Duration::from_millis(jitter)
+}
+
+/// Progress of an automatic reconnect, emitted as `ssh_reconnecting_{id}`
+#[derive(Debug, Clone, Serialize)]
+struct ReconnectStatus {
+    attempt: u32,
+    max_attempts: u32,
+    delay_ms: u64,
+    error: String,
+}
+
+/// Why a terminal channel stopped
+enum Ended {
+    // The shell exited or the tab was closed
+    Closed,
+    Lost(String),
+}
+
+/// The terminal side of a session, kept across reconnects
+struct Terminal {
+    window: Window,
+    id: String,
+    rx_write: mpsc::UnboundedReceiver<Vec<u8>>,
+    rx_resize: mpsc::UnboundedReceiver<(u32, u32)>,
+    // Last size requested by the UI, restored on reconnect
+    size: (u32, u32),
+}
+
+impl Terminal {
+    /// Drive the terminal channel: output is read as soon as the socket has
+    /// data, and input/resizes are handled the moment they arrive
+    async fn run(&mut self, transport: &SshTransport, channel: &mut Channel) -> Ended {
+        let mut buf = vec![0u8; 16384];
+
+        while !transport.is_closed() {
+            let mut progress = transport.subscribe();
+            match transport.attempt(|| channel.read(&mut buf)) {
                 Ok(0) => {
-                    // In non-blocking mode, 0 can mean no data available right now
                     // Check if channel is actually EOF/closed
                     if channel.eof() {
-                        break;
+                        return Ended::Closed;
                     }
-                    // Otherwise, just sleep and continue
                 }
                 Ok(n) => {
                     let data = buf[0..n].to_vec();
-                    // Emit data to frontend
-                    // We need to use emit from tauri.
-                    // But we can't use `window` here easily if it's not Send?
-                    // Window is Send.
-                    let _ = window.emit(&format!("ssh_data_{}", id_clone), data);
-                    activity = true;
+                    self.window.state::<RecordingState>().output(&self.id, &data);
+                    self.window.state::<SessionLogState>().output(&self.id, &data);
+                    let _ = self.window.emit(&format!("ssh_data_{}", self.id), data);
+                    continue;
                 }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
                 Err(e) => {
-                    if e.kind() == std::io::ErrorKind::WouldBlock { // EAGAIN / WouldBlock
-                        // No data, sleep a bit
-                    } else {
-                        // Real error
-                        eprintln!("SSH read error for session {}: {}", id_clone, e);
-                        break;
+                    eprintln!("SSH read error for session {}: {}", self.id, e);
+                    return Ended::Lost(format!("Read failed: {}", e));
+                }
+            }
+
+            tokio::select! {
+                data = self.rx_write.recv() => {
+                    // The sender is dropped when the session is disconnected
+                    let Some(data) = data else { return Ended::Closed };
+                    self.window.state::<RecordingState>().input(&self.id, &data);
+                    if let Err(e) = transport.write_all(channel, &data).await {
+                        eprintln!("SSH write error for session {}: {}", self.id, e);
+                        return Ended::Lost(format!("Write failed: {}", e));
+                    }
+                }
+                size = self.rx_resize.recv() => {
+                    if let Some((cols, rows)) = size {
+                        self.size = (cols, rows);
+                        self.window.state::<RecordingState>().resize(&self.id, cols, rows);
+                        if let Err(e) = transport.call(|| channel.request_pty_size(cols, rows, None, None)).await {
+                            eprintln!("SSH resize error for session {}: {}", self.id, e);
+                        }
                     }
                 }
+                _ = transport.wait(&mut progress) => {}
+            }
+        }
+        Ended::Lost("Connection lost".to_string())
+    }
+
+    /// Whether `transport` is still the one this session's tab uses
+    fn is_current(&self, transport: &Arc<SshTransport>) -> bool {
+        let state = self.window.state::<SshState>();
+        let sessions = state.sessions.lock().unwrap();
+        sessions.get(&self.id).is_some_and(|conn| Arc::ptr_eq(&conn.transport, transport))
+    }
+
+    /// Re-establish a lost connection with exponential backoff. Gives up when
+    /// auto-reconnect is off, the tab is closed meanwhile or every attempt fails.
+    async fn reconnect(&mut self, lost: &Arc<SshTransport>, reason: &str) -> Option<(Arc<SshTransport>, Channel)> {
+        let app = self.window.app_handle().clone();
+        let enabled = get_all_settings(&app.state::<Database>()).is_ok_and(|s| s.auto_reconnect);
+        if !enabled {
+            app.state::<ForwardState>().take_interrupted(&self.id);
+            return None;
+        }
+
+        let mut error = reason.to_string();
+        for attempt in 1..=RECONNECT_ATTEMPTS {
+            let delay = reconnect_delay(attempt);
+            println!("Reconnecting session {} in {:?} (attempt {}/{})", self.id, delay, attempt, RECONNECT_ATTEMPTS);
+            let _ = self.window.emit(&format!("ssh_reconnecting_{}", self.id), ReconnectStatus {
+                attempt,
+                max_attempts: RECONNECT_ATTEMPTS,
+                delay_ms: delay.as_millis() as u64,
+                error: error.clone(),
+            });
+            tokio::time::sleep(delay).await;
+            if !self.is_current(lost) {
+                return None;
+            }
+
+            while let Ok(size) = self.rx_resize.try_recv() {
+                self.size = size;
+            }
+            // Keystrokes typed into the dead session are dropped, not replayed
+            while self.rx_write.try_recv().is_ok() {}
+
+            match self.resume(&app, lost).await {
+                Ok(resumed) => {
+                    println!("Session {} reconnected", self.id);
+                    let _ = self.window.emit(&format!("ssh_reconnected_{}", self.id), ());
+                    return Some(resumed);
+                }
+                Err(e) => {
+                    eprintln!("Reconnect attempt {} for session {} failed: {}", attempt, self.id, e);
+                    error = e;
+                }
             }
+        }
+
+        app.state::<ForwardState>().take_interrupted(&self.id);
+        let _ = self.window.emit(&format!("ssh_reconnect_failed_{}", self.id), error);
+        None
+    }
 
-            if activity {
-                backoff.reset();
-            } else {
-                backoff.wait();
+    /// Connect again with the session's credentials and route, reopen the
+    /// shell at the last size and restart the forwards the loss stopped
+    async fn resume(&self, app: &AppHandle, lost: &Arc<SshTransport>) -> Result<(Arc<SshTransport>, Channel), String> {
+        let (host, port, mut credentials, jump_host_id) = {
+            let state = app.state::<SshState>();
+            let sessions = state.sessions.lock().unwrap();
+            let conn = sessions.get(&self.id).ok_or("SSH session not found")?;
+            (conn.host.clone(), conn.port, conn.credentials.clone(), conn.jump_host_id)
+        };
+
+        let forward_agent = saved_server(app, credentials.server_id)?.is_some_and(|s| s.forward_agent);
+        let transport = SshTransport::connect(app, &host, port, &mut credentials, jump_host_id).await?;
+        let (channel, forwarding) = open_terminal(&transport, forward_agent, self.size).await?;
+
+        {
+            let state = app.state::<SshState>();
+            let mut sessions = state.sessions.lock().unwrap();
+            match sessions.get_mut(&self.id) {
+                Some(conn) if Arc::ptr_eq(&conn.transport, lost) => {
+                    conn.transport = transport.clone();
+                    conn.credentials = credentials;
+                }
+                _ => {
+                    transport.close();
+                    return Err("SSH session was closed".to_string());
+                }
             }
         }
-        println!("SSH thread exiting for session: {}", id_clone);
-        let _ = window.emit(&format!("ssh_close_{}", id_clone), ());
-    });
 
-    Ok(())
+        for rule in app.state::<ForwardState>().take_interrupted(&self.id) {
+            if let Err(e) = start_forward(app, &self.id, transport.clone(), rule).await {
+                eprintln!("Failed to restart port forward for session {}: {}", self.id, e);
+            }
+        }
+        if forwarding {
+            tauri::async_runtime::spawn(agent_forward::serve(self.window.clone(), self.id.clone(), transport.clone()));
+        }
+        Ok((transport, channel))
+    }
 }
 
 #[tauri::command]
@@ -526,7 +404,8 @@ pub fn resize_ssh(
     cols: u32,
     rows: u32,
 ) -> Result<(), String> {
-    if let Some(conn) = state.sessions.lock().unwrap().get(&id) {
+    if let Some(conn) = state.sessions.lock().unwrap().get_mut(&id) {
+        conn.size = (cols, rows);
         if let Some(tx) = &conn.resizer {
             let _ = tx.send((cols, rows));
         }
@@ -540,52 +419,44 @@ pub fn disconnect_ssh(
     id: String,
 ) -> Result<(), String> {
     println!("Disconnecting SSH session: {}", id);
-    let mut sessions = state.sessions.lock().unwrap();
-    println!("Sessions before removal: {:?}", sessions.keys().collect::<Vec<_>>());
-    sessions.remove(&id);
-    println!("Sessions after removal: {:?}", sessions.keys().collect::<Vec<_>>());
-    // The thread will eventually exit when channels are dropped or read fails?
-    // Actually, dropping the sender will cause try_recv to fail with Disconnected.
-    // We should handle that in the loop.
+    let conn = state.sessions.lock().unwrap().remove(&id);
+    // Closing the transport ends the terminal thread and every other
+    // channel (SFTP, forwards, exec) opened on it
+    if let Some(conn) = conn {
+        conn.transport.close();
+    }
     Ok(())
 }
 
 #[tauri::command]
-pub fn read_remote_file(
+pub async fn read_remote_file(
     state: tauri::State<'_, SshState>,
     id: String,
     path: String,
 ) -> Result<String, String> {
-    if let Some(conn) = state.sessions.lock().unwrap().get(&id) {
-        if let Some(sftp_sess) = &conn.sftp_session {
-            // Use dedicated SFTP session (already in blocking mode)
-            let sftp = sftp_sess.sftp().map_err(|e| e.to_string())?;
-            let mut file = sftp.open(std::path::Path::new(&path)).map_err(|e| e.to_string())?;
-            let mut content = String::new();
-            file.read_to_string(&mut content).map_err(|e| e.to_string())?;
-            return Ok(content);
-        }
-    }
-    Err("Session not found or SFTP unavailable".to_string())
+    let transport = state.transport(&id)?;
+    let sftp = transport.sftp().await?;
+    let mut file = transport.call(|| sftp.open(std::path::Path::new(&path))).await.map_err(|e| e.to_string())?;
+
+    let mut content = Vec::new();
+    transport.read_to_end(&mut file, &mut content).await.map_err(|e| e.to_string())?;
+    let _ = transport.call(|| file.close()).await;
+    String::from_utf8(content).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn write_remote_file(
+pub async fn write_remote_file(
     state: tauri::State<'_, SshState>,
     id: String,
     path: String,
     content: String,
 ) -> Result<(), String> {
-    if let Some(conn) = state.sessions.lock().unwrap().get(&id) {
-        if let Some(sftp_sess) = &conn.sftp_session {
-            // Use dedicated SFTP session (already in blocking mode)
-            let sftp = sftp_sess.sftp().map_err(|e| e.to_string())?;
-            let mut file = sftp.create(std::path::Path::new(&path)).map_err(|e| e.to_string())?;
-            file.write_all(content.as_bytes()).map_err(|e| e.to_string())?;
-            return Ok(());
-        }
-    }
-    Err("Session not found or SFTP unavailable".to_string())
+    let transport = state.transport(&id)?;
+    let sftp = transport.sftp().await?;
+    let mut file = transport.call(|| sftp.create(std::path::Path::new(&path))).await.map_err(|e| e.to_string())?;
+
+    transport.write_all(&mut file, content.as_bytes()).await.map_err(|e| e.to_string())?;
+    transport.call(|| file.close()).await.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -593,7 +464,6 @@ pub async fn duplicate_session(
     window: Window,
     state: tauri::State<'_, SshState>,
     local_state: tauri::State<'_, crate::local_term::LocalState>,
-    db: tauri::State<'_, Database>,
     source_id: String,
     new_id: String,
 ) -> Result<(), String> {
@@ -605,26 +475,25 @@ pub async fn duplicate_session(
         sessions.get(&source_id).map(|session| (
             session.host.clone(),
             session.port,
-            session.username.clone(),
-            session.password.clone(),
-            session.private_key.clone(),
+            session.credentials.clone(),
             session.jump_host_id,
         ))
     };
 
-    if let Some((host, port, username, password, private_key, jump_host_id)) = ssh_params {
+    if let Some((host, port, credentials, jump_host_id)) = ssh_params {
         return connect_ssh(
             window,
             state,
-            db,
             new_id,
             host,
             port,
-            username,
-            password,
-            private_key,
+            credentials.username,
+            credentials.password,
+            credentials.private_key,
             None,
-            jump_host_id
+            jump_host_id,
+            credentials.server_id,
+            credentials.passphrase,
         ).await;
     }
 
@@ -0,0 +1,291 @@
+use std::path::{Path, PathBuf};
+use crate::known_hosts::wildcard_match;
+use crate::ssh_utils::expand_home;
+
+// Same nesting limit OpenSSH applies to Include
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// A `Host` block, or the implicit block of options before the first one
+struct Block {
+    patterns: Vec<String>,
+    options: Vec<(String, Vec<String>)>,
+}
+
+/// A parsed OpenSSH client config with its `Include`s inlined
+pub struct SshConfig {
+    blocks: Vec<Block>,
+    pub warnings: Vec<String>,
+}
+
+/// Options that apply to one host alias, in the order ssh would see them
+pub struct HostConfig {
+    pub alias: String,
+    options: Vec<(String, Vec<String>)>,
+}
+
+impl HostConfig {
+    /// First value given for `key` (keys are case-insensitive); like ssh,
+    /// the first occurrence wins
+    pub fn get(&self, key: &str) -> Option<&str> {
+        let key = key.to_lowercase();
+        self.options
+            .iter()
+            .find(|(k, _)| *k == key)
+            .and_then(|(_, args)| args.first().map(String::as_str))
+    }
+
+    /// Every occurrence of a key that may be repeated (IdentityFile,
+    /// LocalForward, ...)
+    pub fn all(&self, key: &str) -> Vec<&[String]> {
+        let key = key.to_lowercase();
+        self.options
+            .iter()
+            .filter(|(k, _)| *k == key)
+            .map(|(_, args)| args.as_slice())
+            .collect()
+    }
+}
+
+impl SshConfig {
+    /// Parse the config at `path`; relative `Include`s resolve against ~/.ssh
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let include_dir = dirs::home_dir()
+            .map(|home| home.join(".ssh"))
+            .unwrap_or_else(|| path.parent().map(Path::to_path_buf).unwrap_or_default());
+        Ok(Self::parse(&text, &include_dir))
+    }
+
+    pub fn parse(text: &str, include_dir: &Path) -> Self {
+        let mut config = SshConfig {
+            blocks: vec![Block { patterns: vec!["*".to_string()], options: Vec::new() }],
+            warnings: Vec::new(),
+        };
+        config.parse_text(text, include_dir, 0);
+        config
+    }
+
+    fn parse_text(&mut self, text: &str, include_dir: &Path, depth: usize) {
+        for line in text.lines() {
+            let Some((key, args)) = split_line(line) else {
+                continue;
+            };
+
+            match key.as_str() {
+                "host" => self.blocks.push(Block { patterns: args, options: Vec::new() }),
+                "match" => {
+                    // Conditional blocks can't be evaluated offline; their
+                    // options must not leak into other hosts
+                    self.warnings.push(format!("Skipped unsupported 'Match {}' block", args.join(" ")));
+                    self.blocks.push(Block { patterns: Vec::new(), options: Vec::new() });
+                }
+                "include" => {
+                    if depth >= MAX_INCLUDE_DEPTH {
+                        self.warnings.push("Include nested too deeply, ignoring".to_string());
+                        continue;
+                    }
+                    let patterns = self.blocks.last().map(|b| b.patterns.clone()).unwrap_or_default();
+                    for arg in &args {
+                        for path in expand_include(arg, include_dir) {
+                            match std::fs::read_to_string(&path) {
+                                Ok(text) => self.parse_text(&text, include_dir, depth + 1),
+                                Err(e) => self.warnings.push(format!("Failed to read {}: {}", path.display(), e)),
+                            }
+                        }
+                    }
+                    // Lines after the Include still belong to the enclosing block
+                    self.blocks.push(Block { patterns, options: Vec::new() });
+                }
+                _ => {
+                    if let Some(block) = self.blocks.last_mut() {
+                        block.options.push((key, args));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Concrete aliases named on `Host` lines, skipping patterns and negations
+    pub fn aliases(&self) -> Vec<String> {
+        let mut aliases: Vec<String> = Vec::new();
+        for pattern in self.blocks.iter().flat_map(|b| &b.patterns) {
+            let concrete = !pattern.contains(['*', '?', '!']);
+            if concrete && !aliases.contains(pattern) {
+                aliases.push(pattern.clone());
+            }
+        }
+        aliases
+    }
+
+    pub fn resolve(&self, alias: &str) -> HostConfig {
+        let options = self
+            .blocks
+            .iter()
+            .filter(|block| host_matches(&block.patterns, alias))
+            .flat_map(|block| block.options.iter().cloned())
+            .collect();
+        HostConfig { alias: alias.to_string(), options }
+    }
+}
+
+fn host_matches(patterns: &[String], alias: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns {
+        match pattern.strip_prefix('!') {
+            Some(negated) if wildcard_match(negated, alias) => return false,
+            Some(_) => {}
+            None => matched |= wildcard_match(pattern, alias),
+        }
+    }
+    matched
+}
+
+/// Paths named by an Include argument; the file name may contain wildcards
+fn expand_include(arg: &str, include_dir: &Path) -> Vec<PathBuf> {
+    let path = expand_home(arg);
+    let path = if path.is_absolute() { path } else { include_dir.join(path) };
+
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    if !file_name.contains(['*', '?']) {
+        return vec![path];
+    }
+
+    let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let mut matches: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|p| p.is_file())
+                .filter(|p| {
+                    p.file_name()
+                        .is_some_and(|name| wildcard_match(&file_name, &name.to_string_lossy()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    matches.sort();
+    matches
+}
+
+/// Split a config line into a lowercased keyword and its arguments.
+/// Supports `Key value`, `Key=value`, double quotes and trailing comments.
+fn split_line(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let key_end = line.find(|c: char| c.is_whitespace() || c == '=').unwrap_or(line.len());
+    let key = line[..key_end].to_lowercase();
+    let mut rest = line[key_end..].trim_start();
+    if let Some(after_equals) = rest.strip_prefix('=') {
+        rest = after_equals.trim_start();
+    }
+
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut quoted = false;
+    for c in rest.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_token = true;
+            }
+            '#' if !quoted && !in_token => break,
+            c if c.is_whitespace() && !quoted => {
+                if in_token {
+                    args.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+    if in_token {
+        args.push(current);
+    }
+
+    Some((key, args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+# Defaults for the lab
+User admin
+
+Host web db
+    HostName %h.lab.internal
+    LocalForward 8080 localhost:80
+
+Host db
+    Port=2222
+    IdentityFile "~/.ssh/db key"
+
+Host *.example.com !skip.example.com
+    User deploy
+
+Host *
+    User ignored
+    Port 22
+"#;
+
+    #[test]
+    fn test_split_line() {
+        assert_eq!(split_line("  # comment"), None);
+        assert_eq!(
+            split_line("Port=2222").unwrap(),
+            ("port".to_string(), vec!["2222".to_string()])
+        );
+        assert_eq!(
+            split_line(r#"IdentityFile "~/my key" # trailing"#).unwrap(),
+            ("identityfile".to_string(), vec!["~/my key".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_resolve_first_value_wins() {
+        let config = SshConfig::parse(CONFIG, Path::new("/nonexistent"));
+        assert_eq!(config.aliases(), vec!["web", "db"]);
+
+        let db = config.resolve("db");
+        assert_eq!(db.get("HostName"), Some("%h.lab.internal"));
+        assert_eq!(db.get("Port"), Some("2222"));
+        assert_eq!(db.get("User"), Some("admin"));
+        assert_eq!(db.get("IdentityFile"), Some("~/.ssh/db key"));
+        assert_eq!(db.all("LocalForward").len(), 1);
+
+        assert_eq!(config.resolve("web").get("Port"), Some("22"));
+    }
+
+    #[test]
+    fn test_wildcards_and_negation() {
+        let config = SshConfig::parse("Host *.example.com !skip.example.com\n  User deploy\n", Path::new("/"));
+        assert_eq!(config.resolve("app.example.com").get("User"), Some("deploy"));
+        assert_eq!(config.resolve("skip.example.com").get("User"), None);
+    }
+
+    #[test]
+    fn test_include_and_match() {
+        let dir = std::env::temp_dir().join(format!("nebula-ssh-config-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("conf.d")).unwrap();
+        std::fs::write(dir.join("conf.d/10-bastion.conf"), "Host bastion\n  HostName 203.0.113.5\n").unwrap();
+
+        let text = "Include conf.d/*.conf\nMatch exec \"true\"\n  User nobody\nHost app\n  ProxyJump bastion\n";
+        let config = SshConfig::parse(text, &dir);
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(config.aliases(), vec!["bastion", "app"]);
+        assert_eq!(config.resolve("bastion").get("HostName"), Some("203.0.113.5"));
+        assert_eq!(config.resolve("app").get("User"), None);
+        assert_eq!(config.warnings.len(), 1);
+    }
+}
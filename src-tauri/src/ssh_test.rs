@@ -1,38 +1,33 @@
-use ssh2::Session;
-use std::net::TcpStream;
-use std::time::Duration;
+use crate::ssh_utils::Credentials;
+use crate::transport::SshTransport;
 
 #[tauri::command]
 pub async fn test_ssh_connection(
+    app: tauri::AppHandle,
     host: String,
     port: u16,
     username: String,
     password: Option<String>,
+    private_key: Option<String>,
+    passphrase: Option<String>,
+    jump_host_id: Option<i64>,
+    server_id: Option<i64>, // Saved server whose stored secrets fill in blanks
 ) -> Result<String, String> {
-    // Attempt to connect
-    let addr = format!("{}:{}", host, port);
-    let tcp = TcpStream::connect_timeout(
-        &addr.parse().map_err(|e| format!("Invalid address: {}", e))?,
-        Duration::from_secs(5),
-    )
-    .map_err(|e| format!("Connection failed: {}", e))?;
+    // Attempt to connect (through the same jump host route a session would use)
+    // and authenticate
+    let mut credentials = Credentials {
+        username,
+        password,
+        private_key,
+        passphrase,
+        server_id,
+        certificate: None,
+        cancel: None,
+    };
+    let transport = SshTransport::connect(&app, &host, port, &mut credentials, jump_host_id)
+        .await
+        .map_err(|e| format!("Connection failed: {}", e))?;
+    transport.close();
 
-    let mut sess = Session::new().map_err(|e| format!("Failed to create session: {}", e))?;
-    sess.set_tcp_stream(tcp);
-    sess.handshake()
-        .map_err(|e| format!("SSH handshake failed: {}", e))?;
-
-    // Try authentication
-    if let Some(pass) = password {
-        sess.userauth_password(&username, &pass)
-            .map_err(|e| format!("Authentication failed: {}", e))?;
-    } else {
-        return Err("Password is required for testing".to_string());
-    }
-
-    if sess.authenticated() {
-        Ok("Connection successful!".to_string())
-    } else {
-        Err("Authentication failed".to_string())
-    }
+    Ok("Connection successful!".to_string())
 }
@@ -0,0 +1,603 @@
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use ssh2::{BlockDirections, Channel, ErrorCode, Listener, MethodType, Session, Sftp};
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, Interest};
+use tokio::net::TcpListener;
+use tokio::sync::{watch, OnceCell};
+use crate::db::Database;
+use crate::certificates::{certified_key_algorithms, fetch_host_certificate, HostCertificateCache};
+use crate::known_hosts::{cert_authorities_for, verify_host_key};
+use crate::routing::{connect_route, resolve_route};
+use crate::ssh_utils::{authenticate, Credentials};
+
+// libssh2 returns LIBSSH2_ERROR_EAGAIN when a non-blocking call would block
+const LIBSSH2_ERROR_EAGAIN: i32 = -37;
+const LIBSSH2_ERROR_SOCKET_DISCONNECT: i32 = -13;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const KEEPALIVE_INTERVAL: u32 = 30;
+// Keepalive replies that may go missing before the connection counts as dead
+const KEEPALIVE_MAX_MISSED: u32 = 3;
+// Safety net: a waiter re-checks at least this often even without a wakeup
+const WAIT_FALLBACK: Duration = Duration::from_millis(500);
+// Upper bound for the rare calls made with the session switched to blocking
+const BLOCKING_TIMEOUT_MS: u32 = 5000;
+
+pub fn would_block(e: &ssh2::Error) -> bool {
+    e.code() == ErrorCode::Session(LIBSSH2_ERROR_EAGAIN)
+}
+
+fn closed_error() -> ssh2::Error {
+    ssh2::Error::new(ErrorCode::Session(LIBSSH2_ERROR_SOCKET_DISCONNECT), "Connection closed")
+}
+
+/// One authenticated SSH connection shared by everything that talks to a
+/// server from a tab: the terminal, SFTP, the monitor and exec callers each
+/// open their own channel on it instead of logging in again.
+///
+/// The session runs in non-blocking mode and is driven from the tokio
+/// runtime: a call that would block waits for the socket to become ready
+/// (in the direction libssh2 reports) instead of sleeping and polling.
+/// Because any caller may pull packets for other channels off the socket,
+/// callers that drained it bump `progress` so the rest re-check their
+/// channels.
+pub struct SshTransport {
+    session: Session,
+    // The session's socket registered with the reactor; libssh2 does the
+    // actual reads and writes on the same file descriptor
+    socket: tokio::net::TcpStream,
+    progress: watch::Sender<u64>,
+    // Held around every libssh2 call, so switching to blocking mode can
+    // never catch another caller mid-operation
+    io_lock: Mutex<()>,
+    sftp: OnceCell<Sftp>,
+    closed: AtomicBool,
+    // Last time the socket had data for us
+    last_received: Mutex<Instant>,
+    // Jump host this transport is tunnelled through, kept alive while in use
+    #[allow(dead_code)]
+    via: Option<Arc<SshTransport>>,
+    pub host: String,
+    pub port: u16,
+}
+
+impl SshTransport {
+    /// Open, verify and authenticate a connection to `host:port`, routed
+    /// through the chain of jump hosts starting at `jump_host_id`.
+    pub async fn connect(
+        app: &AppHandle,
+        host: &str,
+        port: u16,
+        credentials: &mut Credentials,
+        jump_host_id: Option<i64>,
+    ) -> Result<Arc<Self>, String> {
+        let route = {
+            let db = app.state::<Database>();
+            resolve_route(&db, credentials.server_id, jump_host_id)?
+        };
+        let via = connect_route(app, &route, credentials.cancel.as_ref()).await?;
+        Self::connect_via(app, host, port, credentials, via).await
+    }
+
+    /// Connect to `host:port` directly, or tunnelled through `via`
+    pub async fn connect_via(
+        app: &AppHandle,
+        host: &str,
+        port: u16,
+        credentials: &mut Credentials,
+        via: Option<Arc<SshTransport>>,
+    ) -> Result<Arc<Self>, String> {
+        // Hosts covered by an @cert-authority entry may prove themselves
+        // with a host certificate, fetched over a separate connection the
+        // first time
+        let certificates = app.state::<HostCertificateCache>();
+        let host_certificate = if cert_authorities_for(app, host, port)?.is_empty() {
+            None
+        } else if let Some(cached) = certificates.get(host, port) {
+            cached
+        } else {
+            match open_tcp(via.as_ref(), host, port).await {
+                Ok(probe) => {
+                    let probed = tauri::async_runtime::spawn_blocking(move || fetch_host_certificate(probe))
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    certificates.insert(host, port, probed.clone());
+                    probed
+                }
+                Err(_) => None,
+            }
+        };
+
+        let tcp = open_tcp(via.as_ref(), host, port).await?;
+        tcp.set_nodelay(true).map_err(|e| e.to_string())?;
+        let socket = tcp.try_clone().map_err(|e| e.to_string())?;
+
+        // The handshake and authentication block (and may wait on prompts
+        // answered by the user), so keep them off the async workers
+        let handshake_app = app.clone();
+        let handshake_host = host.to_string();
+        let mut handshake_credentials = credentials.clone();
+        let (session, authenticated) = tauri::async_runtime::spawn_blocking(move || {
+            let mut session = Session::new().map_err(|e| e.to_string())?;
+            session.set_tcp_stream(tcp);
+            if let Some(cert) = &host_certificate {
+                // Make the server present the key its certificate vouches for
+                session
+                    .method_pref(MethodType::HostKey, &certified_key_algorithms(cert))
+                    .map_err(|e| e.to_string())?;
+            }
+            let verified = session
+                .handshake()
+                .map_err(|e| format!("SSH handshake failed: {}", e))
+                .and_then(|_| {
+                    // The caller may have given up while the handshake ran; an
+                    // unknown host would otherwise still ask about its key
+                    if handshake_credentials.cancelled() {
+                        return Err("Connection cancelled".to_string());
+                    }
+                    verify_host_key(&handshake_app, &session, &handshake_host, port, host_certificate.as_ref())
+                });
+            if verified.is_err() {
+                // The host may have changed its certificate since it was probed
+                handshake_app.state::<HostCertificateCache>().forget(&handshake_host, port);
+            }
+            verified?;
+            authenticate(&handshake_app, &session, &mut handshake_credentials)?;
+            Ok::<_, String>((session, handshake_credentials))
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+        *credentials = authenticated;
+
+        session.set_keepalive(true, KEEPALIVE_INTERVAL);
+        // Every user shares this session, so it must never block
+        session.set_blocking(false);
+        socket.set_nonblocking(true).map_err(|e| e.to_string())?;
+        let socket = tokio::net::TcpStream::from_std(socket).map_err(|e| e.to_string())?;
+
+        println!("SSH transport established to {}:{}", host, port);
+        let (progress, _) = watch::channel(0);
+        let transport = Arc::new(Self {
+            session,
+            socket,
+            progress,
+            io_lock: Mutex::new(()),
+            sftp: OnceCell::new(),
+            closed: AtomicBool::new(false),
+            last_received: Mutex::new(Instant::now()),
+            via,
+            host: host.to_string(),
+            port,
+        });
+
+        tauri::async_runtime::spawn(keepalive(Arc::downgrade(&transport)));
+        Ok(transport)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Disconnect the session. Channels still held by other users start
+    /// failing and their tasks exit. Jump hosts may be shared with other
+    /// routes, so they are left to close once their last user is gone.
+    pub fn close(&self) {
+        if self.closed.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        println!("Closing SSH transport to {}:{}", self.host, self.port);
+
+        let _ = self.session.disconnect(None, "Disconnected by user", None);
+        self.notify_progress();
+    }
+
+    /// Give up on a connection that stopped working. Unlike `close` nothing
+    /// is sent, as the peer is most likely gone.
+    pub fn abort(&self, reason: &str) {
+        if self.closed.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        eprintln!("SSH transport to {}:{} lost: {}", self.host, self.port, reason);
+        self.notify_progress();
+    }
+
+    /// The underlying libssh2 session, for the few calls ssh2 does not wrap.
+    /// Only use it inside `attempt`/`call`, like any other session call.
+    pub fn raw_session(&self) -> *mut libssh2_sys::LIBSSH2_SESSION {
+        let mut raw = self.session.raw();
+        &mut *raw
+    }
+
+    /// Resolves once the transport has been closed
+    pub async fn closed(&self) {
+        let mut progress = self.subscribe();
+        while !self.is_closed() {
+            if progress.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Take a progress snapshot; subscribe *before* attempting an operation
+    /// so a wakeup that happens while it runs is not missed.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.progress.subscribe()
+    }
+
+    fn notify_progress(&self) {
+        self.progress.send_modify(|generation| *generation = generation.wrapping_add(1));
+    }
+
+    /// Run one non-blocking libssh2 operation
+    pub fn attempt<T>(&self, op: impl FnOnce() -> T) -> T {
+        // Clear readiness before touching the socket, so data that arrives
+        // while `op` runs raises a fresh readiness event
+        let mut drained = false;
+        let _ = self.socket.try_io(Interest::READABLE, || {
+            drained = true;
+            Err::<(), _>(io::ErrorKind::WouldBlock.into())
+        });
+        let _ = self.socket.try_io(Interest::WRITABLE, || Err::<(), _>(io::ErrorKind::WouldBlock.into()));
+
+        let result = {
+            let _io = self.io_lock.lock().unwrap();
+            op()
+        };
+        if drained {
+            *self.last_received.lock().unwrap() = Instant::now();
+            // libssh2 may have buffered packets for other channels
+            self.notify_progress();
+        }
+        result
+    }
+
+    /// Run `op` with the session briefly in blocking mode, for libssh2 calls
+    /// that cannot be retried, such as the forward cancel a `Listener` sends
+    /// when dropped.
+    pub fn blocking<T>(&self, op: impl FnOnce() -> T) -> T {
+        let _io = self.io_lock.lock().unwrap();
+        self.session.set_timeout(BLOCKING_TIMEOUT_MS);
+        self.session.set_blocking(true);
+        let result = op();
+        self.session.set_blocking(false);
+        self.session.set_timeout(0);
+        result
+    }
+
+    /// Wait until an operation that would block is worth retrying
+    pub async fn wait(&self, progress: &mut watch::Receiver<u64>) {
+        if self.is_closed() {
+            return;
+        }
+        let interest = match self.session.block_directions() {
+            BlockDirections::Outbound => Interest::WRITABLE,
+            BlockDirections::Both => Interest::READABLE | Interest::WRITABLE,
+            _ => Interest::READABLE,
+        };
+
+        tokio::select! {
+            _ = self.socket.ready(interest) => {}
+            _ = progress.changed() => {}
+            _ = tokio::time::sleep(WAIT_FALLBACK) => {}
+        }
+    }
+
+    /// Run a libssh2 call to completion, waiting while it would block
+    pub async fn call<T>(&self, mut op: impl FnMut() -> Result<T, ssh2::Error>) -> Result<T, ssh2::Error> {
+        loop {
+            if self.is_closed() {
+                return Err(closed_error());
+            }
+            let mut progress = self.subscribe();
+            match self.attempt(&mut op) {
+                Err(e) if would_block(&e) => self.wait(&mut progress).await,
+                result => return result,
+            }
+        }
+    }
+
+    /// Read from a channel or SFTP file, waiting until data is available
+    pub async fn read(&self, reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.is_closed() {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "Connection closed"));
+            }
+            let mut progress = self.subscribe();
+            match self.attempt(|| reader.read(buf)) {
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => self.wait(&mut progress).await,
+                result => return result,
+            }
+        }
+    }
+
+    /// Read until EOF into `out`
+    pub async fn read_to_end(&self, reader: &mut impl Read, out: &mut Vec<u8>) -> io::Result<()> {
+        let mut buf = vec![0u8; 32768];
+        loop {
+            match self.read(reader, &mut buf).await? {
+                0 => return Ok(()),
+                n => out.extend_from_slice(&buf[..n]),
+            }
+        }
+    }
+
+    /// Write all of `buf` to a channel or SFTP file
+    pub async fn write_all(&self, writer: &mut impl Write, mut buf: &[u8]) -> io::Result<()> {
+        while !buf.is_empty() {
+            if self.is_closed() {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "Connection closed"));
+            }
+            let mut progress = self.subscribe();
+            match self.attempt(|| writer.write(buf)) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => buf = &buf[n..],
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => self.wait(&mut progress).await,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn channel_session(&self) -> Result<Channel, String> {
+        self.call(|| self.session.channel_session()).await.map_err(|e| e.to_string())
+    }
+
+    pub async fn channel_direct_tcpip(&self, host: &str, port: u16) -> Result<Channel, String> {
+        self.call(|| self.session.channel_direct_tcpip(host, port, None))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Ask the server to listen on `host:port` and hand connections back as
+    /// channels (ssh -R). Returns the listener and the port actually bound.
+    pub async fn forward_listen(&self, host: &str, port: u16) -> Result<(Listener, u16), String> {
+        self.call(|| self.session.channel_forward_listen(port, Some(host), None))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Stop a remote forward, telling the server to cancel it
+    pub fn cancel_listener(&self, listener: Listener) {
+        if self.is_closed() {
+            return;
+        }
+        self.blocking(|| drop(listener));
+    }
+
+    /// Open an interactive shell channel with a PTY of the given size. With
+    /// `forward_agent` the server is asked to forward our agent through it;
+    /// the flag returned says whether it agreed.
+    pub async fn open_shell(&self, term: &str, cols: u32, rows: u32, forward_agent: bool) -> Result<(Channel, bool), String> {
+        let mut channel = self.channel_session().await?;
+        let mut forwarding = false;
+        if forward_agent {
+            match self.call(|| channel.request_auth_agent_forwarding()).await {
+                Ok(()) => forwarding = true,
+                // Servers may refuse (AllowAgentForwarding no); the shell still works
+                Err(e) => eprintln!("Agent forwarding refused by {}:{}: {}", self.host, self.port, e),
+            }
+        }
+        self.call(|| channel.request_pty(term, None, Some((cols, rows, 0, 0))))
+            .await
+            .map_err(|e| e.to_string())?;
+        self.call(|| channel.shell()).await.map_err(|e| e.to_string())?;
+        Ok((channel, forwarding))
+    }
+
+    /// Run `command` on its own channel and collect stdout, giving up on
+    /// output after `timeout`.
+    pub async fn exec(&self, command: &str, timeout: Duration) -> Result<String, String> {
+        let mut channel = self.channel_session().await?;
+        self.call(|| channel.exec(command)).await.map_err(|e| e.to_string())?;
+
+        let mut output = Vec::new();
+        let read = self.read_to_end(&mut channel, &mut output);
+        if let Ok(result) = tokio::time::timeout(timeout, read).await {
+            result.map_err(|e| e.to_string())?;
+        }
+
+        let _ = self.call(|| channel.close()).await;
+        Ok(String::from_utf8_lossy(&output).into_owned())
+    }
+
+    /// This connection's SFTP subsystem, opened on first use
+    pub async fn sftp(&self) -> Result<&Sftp, String> {
+        self.sftp
+            .get_or_try_init(|| async {
+                self.call(|| self.session.sftp()).await.map_err(|e| e.to_string())
+            })
+            .await
+    }
+
+    /// Expose `host:port` (as seen from this server) on a local loopback
+    /// port and return a stream connected to it. Only our own connection is
+    /// accepted, and the port is closed again right after.
+    async fn tunnel(self: &Arc<Self>, host: &str, port: u16) -> Result<TcpStream, String> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.map_err(|e| e.to_string())?;
+        let local_addr = listener.local_addr().map_err(|e| e.to_string())?;
+        println!("Tunnelling {}:{} through {}:{} via {}", host, port, self.host, self.port, local_addr);
+
+        let stream = tokio::net::TcpStream::connect(local_addr).await.map_err(|e| e.to_string())?;
+        let ours = stream.local_addr().map_err(|e| e.to_string())?;
+        let accept = async {
+            loop {
+                let (local_stream, peer) = listener.accept().await?;
+                if peer == ours {
+                    return Ok::<_, io::Error>(local_stream);
+                }
+                eprintln!("Rejected connection from {} to the tunnel for {}:{}", peer, host, port);
+            }
+        };
+        let local_stream = tokio::time::timeout(CONNECT_TIMEOUT, accept)
+            .await
+            .map_err(|_| "Timed out setting up the tunnel".to_string())?
+            .map_err(|e| e.to_string())?;
+        drop(listener);
+
+        let channel = self.channel_direct_tcpip(host, port).await?;
+        let hop = self.clone();
+        tauri::async_runtime::spawn(async move {
+            hop.pump(channel, local_stream, &Traffic::default()).await;
+        });
+
+        let stream = stream.into_std().map_err(|e| e.to_string())?;
+        // libssh2 drives this end in blocking mode during the handshake
+        stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+        Ok(stream)
+    }
+
+    /// Copy data both ways between a channel and a local socket until the
+    /// remote side closes or either side fails, counting bytes in `traffic`.
+    pub async fn pump(&self, mut channel: Channel, mut local_stream: tokio::net::TcpStream, traffic: &Traffic) {
+        let (mut local_reader, mut local_writer) = local_stream.split();
+        let mut buf_local = vec![0u8; 16384];
+        let mut buf_remote = vec![0u8; 16384];
+        let mut local_open = true;
+
+        while !self.is_closed() {
+            // Remote -> Local, as long as the channel has data buffered
+            let mut progress = self.subscribe();
+            match self.attempt(|| channel.read(&mut buf_remote)) {
+                Ok(0) => break, // EOF
+                Ok(n) => {
+                    if local_writer.write_all(&buf_remote[..n]).await.is_err() {
+                        break;
+                    }
+                    traffic.add_received(n);
+                    continue;
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(_) => break,
+            }
+
+            tokio::select! {
+                // Local -> Remote
+                result = local_reader.read(&mut buf_local), if local_open => match result {
+                    Ok(0) | Err(_) => {
+                        // Half close: the remote end may still be sending
+                        local_open = false;
+                        let _ = self.call(|| channel.send_eof()).await;
+                    }
+                    Ok(n) => {
+                        if self.write_all(&mut channel, &buf_local[..n]).await.is_err() {
+                            break;
+                        }
+                        traffic.add_sent(n);
+                    }
+                },
+                _ = self.wait(&mut progress) => {}
+            }
+        }
+
+        let _ = local_writer.shutdown().await;
+        let _ = self.call(|| channel.close()).await;
+    }
+}
+
+/// Bytes moved through a pumped connection. Counts also roll up into the
+/// parent, so a forward can report totals across its connections.
+#[derive(Default)]
+pub struct Traffic {
+    sent: AtomicU64,
+    received: AtomicU64,
+    parent: Option<Arc<Traffic>>,
+}
+
+impl Traffic {
+    pub fn child_of(parent: &Arc<Traffic>) -> Self {
+        Self {
+            parent: Some(parent.clone()),
+            ..Default::default()
+        }
+    }
+
+    /// Bytes sent to the remote side
+    pub fn sent(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    /// Bytes received from the remote side
+    pub fn received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    fn add_sent(&self, n: usize) {
+        self.sent.fetch_add(n as u64, Ordering::Relaxed);
+        if let Some(parent) = &self.parent {
+            parent.add_sent(n);
+        }
+    }
+
+    fn add_received(&self, n: usize) {
+        self.received.fetch_add(n as u64, Ordering::Relaxed);
+        if let Some(parent) = &self.parent {
+            parent.add_received(n);
+        }
+    }
+}
+
+impl Drop for SshTransport {
+    // Shared jump hosts are only released here, when the last route using them is gone
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Send keepalives, and abort the transport once the server has been silent
+/// for several keepalive intervals or a keepalive cannot be sent
+async fn keepalive(transport: Weak<SshTransport>) {
+    let silence_limit = Duration::from_secs((KEEPALIVE_INTERVAL * KEEPALIVE_MAX_MISSED) as u64);
+    let mut interval = KEEPALIVE_INTERVAL as u64;
+    loop {
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+        let Some(transport) = transport.upgrade() else {
+            return;
+        };
+        if transport.is_closed() {
+            return;
+        }
+        let silent = transport.last_received.lock().unwrap().elapsed();
+        if silent > silence_limit {
+            transport.abort(&format!("no response for {} seconds", silent.as_secs()));
+            return;
+        }
+        match transport.call(|| transport.session.keepalive_send()).await {
+            Ok(next) => interval = next.max(1) as u64,
+            Err(e) => {
+                transport.abort(&format!("keepalive failed: {}", e));
+                return;
+            }
+        }
+    }
+}
+
+/// A TCP stream to `host:port`, directly or tunnelled through `via`
+async fn open_tcp(via: Option<&Arc<SshTransport>>, host: &str, port: u16) -> Result<TcpStream, String> {
+    match via {
+        Some(hop) => hop.tunnel(host, port).await,
+        None => {
+            let target = host.to_string();
+            tauri::async_runtime::spawn_blocking(move || connect_tcp(&target, port))
+                .await
+                .map_err(|e| e.to_string())?
+        }
+    }
+}
+
+fn connect_tcp(host: &str, port: u16) -> Result<TcpStream, String> {
+    let addrs = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Failed to resolve {}: {}", host, e))?;
+
+    let mut last_error = format!("No addresses found for {}", host);
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = format!("Connection to {} failed: {}", addr, e),
+        }
+    }
+    Err(last_error)
+}
@@ -0,0 +1,92 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::State;
+use crate::db::Database;
+use crate::repositories::credentials;
+
+// How long a master-password confirmation unlocks reveal/copy
+const CONFIRMATION_WINDOW: Duration = Duration::from_secs(120);
+// The UI clears a copied secret from the clipboard after this long
+const CLIPBOARD_CLEAR_SECS: u64 = 30;
+
+/// When the user last re-entered the master password
+pub struct VaultState {
+    confirmed_at: Mutex<Option<Instant>>,
+}
+
+impl VaultState {
+    pub fn new() -> Self {
+        Self {
+            confirmed_at: Mutex::new(None),
+        }
+    }
+
+    /// Drop any confirmation, e.g. when the app locks
+    pub fn forget(&self) {
+        *self.confirmed_at.lock().unwrap() = None;
+    }
+
+    pub(crate) fn require_confirmation(&self) -> Result<(), String> {
+        match *self.confirmed_at.lock().unwrap() {
+            Some(at) if at.elapsed() < CONFIRMATION_WINDOW => Ok(()),
+            _ => Err("Confirm the master password to access stored secrets".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CopiedSecret {
+    pub value: String,
+    pub clear_after_secs: u64,
+}
+
+/// Re-enter the master password before revealing or copying secrets
+#[tauri::command]
+pub fn confirm_master_password(vault: State<VaultState>, password: String) -> Result<(), String> {
+    if !crate::security::verify_master_password(password)? {
+        return Err("Incorrect password".to_string());
+    }
+    *vault.confirmed_at.lock().unwrap() = Some(Instant::now());
+    Ok(())
+}
+
+fn read_secret(db: &Database, server_id: i64, field: &str) -> Result<Option<String>, String> {
+    let credential = credentials::get_server_credential(db, server_id).map_err(|e| e.to_string())?;
+    match field {
+        "password" => Ok(credential.and_then(|c| c.password)),
+        "key_passphrase" => Ok(credential.and_then(|c| c.key_passphrase)),
+        other => Err(format!("Unknown secret field: {}", other)),
+    }
+}
+
+/// Show a stored secret ("password" or "key_passphrase") of a server
+#[tauri::command]
+pub fn reveal_credential(
+    db: State<Database>,
+    vault: State<VaultState>,
+    server_id: i64,
+    field: String,
+) -> Result<Option<String>, String> {
+    vault.require_confirmation()?;
+    println!("Revealing {} of server {}", field, server_id);
+    read_secret(&db, server_id, &field)
+}
+
+/// Hand a stored secret to the UI for the clipboard, with the delay after
+/// which it should be cleared again
+#[tauri::command]
+pub fn copy_credential(
+    db: State<Database>,
+    vault: State<VaultState>,
+    server_id: i64,
+    field: String,
+) -> Result<CopiedSecret, String> {
+    vault.require_confirmation()?;
+    println!("Copying {} of server {}", field, server_id);
+    let value = read_secret(&db, server_id, &field)?.ok_or("No secret stored")?;
+    Ok(CopiedSecret {
+        value,
+        clear_after_secs: CLIPBOARD_CLEAR_SECS,
+    })
+}
@@ -5,11 +5,18 @@ export interface Server {
   host?: string;
   port?: number;
   username?: string;
-  password?: string;
+  password?: string; // Only sent when saving; '' removes the saved one
+  has_password?: boolean;
+  has_key_passphrase?: boolean;
   privateKeyPath?: string;
+  private_key_path?: string | null;
+  key_id?: number | null; // Key from the key store, used when no key path is set
   localForwards?: PortForward[];
   remoteForwards?: PortForward[];
+  forwarding_rules?: PortForwardingRule[];
   jumpHostId?: string | null;
+  forward_agent?: boolean; // Forward our SSH agent to this server (ssh -A)
+  record_sessions?: boolean; // Record every session to an asciicast file
   group?: string;
   tags?: string[];
   color?: string;
@@ -17,6 +24,226 @@ export interface Server {
   updated_at?: number;
 }
 
+// A key pair in the built-in key store
+export interface SshKey {
+  id?: number;
+  name: string;
+  key_type: string;
+  bits?: number;
+  fingerprint: string;
+  public_key: string; // authorized_keys line
+  encrypted: boolean;
+  comment?: string;
+  created_at: number;
+  updated_at: number;
+}
+
+// Raised when connecting to a host whose key is not in known_hosts yet
+export interface HostKeyPromptRequest {
+  request_id: string;
+  host: string;
+  port: number;
+  key_type: string;
+  fingerprint: string;
+}
+
+// Raised when an encrypted private key has no stored passphrase
+export interface PassphrasePromptRequest {
+  request_id: string;
+  username: string;
+  key: string;
+  retry: boolean;
+  can_remember: boolean;
+}
+
+// Keyboard-interactive challenges sent by the server during login
+export interface KeyboardInteractiveRequest {
+  request_id: string;
+  username: string;
+  instructions: string;
+  prompts: { text: string; echo: boolean }[];
+}
+
+// Raised when a key added to the agent with confirmation is asked to sign
+export interface AgentConfirmRequest {
+  request_id: string;
+  key_name: string;
+  fingerprint: string;
+  client: string; // Who wants the signature
+}
+
+// A key unlocked in the built-in SSH agent
+export interface AgentKeyInfo {
+  key_id: number;
+  name: string;
+  key_type: string;
+  fingerprint: string;
+  confirm: boolean;
+  expires_in_secs?: number | null;
+}
+
+export interface AgentStatus {
+  socket_path: string | null; // null when the socket isn't served
+  keys: AgentKeyInfo[];
+}
+
+// OpenSSH certificate details, as returned by get_server_certificate
+export interface CertificateInfo {
+  key_id: string;
+  serial: number;
+  cert_type: 'user' | 'host';
+  principals: string[];
+  valid_after: number; // Unix seconds
+  valid_before: number;
+  ca_fingerprint: string;
+  expired: boolean;
+  expires_soon: boolean;
+}
+
+// A session recording in progress (recording_status_{id})
+export interface RecordingInfo {
+  session_id: string;
+  path: string;
+  started_at: number; // Unix seconds
+  record_input: boolean;
+}
+
+// asciicast v2 header
+export interface CastHeader {
+  version: number;
+  width: number;
+  height: number;
+  timestamp?: number;
+  idle_time_limit?: number;
+  title?: string;
+}
+
+export interface RecordingFile {
+  path: string;
+  name: string;
+  size: number;
+  modified: number; // Unix seconds
+  header: CastHeader;
+}
+
+// Payload of playback_event_{id}
+export interface PlaybackEvent {
+  time: number;
+  kind: 'o' | 'r' | 'm';
+  data: string;
+}
+
+// Plain-text session logs (get/set_session_log_settings)
+export interface SessionLogSettings {
+  enabled: boolean;
+  directory?: string | null; // Defaults to <app data>/logs/sessions
+  directory_pattern: string; // {server}, {host} and {date} are replaced
+  timestamps: boolean;
+  strip_ansi: boolean;
+  max_file_size_mb: number; // 0 for no limit
+  retention_days: number; // 0 to keep forever
+}
+
+// Payload of ssh_reconnecting_{id} while the backend retries a lost connection
+export interface ReconnectStatus {
+  attempt: number;
+  max_attempts: number;
+  delay_ms: number;
+  error: string;
+}
+
+export interface ExecOptions {
+  stdin?: string;
+  env?: Record<string, string>;
+  timeout_secs?: number;
+  pty?: boolean;
+}
+
+// Result of exec_command; output arrives as exec_stdout_{id} / exec_stderr_{id} events
+export interface ExecResult {
+  exit_status: number | null;
+  exit_signal: string | null;
+  error_message: string | null;
+  timed_out: boolean;
+  cancelled: boolean;
+  duration_ms: number;
+}
+
+// Which saved servers a fan-out run targets; every criterion given must match
+export interface ServerSelector {
+  group?: string;
+  tags?: string[]; // Any of these
+  server_ids?: number[];
+}
+
+export interface FanoutRun {
+  id: number;
+  command: string;
+  selector: ServerSelector;
+  parallelism: number;
+  timeout_secs: number | null;
+  started_at: number;
+  finished_at: number | null;
+  total: number;
+  succeeded: number;
+  failed: number;
+}
+
+export interface FanoutHostResult {
+  server_id: number;
+  server_name: string;
+  host: string;
+  exit_status: number | null;
+  exit_signal: string | null;
+  error: string | null;
+  timed_out: boolean;
+  cancelled: boolean;
+  duration_ms: number;
+  output: string;
+  truncated: boolean;
+}
+
+export interface FanoutRunDetail {
+  run: FanoutRun;
+  results: FanoutHostResult[];
+}
+
+export interface PortForwardingRule {
+  id: string;
+  rule_type: 'Local' | 'Remote' | 'Dynamic';
+  source_port: number;
+  destination_host?: string;
+  destination_port?: number;
+  bind_address?: string; // Listening address; loopback when unset
+}
+
+// A running forward (port_forward_status)
+export interface ForwardStatus {
+  session_id: string;
+  rule_id: string;
+  rule_type: string;
+  state: 'starting' | 'listening' | 'stopped' | 'error';
+  bind_address: string;
+  active_connections: number;
+  total_connections: number;
+  bytes_in: number;
+  bytes_out: number;
+  error?: string | null;
+}
+
+// One connection through a forward (port_forward_connection)
+export interface ForwardConnection {
+  session_id: string;
+  rule_id: string;
+  connection_id: number;
+  peer: string; // Empty when unknown, as for remote forwards
+  target: string;
+  state: 'open' | 'closed';
+  opened_at: number; // Unix seconds
+  bytes_in: number;
+  bytes_out: number;
+}
+
 export interface PortForward {
   localPort: number;
   remoteHost: string;
@@ -126,6 +353,7 @@ export interface AppSettings {
   ai_base_url?: string;
   auto_reconnect: boolean;
   lock_timeout: number; // Minutes, 0 to disable
+  agent_for_local_terminals?: boolean; // Expose the built-in SSH agent to local terminals
 }
 
 // Session Persistence Types
@@ -152,3 +380,31 @@ export interface PaneState {
   active_view: 'terminal' | 'sftp' | 'monitor' | 'editor';
   editor_file: string | null;
 }
+
+// A field an ssh_config import would change on an existing server
+export interface FieldChange {
+  field: string;
+  old?: string | null;
+  new?: string | null;
+}
+
+export interface ImportEntry {
+  alias: string;
+  action: 'create' | 'update' | 'unchanged';
+  existing_id?: number | null;
+  server: Server;
+  jump_host?: string | null;
+  changes: FieldChange[];
+}
+
+export interface ImportPreview {
+  entries: ImportEntry[];
+  warnings: string[];
+}
+
+export interface ImportSummary {
+  created: number;
+  updated: number;
+  unchanged: number;
+  warnings: string[];
+}